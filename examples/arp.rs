@@ -18,6 +18,7 @@ use cortex_m::interrupt::Mutex;
 use stm32_eth::{
     mac::{phy::BarePhy, Phy},
     stm32::{interrupt, CorePeripherals, Peripherals, SYST},
+    util::EthernetFrame,
     Parts,
 };
 
@@ -82,7 +83,7 @@ fn main() -> ! {
 
             const DST_MAC: [u8; 6] = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
             const SRC_MAC: [u8; 6] = [0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
-            const ETH_TYPE: [u8; 2] = [0x08, 0x06]; // ARP
+            const ETH_TYPE: u16 = 0x0806; // ARP
             const HTYPE: [u8; 2] = [0x00, 0x01]; // Hardware Type: ethernet
             const PTYPE: [u8; 2] = [0x08, 0x00]; // IP
             const HLEN: [u8; 1] = [0x06]; // MAC length
@@ -93,20 +94,25 @@ fn main() -> ! {
             const TARGET_IP: [u8; 4] = [0x0A, 0x00, 0x00, 0x02]; // 10.0.0.2
 
             let r = dma.send(SIZE, None, |buf| {
-                buf[0..6].copy_from_slice(&DST_MAC);
-                buf[6..12].copy_from_slice(&SRC_MAC);
-                buf[12..14].copy_from_slice(&ETH_TYPE);
-                buf[14..16].copy_from_slice(&HTYPE);
-
-                buf[16..18].copy_from_slice(&PTYPE);
-                buf[18..19].copy_from_slice(&HLEN);
-                buf[19..20].copy_from_slice(&PLEN);
-                buf[20..22].copy_from_slice(&OPER);
-                buf[22..28].copy_from_slice(&SRC_MAC);
-                buf[28..32].copy_from_slice(&SENDER_IP);
-
-                buf[32..38].copy_from_slice(&TARGET_MAC);
-                buf[38..42].copy_from_slice(&TARGET_IP);
+                // NOTE(unwrap): the frame is longer than an Ethernet header.
+                let mut frame = EthernetFrame::new(buf).unwrap();
+                frame
+                    .set_destination(DST_MAC)
+                    .set_source(SRC_MAC)
+                    .set_ethertype(ETH_TYPE);
+
+                let buf = frame.into_payload();
+                buf[0..2].copy_from_slice(&HTYPE);
+
+                buf[2..4].copy_from_slice(&PTYPE);
+                buf[4..5].copy_from_slice(&HLEN);
+                buf[5..6].copy_from_slice(&PLEN);
+                buf[6..8].copy_from_slice(&OPER);
+                buf[8..14].copy_from_slice(&SRC_MAC);
+                buf[14..18].copy_from_slice(&SENDER_IP);
+
+                buf[18..24].copy_from_slice(&TARGET_MAC);
+                buf[24..28].copy_from_slice(&TARGET_IP);
             });
 
             match r {
@@ -114,6 +120,8 @@ fn main() -> ! {
                     defmt::info!("ARP sent");
                 }
                 Err(TxError::WouldBlock) => defmt::info!("ARP failed"),
+                Err(TxError::Corruption) => defmt::error!("TX descriptor corrupted"),
+                Err(TxError::FrameTooLong) => defmt::error!("ARP frame too long"),
             }
         } else {
             defmt::info!("Down");
@@ -48,11 +48,11 @@ mod app {
 
     use rtic_sync::arbiter::Arbiter;
 
-    use ieee802_3_miim::{phy::PhySpeed, Phy};
+    use ieee802_3_miim::Phy;
 
     use stm32_eth::{
         dma::{EthernetDMA, PacketId, RxRing, RxRingEntry, TxRing, TxRingEntry},
-        mac::Speed,
+        mac::EthernetMAC,
         ptp::{EthernetPTP, Subseconds, Timestamp},
         Parts,
     };
@@ -101,9 +101,6 @@ mod app {
         defmt::info!("Enabling interrupts");
         dma.enable_interrupt();
 
-        let (rx, tx) = dma.split();
-        let (do_tx_send, do_tx_recv) = cx.local.tx_channel.split();
-
         match EthernetPhy::from_miim(mac, 0) {
             Ok(mut phy) => {
                 defmt::info!(
@@ -119,13 +116,7 @@ mod app {
 
                 defmt::info!("Link up.");
 
-                if let Some(speed) = phy.speed().map(|s| match s {
-                    PhySpeed::HalfDuplexBase10T => Speed::HalfDuplexBase10T,
-                    PhySpeed::FullDuplexBase10T => Speed::FullDuplexBase10T,
-                    PhySpeed::HalfDuplexBase100Tx => Speed::HalfDuplexBase100Tx,
-                    PhySpeed::FullDuplexBase100Tx => Speed::FullDuplexBase100Tx,
-                }) {
-                    phy.get_miim().set_speed(speed);
+                if let Some(speed) = EthernetMAC::establish_link(&mut phy, dma) {
                     defmt::info!("Detected link speed: {}", speed);
                 } else {
                     defmt::warn!("Failed to detect link speed.");
@@ -136,6 +127,9 @@ mod app {
             }
         };
 
+        let (rx, tx) = dma.split();
+        let (do_tx_send, do_tx_recv) = cx.local.tx_channel.split();
+
         sender::spawn(tx, do_tx_recv).ok();
         receiver::spawn(rx, arbiter).ok();
         ptp_scheduler::spawn(arbiter, do_tx_send).ok();
@@ -187,13 +181,13 @@ mod app {
                     defmt::debug!("Addend correction value: {}", p1);
 
                     if diff.is_negative() {
-                        ptp.set_addend(addend - p1 / 2);
+                        ptp.set_addend(addend - p1 / 2).unwrap();
                     } else {
-                        ptp.set_addend(addend + p1 / 2);
+                        ptp.set_addend(addend + p1 / 2).unwrap();
                     };
                 } else {
                     defmt::warn!("Updated time.");
-                    ptp.update_time(diff);
+                    ptp.update_time(diff).unwrap();
                 }
                 drop(ptp);
             }
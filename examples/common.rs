@@ -293,7 +293,7 @@ mod pins {
 use ieee802_3_miim::{
     phy::{
         lan87xxa::{LAN8720A, LAN8742A},
-        BarePhy, KSZ8081R,
+        BarePhy, PhyWithSpeed, KSZ8081R,
     },
     Miim, Pause, Phy,
 };
@@ -334,6 +334,12 @@ impl<M: Miim> Phy<M> for EthernetPhy<M> {
     }
 }
 
+impl<M: Miim> PhyWithSpeed<M> for EthernetPhy<M> {
+    fn get_link_speed(&mut self) -> Option<ieee802_3_miim::phy::AdvancedPhySpeed> {
+        self.speed().map(Into::into)
+    }
+}
+
 impl<M: Miim> EthernetPhy<M> {
     /// Attempt to create one of the known PHYs from the given
     /// MIIM.
@@ -0,0 +1,234 @@
+//! For build and run instructions, see README.md
+//!
+//! An example that responds to pings, using only the DMA API.
+//!
+//! It answers ARP requests and ICMP echo requests for `10.0.0.10`,
+//! so the device can be pinged from a host that is in `10.0.0.0/24`:
+//!
+//! ```text
+//! ping 10.0.0.10
+//! ```
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+use core::default::Default;
+use cortex_m_rt::entry;
+
+use stm32_eth::{
+    mac::{phy::BarePhy, Phy},
+    stm32::Peripherals,
+    Parts,
+};
+
+use stm32_eth::dma::{RxRingEntry, TxError, TxRingEntry};
+
+pub mod common;
+
+const PHY_ADDR: u8 = 0;
+
+const MAC_ADDR: [u8; 6] = [0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
+const IP_ADDR: [u8; 4] = [10, 0, 0, 10];
+
+const ETH_TYPE_ARP: [u8; 2] = [0x08, 0x06];
+const ETH_TYPE_IPV4: [u8; 2] = [0x08, 0x00];
+
+const ARP_HEADER: [u8; 6] = [
+    0x00, 0x01, // Hardware Type: ethernet
+    0x08, 0x00, // Protocol Type: IPv4
+    0x06, // MAC length
+    0x04, // IPv4 address length
+];
+const ARP_OPER_REQUEST: [u8; 2] = [0x00, 0x01];
+const ARP_OPER_REPLY: [u8; 2] = [0x00, 0x02];
+
+const IP_PROTOCOL_ICMP: u8 = 1;
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+
+#[entry]
+fn main() -> ! {
+    let p = Peripherals::take().unwrap();
+
+    let (clocks, gpio, ethernet) = common::setup_peripherals(p);
+
+    defmt::info!("Enabling ethernet...");
+
+    let (eth_pins, mdio, mdc, _) = common::setup_pins(gpio);
+
+    let mut rx_ring: [RxRingEntry; 4] = Default::default();
+    let mut tx_ring: [TxRingEntry; 4] = Default::default();
+
+    let Parts {
+        mut dma,
+        mac,
+        #[cfg(feature = "ptp")]
+            ptp: _,
+    } = stm32_eth::new(
+        ethernet,
+        &mut rx_ring[..],
+        &mut tx_ring[..],
+        clocks,
+        eth_pins,
+    )
+    .unwrap();
+
+    let mut last_link_up = false;
+
+    let mut phy = BarePhy::new(mac.with_mii(mdio, mdc), PHY_ADDR, Default::default());
+
+    defmt::info!("Answering pings at {}", IP_ADDR);
+
+    loop {
+        let link_up = phy.phy_link_up();
+
+        if link_up != last_link_up {
+            if link_up {
+                defmt::info!("Ethernet: link detected");
+            } else {
+                defmt::info!("Ethernet: no link detected");
+            }
+            last_link_up = link_up;
+        }
+
+        let (rx, tx) = dma.split();
+
+        let request = match rx.recv_next(None) {
+            Ok(request) => request,
+            Err(_) => continue,
+        };
+
+        let kind = match Request::parse(&request) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        // The reply is exactly as long as the request in both cases.
+        let mut reply = match tx.send_next(request.len(), None) {
+            Ok(reply) => reply,
+            Err(TxError::WouldBlock) => {
+                defmt::warn!("No TX descriptor available, dropping {}", kind);
+                continue;
+            }
+            Err(TxError::Corruption) => {
+                defmt::error!("TX descriptor corrupted, dropping {}", kind);
+                continue;
+            }
+            Err(TxError::FrameTooLong) => {
+                defmt::error!("Reply too long, dropping {}", kind);
+                continue;
+            }
+        };
+
+        reply.copy_from_slice(&request);
+        request.free();
+
+        // Reply to the sender of the request
+        let (dst, src) = reply.split_at_mut(6);
+        dst.copy_from_slice(&src[..6]);
+        src[..6].copy_from_slice(&MAC_ADDR);
+
+        match kind {
+            Request::Arp => {
+                reply[20..22].copy_from_slice(&ARP_OPER_REPLY);
+
+                // The sender of the request becomes the target of the reply
+                let (sender, target) = reply[22..42].split_at_mut(10);
+                target.copy_from_slice(sender);
+
+                reply[22..28].copy_from_slice(&MAC_ADDR);
+                reply[28..32].copy_from_slice(&IP_ADDR);
+            }
+            Request::IcmpEcho {
+                icmp_start,
+                icmp_end,
+            } => {
+                // Swap the IP addresses. This does not change the IP header checksum.
+                let (src, dst) = reply[26..34].split_at_mut(4);
+                src.swap_with_slice(dst);
+
+                let icmp = &mut reply[icmp_start..icmp_end];
+                icmp[0] = ICMP_ECHO_REPLY;
+                icmp[2..4].copy_from_slice(&[0, 0]);
+                let checksum = checksum(icmp);
+                icmp[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+                // We have already calculated the checksum ourselves, and by
+                // default no checksums are inserted by the hardware.
+            }
+        }
+
+        reply.send();
+
+        defmt::info!("Replied to {}", kind);
+    }
+}
+
+/// A request that we reply to.
+#[derive(defmt::Format, Clone, Copy)]
+enum Request {
+    /// An ARP request for our IP address.
+    Arp,
+    /// An ICMP echo request sent to our IP address. The ICMP message
+    /// is located at `frame[icmp_start..icmp_end]`.
+    IcmpEcho { icmp_start: usize, icmp_end: usize },
+}
+
+impl Request {
+    fn parse(frame: &[u8]) -> Option<Self> {
+        if frame.len() < 14 {
+            return None;
+        }
+
+        let ethertype = &frame[12..14];
+
+        if ethertype == ETH_TYPE_ARP {
+            let is_request = frame.len() >= 42
+                && frame[14..20] == ARP_HEADER
+                && frame[20..22] == ARP_OPER_REQUEST
+                && frame[38..42] == IP_ADDR;
+
+            is_request.then_some(Self::Arp)
+        } else if ethertype == ETH_TYPE_IPV4 {
+            if frame.len() < 34 || frame[14] >> 4 != 4 {
+                return None;
+            }
+
+            let header_len = ((frame[14] & 0x0F) as usize) * 4;
+            let total_len = u16::from_be_bytes([frame[16], frame[17]]) as usize;
+
+            let icmp_start = 14 + header_len;
+            let icmp_end = 14 + total_len;
+
+            let is_request = header_len >= 20
+                && icmp_end <= frame.len()
+                && icmp_start + 8 <= icmp_end
+                && frame[23] == IP_PROTOCOL_ICMP
+                && frame[30..34] == IP_ADDR
+                && frame[icmp_start] == ICMP_ECHO_REQUEST;
+
+            is_request.then_some(Self::IcmpEcho {
+                icmp_start,
+                icmp_end,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Calculate the internet checksum (RFC 1071) of `data`.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)]) as u32)
+        .sum::<u32>();
+
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
@@ -143,6 +143,14 @@ fn main() -> ! {
                         tx_pkts += 1;
                     }
                     Err(TxError::WouldBlock) => break 'egress,
+                    Err(TxError::Corruption) => {
+                        defmt::error!("TX descriptor corrupted");
+                        break 'egress;
+                    }
+                    Err(TxError::FrameTooLong) => {
+                        defmt::error!("Frame too long");
+                        break 'egress;
+                    }
                 }
             }
         }
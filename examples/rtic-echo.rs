@@ -23,12 +23,12 @@ mod app {
 
     use crate::common::EthernetPhy;
 
-    use ieee802_3_miim::{phy::PhySpeed, Phy};
+    use ieee802_3_miim::Phy;
     use systick_monotonic::Systick;
 
     use stm32_eth::{
         dma::{EthernetDMA, RxRingEntry, TxRingEntry},
-        mac::Speed,
+        mac::EthernetMAC,
         Parts,
     };
 
@@ -139,13 +139,7 @@ mod app {
 
             defmt::info!("Link up.");
 
-            if let Some(speed) = phy.speed().map(|s| match s {
-                PhySpeed::HalfDuplexBase10T => Speed::HalfDuplexBase10T,
-                PhySpeed::FullDuplexBase10T => Speed::FullDuplexBase10T,
-                PhySpeed::HalfDuplexBase100Tx => Speed::HalfDuplexBase100Tx,
-                PhySpeed::FullDuplexBase100Tx => Speed::FullDuplexBase100Tx,
-            }) {
-                phy.get_miim().set_speed(speed);
+            if let Some(speed) = EthernetMAC::establish_link(&mut phy, &mut dma) {
                 defmt::info!("Detected link speed: {}", speed);
             } else {
                 defmt::warn!("Failed to detect link speed.");
@@ -248,13 +248,13 @@ mod app {
                                 defmt::debug!("Addend correction value: {}", p1);
 
                                 if diff.is_negative() {
-                                    ptp.set_addend(addend - p1 / 2);
+                                    ptp.set_addend(addend - p1 / 2).unwrap();
                                 } else {
-                                    ptp.set_addend(addend + p1 / 2);
+                                    ptp.set_addend(addend + p1 / 2).unwrap();
                                 };
                             } else {
                                 defmt::warn!("Updated time.");
-                                ptp.update_time(diff);
+                                ptp.update_time(diff).unwrap();
                             }
                         }
 
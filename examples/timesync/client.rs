@@ -312,7 +312,7 @@ mod app {
             *addend_integrator = 0.0;
             defmt::info!("Updating time. Offset {} ", offset);
             let updated_time = now + offset;
-            ptp.set_time(updated_time);
+            ptp.set_time(updated_time).unwrap();
         } else {
             let mut offset_nanos = offset.nanos() as i64;
             if offset.is_negative() {
@@ -338,7 +338,7 @@ mod app {
             );
 
             let new_addend = (start_addend as i64 + error / 4 + (*addend_integrator as i64)) as u32;
-            ptp.set_addend(new_addend);
+            ptp.set_addend(new_addend).unwrap();
         }
 
         defmt::debug!(
@@ -0,0 +1,295 @@
+//! Benchmarks of the latency of the DMA and the MAC.
+
+use core::task::Poll;
+
+use crate::{
+    mac::EthernetMAC,
+    ptp::{EthernetPTP, Timestamp},
+};
+
+use super::{EthernetDMA, RxError, TxError, DMA_TIMEOUT_ITERATIONS};
+
+/// The EtherType of the frames sent by [`loopback_rtt`] (IEEE 802
+/// local experimental EtherType 1).
+const ETHERTYPE: [u8; 2] = [0x88, 0xB5];
+
+/// The length of the frames sent by [`loopback_rtt`], excluding the CRC.
+const FRAME_LEN: usize = 60;
+
+/// The minimum, average and maximum of a latency, in nanoseconds.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyStats {
+    /// The smallest measured latency.
+    pub min_nanos: u64,
+    /// The average of all measured latencies.
+    pub avg_nanos: u64,
+    /// The largest measured latency.
+    pub max_nanos: u64,
+}
+
+/// Accumulates latency samples.
+struct Accumulator {
+    min: u64,
+    max: u64,
+    sum: u64,
+    count: u64,
+}
+
+impl Accumulator {
+    const fn new() -> Self {
+        Self {
+            min: u64::MAX,
+            max: 0,
+            sum: 0,
+            count: 0,
+        }
+    }
+
+    fn add(&mut self, nanos: u64) {
+        self.min = self.min.min(nanos);
+        self.max = self.max.max(nanos);
+        self.sum += nanos;
+        self.count += 1;
+    }
+
+    fn stats(&self) -> LatencyStats {
+        LatencyStats {
+            min_nanos: self.min,
+            avg_nanos: self.sum / self.count,
+            max_nanos: self.max,
+        }
+    }
+}
+
+/// The result of [`loopback_rtt`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopbackRtt {
+    /// The time between handing a frame to the DMA and the MAC starting
+    /// its transmission (egress timestamp).
+    pub tx_latency: LatencyStats,
+    /// The time between handing a frame to the DMA and the MAC receiving
+    /// it again (ingress timestamp).
+    pub round_trip: LatencyStats,
+}
+
+/// The result of [`stream_frame`].
+#[cfg(feature = "test-modes")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamStats {
+    /// The amount of frames that were transmitted.
+    pub frames: u32,
+    /// The time it took to transmit all frames, in nanoseconds.
+    pub nanos: u64,
+    /// The achieved frame rate, in frames per second.
+    pub frames_per_second: u64,
+}
+
+/// Errors that can occur while running [`loopback_rtt`] or [`stream_frame`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq)]
+pub enum BenchError {
+    /// No TX descriptor was available.
+    Tx(TxError),
+    /// Receiving the looped back frame failed.
+    Rx(RxError),
+    /// The frame was not transmitted or not received within
+    /// [`DMA_TIMEOUT_ITERATIONS`].
+    Timeout,
+    /// The DMA did not provide a timestamp for the frame.
+    MissingTimestamp,
+}
+
+/// Measure the latency of the DMA, the FIFOs and the MAC by sending
+/// `iterations` frames through the MAC loopback.
+///
+/// The MAC loopback is enabled while this function runs, and restored to its
+/// previous state afterwards. Frames in the RX ring that were received before
+/// this function was called are dropped.
+///
+/// For every frame, the PTP time is read just before it is handed to the
+/// DMA. This is compared to the egress and ingress timestamps captured by the
+/// MAC, so the measured latency covers fetching the descriptor and the frame,
+/// filling the TX FIFO, the transmission of the frame up to its start of frame
+/// delimiter, and (for the round trip) the reception of the start of frame
+/// delimiter through the loopback. It does not include the time required to
+/// write the received frame to memory.
+///
+/// The results are affected by:
+/// - The resolution of the PTP clock (see [`EthernetPTP::init`]). Every
+///   measurement may be off by up to two subsecond increments.
+/// - The time required to read the PTP time and to hand the frame to the DMA.
+/// - Contention on the bus matrix with the CPU and other DMA masters.
+/// - The arbitration between the RX and TX DMA.
+/// - The TX store-and-forward mode, which delays the transmission until the
+///   complete frame is stored in the TX FIFO.
+/// - The speed of the MAC, which determines how long it takes to transmit the
+///   preamble.
+///
+/// The loopback uses the clocks provided by the PHY, so a PHY must be connected
+/// (but no link is required). On the STM32F107, the MAC may not capture
+/// ingress timestamps for frames that are not PTP messages, in which case
+/// [`BenchError::MissingTimestamp`] is returned.
+///
+/// # Panics
+/// This function panics if `iterations` is zero.
+pub fn loopback_rtt(
+    dma: &mut EthernetDMA,
+    mac: &mut EthernetMAC,
+    iterations: u32,
+) -> Result<LoopbackRtt, BenchError> {
+    assert!(iterations > 0, "At least one iteration is required");
+
+    let loopback = mac.loopback();
+    mac.set_loopback(true);
+
+    let mut tx_latency = Accumulator::new();
+    let mut round_trip = Accumulator::new();
+
+    let result = (0..iterations).try_for_each(|sequence| {
+        let (tx, rtt) = measure(dma, mac.address(), sequence)?;
+        tx_latency.add(tx);
+        round_trip.add(rtt);
+        Ok(())
+    });
+
+    mac.set_loopback(loopback);
+
+    result.map(|_| LoopbackRtt {
+        tx_latency: tx_latency.stats(),
+        round_trip: round_trip.stats(),
+    })
+}
+
+/// Transmit `frame` `count` times, as fast as the DMA allows, and measure
+/// the achieved frame rate.
+///
+/// Every free TX entry is filled with a copy of `frame` as soon as it becomes
+/// available, so the DMA transmits the frames back-to-back as long as copying
+/// a frame takes less time than transmitting one. Use
+/// [`EthernetMAC::set_minimum_interframe_gap`] to additionally reduce the gap
+/// between the frames. `frame` is sent as is, so it must contain the complete
+/// Ethernet header. The MAC pads it and appends the CRC.
+///
+/// The frame rate is measured using the PTP time: the time is read just before
+/// the first frame is handed to the DMA, and again once the DMA has finished
+/// with all frames, which is detected by polling the TX ring. The measured
+/// time therefore includes fetching the first frame and the delay of detecting
+/// the end of the transmission, which is negligible for large values of `count`.
+/// The resolution of the measurement is limited by the resolution of the PTP
+/// clock (see [`EthernetPTP::init`]).
+///
+/// Returns [`BenchError::Timeout`] if no TX entry becomes available, or the
+/// frames are not transmitted within [`DMA_TIMEOUT_ITERATIONS`].
+///
+/// # Panics
+/// This function panics if `count` is zero, or if `frame` does not fit into
+/// the TX buffers.
+#[cfg(feature = "test-modes")]
+pub fn stream_frame(
+    dma: &mut EthernetDMA,
+    frame: &[u8],
+    count: u32,
+) -> Result<StreamStats, BenchError> {
+    assert!(count > 0, "At least one frame must be sent");
+
+    let start = EthernetPTP::get_time();
+
+    for _ in 0..count {
+        wait(|| {
+            dma.tx_ring
+                .send_next(frame.len(), None)
+                .ok()
+                .map(|mut packet| {
+                    packet.copy_from_slice(frame);
+                    packet.send();
+                })
+        })?;
+    }
+
+    wait(|| dma.tx_ring.is_empty().then_some(()))?;
+
+    let nanos = (EthernetPTP::get_time() - start).total_nanos().max(1) as u64;
+
+    Ok(StreamStats {
+        frames: count,
+        nanos,
+        frames_per_second: (count as u64 * 1_000_000_000) / nanos,
+    })
+}
+
+/// Poll `f` until it returns `Some`, at most [`DMA_TIMEOUT_ITERATIONS`] times.
+fn wait<T>(mut f: impl FnMut() -> Option<T>) -> Result<T, BenchError> {
+    (0..DMA_TIMEOUT_ITERATIONS)
+        .find_map(|_| f())
+        .ok_or(BenchError::Timeout)
+}
+
+/// Send a single frame through the loopback, and return its TX latency
+/// and round trip time in nanoseconds.
+fn measure(
+    dma: &mut EthernetDMA,
+    address: [u8; 6],
+    sequence: u32,
+) -> Result<(u64, u64), BenchError> {
+    let (start, tx, rx) = loopback_frame(dma, address, sequence)?;
+
+    let nanos = |timestamp: Timestamp| (timestamp - start).total_nanos().max(0) as u64;
+
+    Ok((nanos(tx), nanos(rx)))
+}
+
+/// Send a single frame through the loopback, and return the PTP time at
+/// which it was handed to the DMA, its egress timestamp and its ingress
+/// timestamp.
+///
+/// The MAC loopback must be enabled.
+pub(crate) fn loopback_frame(
+    dma: &mut EthernetDMA,
+    address: [u8; 6],
+    sequence: u32,
+) -> Result<(Timestamp, Timestamp, Timestamp), BenchError> {
+    // Drop all frames that are already waiting in the RX ring.
+    while let Ok(packet) = dma.recv_next(None) {
+        packet.free();
+    }
+
+    let mut tag = [0u8; 6];
+    tag[..2].copy_from_slice(&ETHERTYPE);
+    tag[2..].copy_from_slice(&sequence.to_be_bytes());
+
+    let packet_id = dma.next_packet_id();
+
+    let mut frame = dma
+        .tx_ring
+        .send_next(FRAME_LEN, Some(packet_id.clone()))
+        .map_err(BenchError::Tx)?;
+
+    frame[..6].fill(0xFF);
+    frame[6..12].copy_from_slice(&address);
+    frame[12..18].copy_from_slice(&tag);
+    frame[18..].fill(0);
+
+    let start = EthernetPTP::get_time();
+    frame.send();
+
+    let tx = wait(|| match dma.poll_tx_timestamp(&packet_id) {
+        Poll::Ready(Ok(Some(timestamp))) => Some(Ok(timestamp)),
+        Poll::Ready(_) => Some(Err(BenchError::MissingTimestamp)),
+        Poll::Pending => None,
+    })??;
+
+    let rx = wait(|| match dma.recv_next(None) {
+        Ok(packet) => {
+            let timestamp = (packet.get(12..18) == Some(&tag)).then(|| packet.timestamp());
+            packet.free();
+            timestamp.map(|timestamp| timestamp.ok_or(BenchError::MissingTimestamp))
+        }
+        Err(RxError::WouldBlock) => None,
+        Err(e) => Some(Err(BenchError::Rx(e))),
+    })??;
+
+    Ok((start, tx, rx))
+}
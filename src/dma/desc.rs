@@ -3,12 +3,26 @@ use core::ops::{Deref, DerefMut};
 use aligned::{Aligned, A8};
 use volatile_register::{RO, RW};
 
+// The enhanced descriptor format, which is always used on these chips, has 8 words.
 #[cfg(not(feature = "stm32f1xx-hal"))]
 const DESC_SIZE: usize = 8;
 
-#[cfg(feature = "stm32f1xx-hal")]
+#[cfg(all(feature = "stm32f1xx-hal", not(feature = "descriptor-sentinel")))]
 const DESC_SIZE: usize = 4;
 
+// Normal descriptors have no reserved word, so we add one for the sentinel. The DMA
+// never accesses it, as all descriptors are chained.
+#[cfg(all(feature = "stm32f1xx-hal", feature = "descriptor-sentinel"))]
+const DESC_SIZE: usize = 5;
+
+/// The amount of words of a DMA descriptor, as returned by
+/// [`RxRing::recv_next_raw`](super::RxRing::recv_next_raw).
+///
+/// This is 8 on the STM32F4 and STM32F7, which use enhanced descriptors,
+/// and 4 on the STM32F107 (5 with the `descriptor-sentinel` feature).
+#[cfg(feature = "raw-descriptors")]
+pub const DESCRIPTOR_WORDS: usize = DESC_SIZE;
+
 #[repr(C)]
 pub struct Descriptor {
     pub(crate) desc: Aligned<A8, [u32; DESC_SIZE]>,
@@ -64,3 +78,40 @@ impl Descriptor {
         self.rw(n).modify(f)
     }
 }
+
+#[cfg(any(feature = "defmt", feature = "raw-descriptors"))]
+impl Descriptor {
+    /// Read all words of this descriptor.
+    pub fn words(&self) -> [u32; DESC_SIZE] {
+        let mut words = [0; DESC_SIZE];
+        words
+            .iter_mut()
+            .enumerate()
+            .for_each(|(n, word)| *word = self.read(n));
+        words
+    }
+}
+
+#[cfg(feature = "descriptor-sentinel")]
+impl Descriptor {
+    /// A word of the descriptor that is never written by the DMA.
+    ///
+    /// For enhanced descriptors, this is the reserved RDES5/TDES5 word.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    const SENTINEL_WORD: usize = 5;
+    #[cfg(feature = "stm32f1xx-hal")]
+    const SENTINEL_WORD: usize = 4;
+
+    const SENTINEL: u32 = 0x5E47_1E15;
+
+    /// Stamp the sentinel into this descriptor.
+    pub unsafe fn write_sentinel(&mut self) {
+        self.write(Self::SENTINEL_WORD, Self::SENTINEL);
+    }
+
+    /// Check whether the sentinel written by [`Descriptor::write_sentinel`]
+    /// is still intact.
+    pub fn sentinel_intact(&self) -> bool {
+        self.read(Self::SENTINEL_WORD) == Self::SENTINEL
+    }
+}
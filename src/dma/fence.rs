@@ -0,0 +1,119 @@
+//! Memory ordering between the CPU and the DMA engine.
+//!
+//! Descriptors and their buffers are shared with the DMA engine, which
+//! accesses memory independently of the CPU. All descriptor accesses are
+//! volatile, but buffer accesses are not, so explicit fences are required
+//! around every ownership handoff.
+//!
+//! Which fences are required depends on the core: Cortex-M3 and Cortex-M4
+//! (STM32F1 and STM32F4) do not reorder memory accesses in a way that is
+//! observable by the DMA, so only the compiler must be prevented from
+//! doing so. The Cortex-M7 (STM32F7) has a store buffer and may reorder
+//! accesses to normal memory, so a data memory barrier is also required.
+//! The latter is selected by the `fence` feature.
+//!
+//! The memory model is fixed at compile time, so only the one selected by
+//! the `fence` feature is compiled.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// The fences used when handing descriptors between the CPU and the DMA.
+pub(crate) trait MemoryModel {
+    /// Called before passing ownership of a descriptor to the DMA.
+    ///
+    /// "Preceding reads and writes cannot be moved past subsequent writes."
+    /// This ensures that the buffer contents and the descriptor fields are
+    /// visible to the DMA before it observes the OWN bit.
+    fn release();
+
+    /// Called after passing ownership of a descriptor to the DMA.
+    ///
+    /// This ensures that the write setting the OWN bit is ordered before
+    /// all subsequent memory accesses, most importantly the write to the
+    /// poll demand register that wakes up the DMA. This must order writes
+    /// against later writes _and_ reads, which no weaker ordering than
+    /// `SeqCst` provides.
+    fn publish();
+
+    /// Called after observing that the DMA has passed ownership of a
+    /// descriptor back to the CPU.
+    ///
+    /// "Subsequent reads and writes cannot be moved ahead of preceding reads."
+    /// This ensures that the buffer is not read before the descriptor status
+    /// that indicates that the DMA is done writing it.
+    fn acquire();
+}
+
+/// Only prevents the compiler from reordering memory accesses.
+///
+/// Sufficient for cores that do not reorder accesses themselves.
+#[cfg(not(feature = "fence"))]
+pub(crate) struct CompilerFence;
+
+#[cfg(not(feature = "fence"))]
+impl MemoryModel for CompilerFence {
+    #[inline(always)]
+    fn release() {
+        compiler_fence(Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn publish() {}
+
+    #[inline(always)]
+    fn acquire() {
+        compiler_fence(Ordering::Acquire);
+    }
+}
+
+/// Additionally emits a data memory barrier when handing ownership to
+/// the DMA.
+#[cfg(feature = "fence")]
+pub(crate) struct HardwareFence;
+
+#[cfg(feature = "fence")]
+impl MemoryModel for HardwareFence {
+    #[inline(always)]
+    fn release() {
+        core::sync::atomic::fence(Ordering::Release);
+        compiler_fence(Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn publish() {
+        // Used to flush the store buffer as fast as possible to make the buffer available for the
+        // DMA.
+        core::sync::atomic::fence(Ordering::SeqCst);
+    }
+
+    #[inline(always)]
+    fn acquire() {
+        compiler_fence(Ordering::Acquire);
+    }
+}
+
+/// The memory model used for the current target.
+#[cfg(not(feature = "fence"))]
+pub(crate) type Target = CompilerFence;
+
+/// The memory model used for the current target.
+#[cfg(feature = "fence")]
+pub(crate) type Target = HardwareFence;
+
+/// See [`MemoryModel::release`].
+#[inline(always)]
+pub(crate) fn release() {
+    Target::release()
+}
+
+/// See [`MemoryModel::publish`].
+#[inline(always)]
+pub(crate) fn publish() {
+    Target::publish()
+}
+
+/// See [`MemoryModel::acquire`].
+#[inline(always)]
+pub(crate) fn acquire() {
+    Target::acquire()
+}
@@ -2,7 +2,12 @@
 
 use cortex_m::peripheral::NVIC;
 
-use crate::{peripherals::ETHERNET_DMA, stm32::Interrupt};
+use crate::{
+    mac::{EthernetMAC, MmcCounters, Speed},
+    peripherals::ETHERNET_DMA,
+    stm32::Interrupt,
+    util::VlanTag,
+};
 
 #[cfg(feature = "smoltcp-phy")]
 mod smoltcp_phy;
@@ -12,25 +17,52 @@ pub use smoltcp_phy::*;
 #[cfg(feature = "async-await")]
 use futures::task::AtomicWaker;
 
-#[cfg(any(feature = "ptp", feature = "async-await"))]
 use core::task::Poll;
 
+#[cfg(feature = "ptp")]
+pub mod bench;
+
 pub(crate) mod desc;
+#[cfg(feature = "raw-descriptors")]
+pub use desc::DESCRIPTOR_WORDS;
+
+#[cfg(feature = "stm32f7xx-hal")]
+pub mod mpu;
+
+mod fence;
 
 pub(crate) mod ring;
 
 mod rx;
-pub use rx::{RunningState as RxRunningState, RxError, RxPacket, RxRing, RxRingEntry};
+pub use rx::{
+    BufferProvider, RegionBufferProvider, RegionLayoutError, RunningState as RxRunningState,
+    RxError, RxPacket, RxQueue, RxQueueOverflow, RxRing, RxRingEntry, CLASSIFIER_HEADER_LEN,
+    IP_HEADER_ALIGNMENT_PADDING,
+};
+#[cfg(not(feature = "stm32f1xx-hal"))]
+pub use rx::{ExtendedRxStatus, IpPayloadType, PtpMessageType};
+#[cfg(feature = "rx-size-histogram")]
+pub use rx::{SizeBucket, SizeHistogram};
 
 mod tx;
-pub use tx::{RunningState as TxRunningState, TxError, TxPacket, TxRing, TxRingEntry};
+pub use tx::{
+    ChecksumInsertion, CompletionSlotsFull, CrcPadControl, RunningState as TxRunningState,
+    TxBufferFull, TxError, TxFrameBuilder, TxPacket, TxRing, TxRingEntry, TxStatus,
+    TX_COMPLETION_SLOTS,
+};
 
 #[cfg(feature = "ptp")]
 use crate::ptp::Timestamp;
 
+#[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+use crate::ptp::EthernetPTP;
+
 mod packet_id;
 pub use packet_id::PacketId;
 
+mod shared;
+pub use shared::SharedDma;
+
 /// From the datasheet: *VLAN Frame maxsize = 1522*
 pub(crate) const MTU: usize = 1522;
 
@@ -40,6 +72,260 @@ pub(crate) const MTU: usize = 1522;
 /// with any TX or RX descriptors.
 pub struct PacketIdNotFound;
 
+/// The maximum number of times that the state of the DMA is polled
+/// while waiting for a control operation to complete.
+///
+/// Every poll performs at least one read of a DMA register, which takes
+/// several HCLK cycles, so this corresponds to at least a few milliseconds
+/// on all supported MCUs. Resetting the DMA completes within a few
+/// microseconds, and stopping it within the time required to transmit
+/// or receive a single frame.
+///
+/// The same limit applies to waiting for the MII to complete a transaction,
+/// and for the PTP clock to apply an update. If it is exceeded, a warning is
+/// emitted if the `defmt` feature is enabled.
+pub const DMA_TIMEOUT_ITERATIONS: u32 = 1_000_000;
+
+/// Errors that can occur while controlling the DMA as a whole,
+/// as opposed to errors that affect a single frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DmaError {
+    /// The software reset of the DMA did not complete within
+    /// [`DMA_TIMEOUT_ITERATIONS`].
+    ///
+    /// The reset only completes if all clocks of the Ethernet peripheral are
+    /// running, including the reference clock provided by the PHY.
+    ResetTimeout,
+    /// The RX or TX DMA did not stop within [`DMA_TIMEOUT_ITERATIONS`].
+    SuspendTimeout,
+    /// The DMA encountered a fatal bus error, and has disabled all of
+    /// its bus accesses. It must be reset before it can be used again.
+    FatalBusError,
+    /// Flushing the TX FIFO did not complete within [`DMA_TIMEOUT_ITERATIONS`].
+    FlushTimeout,
+    /// A [`BufferProvider`] provided a buffer that the DMA cannot access
+    /// (see [`is_dma_accessible`]).
+    InaccessibleBuffer,
+    /// A [`BufferProvider`] provided a buffer that is not aligned to 4 bytes.
+    MisalignedBuffer,
+}
+
+/// Check whether the DMA can access the `len` bytes at `address`.
+///
+/// The Ethernet DMA is a bus master that can only reach some of the memories
+/// of the MCU:
+///
+/// | Memory                       | STM32F107 | STM32F4 | STM32F7 |
+/// |------------------------------|-----------|---------|---------|
+/// | SRAM (`0x2000_0000`)         | yes       | yes     | yes     |
+/// | DTCM (`0x2000_0000`)         | -         | -       | yes     |
+/// | CCM (`0x1000_0000`)          | -         | no      | -       |
+/// | Flash (`0x0800_0000`)        | no        | no      | no      |
+/// | ITCM (`0x0000_0000`)         | -         | -       | no      |
+/// | FMC/FSMC (`0x6000_0000`)     | -         | yes     | yes     |
+///
+/// On the STM32F7, the DMA reaches the DTCM through the AHB slave port of
+/// the core. Buffers that are passed to the DMA without copying them (by
+/// [`TxRing::send_static`] and [`BufferProvider`]s) must be accessible. The
+/// copying APIs, such as [`RxRing::recv_into`], accept buffers in any memory.
+pub fn is_dma_accessible(address: *const u8, len: usize) -> bool {
+    const SRAM: core::ops::Range<usize> = 0x2000_0000..0x4000_0000;
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    const EXTERNAL_MEMORY: core::ops::Range<usize> = 0x6000_0000..0xE000_0000;
+
+    let start = address as usize;
+    let end = start.saturating_add(len.saturating_sub(1));
+
+    let contains = |range: core::ops::Range<usize>| range.contains(&start) && range.contains(&end);
+
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    if contains(EXTERNAL_MEMORY) {
+        return true;
+    }
+
+    contains(SRAM)
+}
+
+/// The configuration of the AHB bus interface of the DMA, which is
+/// written to the `ETH_DMABMR` register.
+///
+/// The fields have the same layout on the STM32F107, STM32F4 and STM32F7,
+/// and the reference manuals (RM0008, RM0090, RM0410) place no constraints
+/// on how they are combined, so every value of this type is valid. The
+/// [`Default`] configuration (fixed, address-aligned bursts, and round-robin
+/// arbitration with a ratio of 2:1) is safe for all memories that the DMA
+/// can access (see [`is_dma_accessible`]), including the FMC.
+///
+/// See [`EthernetDMA::set_config`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaConfig {
+    /// Only perform SINGLE, INCR4, INCR8 and INCR16 bursts (FB).
+    ///
+    /// If this is `false`, the DMA also uses undefined-length INCR bursts,
+    /// which not all bus slaves (e.g. the FMC) handle correctly.
+    pub fixed_burst: bool,
+    /// Align bursts to their length (AAB).
+    ///
+    /// If `fixed_burst` is `true`, all bursts are aligned. Otherwise, the
+    /// first burst that accesses a buffer is not aligned, but all subsequent
+    /// ones are.
+    pub address_aligned_beats: bool,
+    /// How the DMA arbitrates between its RX and TX engines (DA and PM).
+    pub arbitration: DmaArbitration,
+}
+
+impl Default for DmaConfig {
+    fn default() -> Self {
+        Self {
+            fixed_burst: true,
+            address_aligned_beats: true,
+            arbitration: DmaArbitration::default(),
+        }
+    }
+}
+
+/// How the DMA arbitrates between its RX and TX engines when both of
+/// them request the bus at the same time.
+///
+/// This is configured with the DA bit and the PM field of the `ETH_DMABMR`
+/// register, which have the same layout on the STM32F107, STM32F4 and
+/// STM32F7.
+///
+/// See [`DmaConfig::arbitration`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmaArbitration {
+    /// The RX and TX engines take turns, with the given ratio of RX to
+    /// TX accesses (DA cleared, PM selects the ratio).
+    RoundRobin(PriorityRatio),
+    /// The RX engine always has priority over the TX engine (DA set).
+    RxPriority,
+}
+
+impl Default for DmaArbitration {
+    fn default() -> Self {
+        Self::RoundRobin(PriorityRatio::TwoToOne)
+    }
+}
+
+/// The ratio of RX to TX bus accesses of [`DmaArbitration::RoundRobin`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityRatio {
+    /// 1:1
+    OneToOne,
+    /// 2:1
+    TwoToOne,
+    /// 3:1
+    ThreeToOne,
+    /// 4:1
+    FourToOne,
+}
+
+impl PriorityRatio {
+    /// The value of the PM field.
+    const fn pm(self) -> u8 {
+        match self {
+            Self::OneToOne => 0b00,
+            Self::TwoToOne => 0b01,
+            Self::ThreeToOne => 0b10,
+            Self::FourToOne => 0b11,
+        }
+    }
+
+    const fn from_pm(pm: u8) -> Self {
+        match pm & 0b11 {
+            0b00 => Self::OneToOne,
+            0b01 => Self::TwoToOne,
+            0b10 => Self::ThreeToOne,
+            _ => Self::FourToOne,
+        }
+    }
+}
+
+/// Poll `done` until it returns `true`, at most [`DMA_TIMEOUT_ITERATIONS`] times.
+///
+/// Returns `Err(timeout)` if `done` does not return `true` in time, and
+/// [`DmaError::FatalBusError`] if a fatal bus error occurs while waiting.
+pub(crate) fn wait_until(
+    eth_dma: &ETHERNET_DMA,
+    timeout: DmaError,
+    mut done: impl FnMut() -> bool,
+) -> Result<(), DmaError> {
+    let what = match timeout {
+        DmaError::ResetTimeout => "the DMA reset",
+        DmaError::SuspendTimeout => "the DMA to stop",
+        DmaError::FlushTimeout => "the TX FIFO flush",
+        _ => "the DMA",
+    };
+
+    let mut fatal_bus_error = false;
+
+    crate::wait::wait_for(what, DMA_TIMEOUT_ITERATIONS, || {
+        if done() {
+            true
+        } else {
+            fatal_bus_error = eth_dma.dmasr.read().fbes().bit_is_set();
+            fatal_bus_error
+        }
+    })
+    .map_err(|_| timeout)?;
+
+    if fatal_bus_error {
+        Err(DmaError::FatalBusError)
+    } else {
+        Ok(())
+    }
+}
+
+/// The enable bits of all abnormal interrupts in `ETH_DMAIER` (TJTIE, ROIE,
+/// TUIE, RBUIE, RPSIE, RWTIE, ETIE and FBEIE), which have the same layout on
+/// the STM32F107, STM32F4 and STM32F7.
+const DMAIER_ABNORMAL_SOURCES: u32 =
+    (1 << 1) | (1 << 4) | (1 << 5) | (1 << 7) | (1 << 8) | (1 << 9) | (1 << 10) | (1 << 13);
+
+/// Enable the abnormal interrupt summary (AISE) if, and only if, at least
+/// one abnormal interrupt is enabled.
+fn update_abnormal_summary(eth_dma: &ETHERNET_DMA) {
+    eth_dma.dmaier.modify(|r, w| {
+        let abnormal = r.bits() & DMAIER_ABNORMAL_SOURCES != 0;
+        w.aise().bit(abnormal)
+    });
+}
+
+/// Write the bus mode fields described by `config` to `ETH_DMABMR`.
+///
+/// The DMA must be stopped.
+fn write_bus_config(eth_dma: &ETHERNET_DMA, config: DmaConfig) {
+    let (da, pm) = match config.arbitration {
+        DmaArbitration::RoundRobin(ratio) => (false, ratio.pm()),
+        // The PM field is ignored while DA is set.
+        DmaArbitration::RxPriority => (true, PriorityRatio::OneToOne.pm()),
+    };
+
+    eth_dma.dmabmr.modify(|_, w| {
+        w.fb()
+            .bit(config.fixed_burst)
+            .aab()
+            .bit(config.address_aligned_beats)
+            .da()
+            .bit(da)
+            .pm()
+            .bits(pm)
+    });
+}
+
+/// This struct is returned by [`EthernetDMA::prepare_for_sleep`] to
+/// indicate that the DMA is stopped. It must be passed to
+/// [`EthernetDMA::wake`] to restart the DMA.
+#[must_use = "The DMA remains stopped until this token is passed to `EthernetDMA::wake`"]
+#[derive(Debug)]
+pub struct SleepToken {
+    _private: (),
+}
+
 /// Ethernet DMA.
 pub struct EthernetDMA<'rx, 'tx> {
     pub(crate) eth_dma: ETHERNET_DMA,
@@ -55,18 +341,20 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
     ///
     /// # Note
     /// - Make sure that the buffers reside in a memory region that is
-    /// accessible by the peripheral. Core-Coupled Memory (CCM) is
-    /// usually not accessible.
+    ///   accessible by the peripheral. Core-Coupled Memory (CCM) is
+    ///   usually not accessible.
     pub(crate) fn new(
         eth_dma: ETHERNET_DMA,
         rx_buffer: &'rx mut [RxRingEntry],
         tx_buffer: &'tx mut [TxRingEntry],
-    ) -> Self {
+    ) -> Result<Self, DmaError> {
         // reset DMA bus mode register
         eth_dma.dmabmr.modify(|_, w| w.sr().set_bit());
 
         // Wait until done
-        while eth_dma.dmabmr.read().sr().bit_is_set() {}
+        wait_until(&eth_dma, DmaError::ResetTimeout, || {
+            eth_dma.dmabmr.read().sr().bit_is_clear()
+        })?;
 
         // operation mode register
         eth_dma.dmaomr.modify(|_, w| {
@@ -85,6 +373,9 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
                 // Forward error frames
                 .fef()
                 .set_bit()
+                // Drop undersized good frames, see `RxRing::set_accept_runt_frames`
+                .fugf()
+                .clear_bit()
                 // Operate on second frame
                 .osf()
                 .set_bit()
@@ -93,32 +384,28 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         // bus mode register
         eth_dma.dmabmr.modify(|_, w| {
             // For any non-f107 chips, we must use enhanced descriptor format to support checksum
-            // offloading and/or timestamps.
+            // offloading and/or timestamps. The descriptors are always parsed in the enhanced
+            // layout on these chips (extended RX status in RDES4, timestamps in RDES6/7), and
+            // are 8 words long to fit it, so this bit must not depend on the enabled features.
             #[cfg(not(feature = "stm32f1xx-hal"))]
             let w = w.edfe().set_bit();
 
             unsafe {
-                // Address-aligned beats
-                w.aab()
-                    .set_bit()
-                    // Fixed burst
-                    .fb()
-                    .set_bit()
-                    // Rx DMA PBL
-                    .rdp()
+                // Rx DMA PBL
+                w.rdp()
                     .bits(32)
                     // Programmable burst length
                     .pbl()
                     .bits(32)
-                    // Rx Tx priority ratio 2:1
-                    .pm()
-                    .bits(0b01)
                     // Use separate PBL
                     .usp()
                     .set_bit()
             }
         });
 
+        // Address-aligned beats, fixed burst and arbitration
+        write_bus_config(&eth_dma, DmaConfig::default());
+
         let mut dma = EthernetDMA {
             eth_dma,
             rx_ring: RxRing::new(rx_buffer),
@@ -131,7 +418,7 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         dma.rx_ring.start(&dma.eth_dma);
         dma.tx_ring.start(&dma.eth_dma);
 
-        dma
+        Ok(dma)
     }
 
     /// Split the [`EthernetDMA`] into concurrently operating send and
@@ -171,6 +458,115 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         }
     }
 
+    /// Enable or disable the early receive and early transmit interrupts.
+    ///
+    /// The early receive interrupt occurs when the DMA has filled the first
+    /// buffer of a received frame, and the early transmit interrupt occurs when
+    /// the DMA has completely transferred a frame to be transmitted into the TX
+    /// FIFO. Both are reported by [`EthernetDMA::interrupt_handler`], and allow
+    /// an application to start processing a frame before the RX or TX interrupt
+    /// occurs.
+    ///
+    /// Both interrupts are disabled by default, as they significantly increase
+    /// the interrupt rate. They are only delivered if the interrupts of the
+    /// DMA are enabled (see [`EthernetDMA::enable_interrupt`]).
+    ///
+    /// # Note
+    /// The RX and TX DMA always operate in store-and-forward mode: a received
+    /// frame is only transferred to memory once it is completely stored in the RX
+    /// FIFO, and a frame is only transmitted once it is completely stored in the
+    /// TX FIFO. Additionally, every RX buffer can hold a complete frame. As a result,
+    /// these interrupts only occur marginally earlier than the RX and TX interrupts.
+    pub fn set_early_interrupts(&mut self, early_rx: bool, early_tx: bool) {
+        self.eth_dma
+            .dmaier
+            .modify(|_, w| w.erie().bit(early_rx).etie().bit(early_tx));
+
+        // The early transmit interrupt is an abnormal interrupt
+        update_abnormal_summary(&self.eth_dma);
+    }
+
+    /// Only request a TX interrupt for every `count`th frame that is sent,
+    /// instead of for every frame.
+    ///
+    /// A burst of `count` frames then only raises one TX interrupt. Additionally,
+    /// while `count` is greater than 1, a TX interrupt is raised whenever the DMA
+    /// has transmitted all queued frames, so the completion of the last frame of
+    /// a burst is always reported, even if it did not request an interrupt.
+    ///
+    /// This reduces the interrupt load when transmitting at a high rate, at the
+    /// cost of increased latency: the completion of a frame (and with it, e.g.,
+    /// the availability of its TX timestamp, or waking a task that waits for a
+    /// free TX entry) may only be reported once `count - 1` further frames have
+    /// been transmitted, or once the TX ring is empty. A `count` that is close
+    /// to the length of the TX ring may cause sending to block until the ring
+    /// has drained completely.
+    ///
+    /// A `count` of 0 is treated as 1. By default, every frame requests
+    /// a TX interrupt.
+    pub fn set_tx_interrupt_coalescing(&mut self, count: usize) {
+        self.tx_ring.set_interrupt_coalescing(count);
+
+        let coalesce = self.tx_ring.interrupt_coalescing() > 1;
+        self.eth_dma.dmaier.modify(|_, w| w.tbuie().bit(coalesce));
+    }
+
+    /// The amount of frames that are sent per requested TX interrupt.
+    ///
+    /// See [`EthernetDMA::set_tx_interrupt_coalescing`].
+    pub fn tx_interrupt_coalescing(&self) -> usize {
+        self.tx_ring.interrupt_coalescing()
+    }
+
+    /// Flush the TX FIFO, discarding all data in it, and wait until
+    /// flushing completes.
+    ///
+    /// This should be done after a TX error, or before reconfiguring the
+    /// MAC (for instance, when switching speeds), so that stale data in the TX
+    /// FIFO does not corrupt the next transmitted frame. A frame that is being
+    /// transmitted while the TX FIFO is flushed is aborted.
+    ///
+    /// Returns [`DmaError::FlushTimeout`] if flushing does not complete within
+    /// [`DMA_TIMEOUT_ITERATIONS`].
+    pub fn flush_tx_fifo(&mut self) -> Result<(), DmaError> {
+        self.eth_dma.dmaomr.modify(|_, w| w.ftf().set_bit());
+
+        // The FTF bit is cleared by the hardware once the flush completes.
+        wait_until(&self.eth_dma, DmaError::FlushTimeout, || {
+            self.eth_dma.dmaomr.read().ftf().bit_is_clear()
+        })
+    }
+
+    /// Enable or disable the automatic recovery from TX underflows.
+    ///
+    /// A TX underflow occurs if the DMA can not transfer a frame into the TX
+    /// FIFO quickly enough while it is being transmitted. The frame is aborted
+    /// (see [`TxStatus::underflow`]), and the TX DMA is suspended.
+    ///
+    /// If recovery is enabled, the TX underflow interrupt is enabled, and
+    /// [`EthernetDMA::interrupt_handler`] restarts the TX DMA whenever it
+    /// reports an underflow. Otherwise, the TX DMA stays suspended until
+    /// the next frame is sent. Recovery is disabled by default, and the
+    /// interrupt is only delivered if the interrupts of the DMA are
+    /// enabled (see [`EthernetDMA::enable_interrupt`]).
+    ///
+    /// The recovery does not flush the TX FIFO. If an underflow is reported,
+    /// [`EthernetDMA::flush_tx_fifo`] can be used to discard the remains of
+    /// the aborted frame.
+    ///
+    /// # Note
+    /// The TX DMA always operates in store-and-forward mode, in which
+    /// underflows should not occur. Frequent underflows indicate that the
+    /// operation mode of the DMA was misconfigured (for instance, by
+    /// switching to a transmit threshold that is too low), or that the
+    /// DMA is starved by other bus masters.
+    pub fn set_tx_underflow_recovery(&mut self, enable: bool) {
+        self.eth_dma.dmaier.modify(|_, w| w.tuie().bit(enable));
+
+        // The underflow interrupt is an abnormal interrupt
+        update_abnormal_summary(&self.eth_dma);
+    }
+
     /// Handle the DMA parts of the `ETH` interrupt.
     pub fn interrupt_handler() -> InterruptReasonSummary {
         // SAFETY: we only perform atomic reads/writes through `eth_dma`.
@@ -180,13 +576,60 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
 
         let status = InterruptReasonSummary {
             is_rx: status.rs().bit_is_set(),
-            is_tx: status.ts().bit_is_set(),
+            // The DMA suspends once it has transmitted all queued frames. This
+            // only raises an interrupt while TX interrupts are coalesced.
+            is_tx: status.ts().bit_is_set()
+                || (status.tbus().bit_is_set() && eth_dma.dmaier.read().tbuie().bit_is_set()),
             is_error: status.ais().bit_is_set(),
+            is_early_rx: status.ers().bit_is_set(),
+            is_early_tx: status.ets().bit_is_set(),
+            is_tx_underflow: status.tus().bit_is_set(),
         };
 
-        eth_dma
-            .dmasr
-            .write(|w| w.nis().set_bit().ts().set_bit().rs().set_bit());
+        let recover_tx_underflow =
+            status.is_tx_underflow && eth_dma.dmaier.read().tuie().bit_is_set();
+
+        eth_dma.dmasr.write(|w| {
+            let w = if status.is_early_tx {
+                w.ets().set_bit()
+            } else {
+                w
+            };
+
+            let w = if status.is_tx_underflow {
+                w.tus().set_bit()
+            } else {
+                w
+            };
+
+            // The early transmit and transmit underflow interrupts are abnormal
+            // interrupts, so their summary bit must be cleared as well.
+            let w = if status.is_error {
+                w.ais().set_bit()
+            } else {
+                w
+            };
+
+            w.nis()
+                .set_bit()
+                .ts()
+                .set_bit()
+                .tbus()
+                .set_bit()
+                .rs()
+                .set_bit()
+                .ers()
+                .set_bit()
+        });
+
+        if recover_tx_underflow {
+            eth_dma.dmaomr.modify(|_, w| w.st().set_bit());
+            TxRing::demand_poll();
+        }
+
+        if status.is_tx {
+            tx::completion::COMPLETIONS.signal();
+        }
 
         #[cfg(feature = "async-await")]
         {
@@ -211,6 +654,68 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         self.rx_ring.recv_next(packet_id.map(Into::into))
     }
 
+    /// Receive the next packet (if any is ready), together with the raw
+    /// status words of its descriptor, or return [`Err`] immediately.
+    ///
+    /// See [`RxRing::recv_next_raw`].
+    #[cfg(feature = "raw-descriptors")]
+    pub fn recv_next_raw(
+        &mut self,
+        packet_id: Option<PacketId>,
+    ) -> Result<(RxPacket<'_>, [u32; DESCRIPTOR_WORDS]), RxError> {
+        self.rx_ring.recv_next_raw(packet_id)
+    }
+
+    /// Try to receive a packet by copying it into `buffer`.
+    ///
+    /// Returns the length of the packet. See [`RxRing::recv_into`].
+    ///
+    /// `buffer` may reside in memory that the DMA cannot access, such as
+    /// CCM or DTCM: the packet is received into a buffer of the ring first.
+    pub fn try_recv_into(&mut self, buffer: &mut [u8]) -> Result<usize, RxError> {
+        self.rx_ring.recv_into(buffer)
+    }
+
+    /// Receive all frames that are ready, grouped by `classify`.
+    ///
+    /// See [`RxRing::recv_classified`].
+    pub fn recv_classified<B, C, F>(&mut self, classify: C, deliver: F) -> usize
+    where
+        B: PartialEq,
+        C: Fn(&[u8]) -> B,
+        F: FnMut(&B, &[u8]),
+    {
+        self.rx_ring.recv_classified(classify, deliver)
+    }
+
+    /// Log the state of every RX and TX descriptor, and the first `bytes`
+    /// bytes of each buffer, using [`defmt`].
+    ///
+    /// For every entry, the decoded state of its descriptor and the raw
+    /// descriptor words are logged. The next entry that is received or sent,
+    /// and the entry whose descriptor the DMA is currently processing, are
+    /// marked.
+    ///
+    /// This only reads the rings, so it can be called at any time, e.g. once a
+    /// ring seems to be stuck. The DMA keeps running while the rings are
+    /// logged, so the entries may be modified by the DMA in the meantime.
+    #[cfg(feature = "defmt")]
+    pub fn dump_rings(&self, bytes: usize) {
+        self.rx_ring.dump(bytes);
+        self.tx_ring.dump(bytes);
+    }
+
+    /// Try to receive a packet that was queued in `queue`.
+    ///
+    /// See [`RxRing::recv_queued`].
+    pub fn recv_queued<const DEPTH: usize>(
+        &mut self,
+        queue: &RxQueue<DEPTH>,
+        packet_id: Option<PacketId>,
+    ) -> Result<RxPacket<'_>, RxError> {
+        self.rx_ring.recv_queued(queue, packet_id)
+    }
+
     /// Is Rx DMA currently running?
     ///
     /// It stops if the ring is full. Call [`EthernetDMA::recv_next()`] to free an
@@ -224,30 +729,647 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         self.tx_ring.is_running()
     }
 
+    /// Quiesce the DMA so that the clocks of the Ethernet peripheral
+    /// can be stopped, e.g. before entering STOP mode.
+    ///
+    /// This function blocks until all frames queued in the TX ring have
+    /// been transmitted, and then stops the TX and RX DMA. Once it returns,
+    /// no descriptor is in the middle of a transfer.
+    ///
+    /// Frames that arrive after the RX DMA is stopped are dropped. Frames
+    /// that were received before, but not yet processed, are dropped by
+    /// [`EthernetDMA::wake`].
+    ///
+    /// The correct sequence for entering and leaving STOP mode is:
+    /// 1. Call this function.
+    /// 2. Disable the Ethernet MAC clocks (`ETHMACEN`, `ETHMACTXEN` and
+    ///    `ETHMACRXEN`) in the RCC.
+    /// 3. Enter STOP mode.
+    /// 4. After waking up, restore the system clocks to the configuration
+    ///    that was used to create the [`EthernetDMA`], and enable the
+    ///    Ethernet MAC clocks again.
+    /// 5. Call [`EthernetDMA::wake`].
+    ///
+    /// If the TX ring is not drained or the DMA does not stop in time, this
+    /// function returns [`DmaError::SuspendTimeout`]. The DMA is still
+    /// running in that case: if only one direction stopped in time, it is
+    /// started again, at the descriptor at which it stopped.
+    #[cfg_attr(
+        all(feature = "ptp", not(feature = "stm32f1xx-hal")),
+        doc = "",
+        doc = "A frame that waits for its launch time (see [`EthernetDMA::send_at`]) is dropped, unless it is already being transmitted."
+    )]
+    pub fn prepare_for_sleep(&mut self) -> Result<SleepToken, DmaError> {
+        #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+        self.tx_ring.cancel_launch();
+
+        wait_until(&self.eth_dma, DmaError::SuspendTimeout, || {
+            self.tx_ring.is_empty()
+        })?;
+
+        if let Err(e) = self.tx_ring.stop(&self.eth_dma) {
+            self.tx_ring.cancel_stop(&self.eth_dma);
+            return Err(e);
+        }
+
+        if let Err(e) = self.rx_ring.stop(&self.eth_dma) {
+            self.rx_ring.cancel_stop(&self.eth_dma);
+            self.tx_ring.cancel_stop(&self.eth_dma);
+            return Err(e);
+        }
+
+        Ok(SleepToken { _private: () })
+    }
+
+    /// Restart the DMA after it was stopped by
+    /// [`EthernetDMA::prepare_for_sleep`].
+    ///
+    /// The ethernet clocks must be enabled before calling this function.
+    ///
+    /// This re-initializes the RX and TX rings, so frames that were received
+    /// before [`EthernetDMA::prepare_for_sleep`] was called but not processed
+    /// yet, and the timestamps of all previous frames, are lost.
+    ///
+    /// No [`PacketId`] used before this call is associated with any
+    /// frame afterwards: polling for its timestamp returns
+    /// [`PacketIdNotFound`] instead of a stale timestamp. Callers that track packet IDs of
+    /// frames that are in flight must discard them, and re-establish
+    /// their tracking for frames sent or received after this call.
+    pub fn wake(&mut self, token: SleepToken) {
+        let SleepToken { _private: () } = token;
+
+        self.rx_ring.start(&self.eth_dma);
+        self.tx_ring.start(&self.eth_dma);
+    }
+
+    /// Use buffers acquired from `provider` for receiving frames,
+    /// instead of the buffers contained in the [`RxRingEntry`]s.
+    ///
+    /// This temporarily stops the RX DMA. Frames that were received but
+    /// not processed yet are dropped, and the packet IDs and timestamps
+    /// of all previously received frames are forgotten.
+    ///
+    /// If a provider was set previously, all buffers acquired from it are
+    /// released to it, except for the ones returned by [`RxPacket::into_buffer`].
+    ///
+    /// If the RX DMA does not stop, `provider` is not used, and an error is
+    /// returned. The RX DMA remains stopped in that case.
+    ///
+    /// If `provider` provides a buffer that the DMA cannot access (see
+    /// [`is_dma_accessible`]) or that is not aligned to 4 bytes, `provider`
+    /// is not used either, and [`DmaError::InaccessibleBuffer`] or
+    /// [`DmaError::MisalignedBuffer`] is returned. The RX DMA is restarted
+    /// with the buffers contained in the entries in that case.
+    pub fn set_rx_buffer_provider(
+        &mut self,
+        provider: &'rx mut dyn BufferProvider,
+    ) -> Result<(), DmaError> {
+        self.rx_ring.stop(&self.eth_dma)?;
+        let result = self.rx_ring.set_buffer_provider(Some(provider));
+        self.rx_ring.start(&self.eth_dma);
+        result
+    }
+
+    /// Stop using the buffer provider set with [`EthernetDMA::set_rx_buffer_provider`],
+    /// and release all buffers that were acquired from it.
+    ///
+    /// This temporarily stops the RX DMA. Frames that were received but
+    /// not processed yet are dropped, and the packet IDs and timestamps
+    /// of all previously received frames are forgotten.
+    ///
+    /// If the RX DMA does not stop, the buffers are not released, and an
+    /// error is returned. The RX DMA remains stopped in that case.
+    pub fn clear_rx_buffer_provider(&mut self) -> Result<(), DmaError> {
+        self.rx_ring.stop(&self.eth_dma)?;
+        self.rx_ring.set_buffer_provider(None)?;
+        self.rx_ring.start(&self.eth_dma);
+        Ok(())
+    }
+
+    /// Enable or disable operate on second frame.
+    ///
+    /// If enabled, the TX DMA starts processing the next frame in the
+    /// TX ring before the status of the current frame has been written back.
+    /// This reduces the idle time between back-to-back transmissions.
+    ///
+    /// This is independent of the transmit store-and-forward mode (which is
+    /// always enabled): the MAC still only starts transmitting a frame once it
+    /// is completely stored in the TX FIFO, but the FIFO can be refilled
+    /// while the previous frame is still being transmitted.
+    ///
+    /// Operate on second frame is enabled by default on all supported MCUs.
+    pub fn set_operate_on_second_frame(&mut self, enable: bool) {
+        self.eth_dma.dmaomr.modify(|_, w| w.osf().bit(enable));
+    }
+
+    /// Check whether operate on second frame is enabled.
+    ///
+    /// See [`EthernetDMA::set_operate_on_second_frame`].
+    pub fn operate_on_second_frame(&self) -> bool {
+        self.eth_dma.dmaomr.read().osf().bit_is_set()
+    }
+
+    /// Configure the AHB bus interface of the DMA.
+    ///
+    /// The bus mode may only be changed while the DMA is stopped, so this
+    /// function stops it like [`EthernetDMA::prepare_for_sleep`], applies
+    /// `config`, and restarts it like [`EthernetDMA::wake`]. It blocks until
+    /// all frames queued in the TX ring have been transmitted, and frames that
+    /// were received but not processed yet are dropped.
+    ///
+    /// If the TX ring is not drained or the DMA does not stop in time,
+    /// `config` is not applied and [`DmaError::SuspendTimeout`] is returned.
+    /// The DMA keeps running with the previous configuration in that case,
+    /// see [`EthernetDMA::prepare_for_sleep`].
+    pub fn set_config(&mut self, config: DmaConfig) -> Result<(), DmaError> {
+        let token = self.prepare_for_sleep()?;
+        write_bus_config(&self.eth_dma, config);
+        self.wake(token);
+        Ok(())
+    }
+
+    /// Get the configuration of the AHB bus interface of the DMA.
+    ///
+    /// See [`EthernetDMA::set_config`].
+    pub fn config(&self) -> DmaConfig {
+        let dmabmr = self.eth_dma.dmabmr.read();
+
+        let arbitration = if dmabmr.da().bit_is_set() {
+            DmaArbitration::RxPriority
+        } else {
+            DmaArbitration::RoundRobin(PriorityRatio::from_pm(dmabmr.pm().bits()))
+        };
+
+        DmaConfig {
+            fixed_burst: dmabmr.fb().bit_is_set(),
+            address_aligned_beats: dmabmr.aab().bit_is_set(),
+            arbitration,
+        }
+    }
+
+    /// Enable or disable fixed bursts on the AHB bus.
+    ///
+    /// See [`DmaConfig::fixed_burst`]. Fixed bursts are enabled by default.
+    ///
+    /// This stops and restarts the DMA, see [`EthernetDMA::set_config`].
+    pub fn set_fixed_burst(&mut self, fixed_burst: bool) -> Result<(), DmaError> {
+        self.set_config(DmaConfig {
+            fixed_burst,
+            ..self.config()
+        })
+    }
+
+    /// Check whether the DMA performs fixed bursts.
+    ///
+    /// See [`EthernetDMA::set_fixed_burst`].
+    pub fn fixed_burst(&self) -> bool {
+        self.config().fixed_burst
+    }
+
+    /// Enable or disable address-aligned beats on the AHB bus.
+    ///
+    /// See [`DmaConfig::address_aligned_beats`]. Address-aligned beats are
+    /// enabled by default.
+    ///
+    /// This stops and restarts the DMA, see [`EthernetDMA::set_config`].
+    pub fn set_address_aligned_beats(&mut self, aligned: bool) -> Result<(), DmaError> {
+        self.set_config(DmaConfig {
+            address_aligned_beats: aligned,
+            ..self.config()
+        })
+    }
+
+    /// Check whether the DMA performs address-aligned beats.
+    ///
+    /// See [`EthernetDMA::set_address_aligned_beats`].
+    pub fn address_aligned_beats(&self) -> bool {
+        self.config().address_aligned_beats
+    }
+
+    /// Configure how the DMA arbitrates between its RX and TX engines.
+    ///
+    /// Use [`DmaArbitration::RxPriority`] or a higher ratio to make sure that
+    /// received frames are written to memory quickly under heavy TX load, so
+    /// that the RX FIFO of the MAC does not overflow. Use a lower ratio for
+    /// transmit-heavy workloads.
+    ///
+    /// The default is [`DmaArbitration::RoundRobin`] with a ratio of 2:1
+    /// ([`PriorityRatio::TwoToOne`]).
+    ///
+    /// This stops and restarts the DMA, see [`EthernetDMA::set_config`].
+    pub fn set_arbitration(&mut self, arbitration: DmaArbitration) -> Result<(), DmaError> {
+        self.set_config(DmaConfig {
+            arbitration,
+            ..self.config()
+        })
+    }
+
+    /// Get the arbitration scheme between the RX and TX engines.
+    ///
+    /// See [`EthernetDMA::set_arbitration`].
+    pub fn arbitration(&self) -> DmaArbitration {
+        self.config().arbitration
+    }
+
+    /// Get the address of the first descriptor in the RX descriptor list,
+    /// as currently programmed into the DMA.
+    pub fn rx_descriptor_base(&self) -> u32 {
+        self.eth_dma.dmardlar.read().bits()
+    }
+
+    /// Set the address of the first descriptor in the RX descriptor list.
+    ///
+    /// The two least significant bits of `base` are ignored by the DMA, so
+    /// `base` must be (at least) word-aligned. Descriptors created by this
+    /// crate are 8-byte aligned.
+    ///
+    /// # Safety
+    /// - The RX DMA must be stopped: the DMA only accepts writes to this
+    ///   register while reception is stopped.
+    /// - `base` must point to a fully initialized, chained descriptor list
+    ///   that resides in memory accessible by the DMA and that outlives its
+    ///   use by the DMA.
+    /// - The [`RxRing`] of this [`EthernetDMA`] is not aware of the new list.
+    ///   The original base address (see [`EthernetDMA::rx_descriptor_base`])
+    ///   must be restored before the [`RxRing`] is used again.
+    pub unsafe fn set_rx_descriptor_base(&mut self, base: u32) {
+        self.eth_dma.dmardlar.write(|w| w.srl().bits(base));
+    }
+
+    /// Get the address of the first descriptor in the TX descriptor list,
+    /// as currently programmed into the DMA.
+    pub fn tx_descriptor_base(&self) -> u32 {
+        self.eth_dma.dmatdlar.read().bits()
+    }
+
+    /// Set the address of the first descriptor in the TX descriptor list.
+    ///
+    /// The two least significant bits of `base` are ignored by the DMA, so
+    /// `base` must be (at least) word-aligned. Descriptors created by this
+    /// crate are 8-byte aligned.
+    ///
+    /// # Safety
+    /// - The TX DMA must be stopped: the DMA only accepts writes to this
+    ///   register while transmission is stopped.
+    /// - `base` must point to a fully initialized, chained descriptor list
+    ///   that resides in memory accessible by the DMA and that outlives its
+    ///   use by the DMA.
+    /// - The [`TxRing`] of this [`EthernetDMA`] is not aware of the new list.
+    ///   The original base address (see [`EthernetDMA::tx_descriptor_base`])
+    ///   must be restored before the [`TxRing`] is used again.
+    pub unsafe fn set_tx_descriptor_base(&mut self, base: u32) {
+        self.eth_dma.dmatdlar.write(|w| w.stl().bits(base));
+    }
+
+    /// Take a snapshot of the descriptors and buffers that the DMA
+    /// is currently processing.
+    ///
+    /// This only reads the current host descriptor and buffer address
+    /// registers of the DMA, so it is cheap and does not affect the DMA.
+    /// A watchdog can compare successive snapshots: if they do not change
+    /// while traffic is expected, the DMA may be stuck, and can be restarted
+    /// with [`EthernetDMA::prepare_for_sleep`] and [`EthernetDMA::wake`].
+    ///
+    /// An idle DMA does not advance either, so snapshots only indicate
+    /// a stuck DMA if frames were sent, or are expected to be received,
+    /// in the meantime.
+    pub fn progress_markers(&self) -> ProgressMarkers {
+        ProgressMarkers {
+            rx_descriptor: self.eth_dma.dmachrdr.read().bits(),
+            rx_buffer: self.eth_dma.dmachrbar.read().bits(),
+            tx_descriptor: self.eth_dma.dmachtdr.read().bits(),
+            tx_buffer: self.eth_dma.dmachtbar.read().bits(),
+        }
+    }
+
+    /// Read and clear the missed frame and buffer overflow counter register
+    /// of the DMA (`DMAMFBOCR`).
+    ///
+    /// The register counts received frames that were dropped because the RX
+    /// DMA had no free descriptor (buffer starvation, i.e. frames are not
+    /// received from the ring quickly enough), separately from frames that were
+    /// dropped because the RX FIFO overflowed (the DMA does not drain the FIFO
+    /// quickly enough).
+    ///
+    /// The hardware clears all counters and overflow flags when the register is
+    /// read, so every dropped frame is reported by exactly one call to this
+    /// function. The register is read exactly once per call, which is why this
+    /// function takes `&mut self`. To track the total amount of dropped frames,
+    /// accumulate the returned counts, and call this function often enough for
+    /// the counters not to overflow.
+    pub fn missed_frame_counter(&mut self) -> MissedFrameCounts {
+        let dmamfbocr = self.eth_dma.dmamfbocr.read();
+
+        MissedFrameCounts {
+            buffer_unavailable: dmamfbocr.mfc().bits(),
+            buffer_unavailable_overflow: dmamfbocr.omfc().bit_is_set(),
+            fifo_overflow: dmamfbocr.mfa().bits(),
+            fifo_overflow_overflow: dmamfbocr.ofoc().bit_is_set(),
+        }
+    }
+
+    /// Check whether the DMA uses the enhanced (alternate) descriptor format.
+    ///
+    /// The format is selected by the bus mode register of the DMA (`EDFE` bit in
+    /// `DMABMR`) when the DMA is set up, and is fixed by the MCU family:
+    /// * The DMA of the STM32F4 and STM32F7 always uses the enhanced format,
+    ///   which carries the extended RX status (checksum offload results,
+    ///   PTP message types) and the timestamps. Each descriptor is 8 words
+    ///   (32 bytes) long, including the words that the normal format lacks.
+    /// * The DMA of the STM32F107 only supports the normal format, with 4 word
+    ///   (16 byte) descriptors that hold the timestamps in place of the buffer
+    ///   addresses, so this function always returns `false` there.
+    ///
+    /// The layout is chosen at compile time because the RX and TX rings parse
+    /// the descriptors in it, regardless of which features are enabled. Selecting
+    /// a different format at runtime would make the DMA and the rings disagree on
+    /// the location of the status and timestamp words, so it cannot be overridden.
+    pub fn enhanced_descriptors(&self) -> bool {
+        #[cfg(not(feature = "stm32f1xx-hal"))]
+        {
+            self.eth_dma.dmabmr.read().edfe().bit_is_set()
+        }
+
+        #[cfg(feature = "stm32f1xx-hal")]
+        {
+            false
+        }
+    }
+
+    /// Take a snapshot of the statistics of the DMA and `mac`.
+    ///
+    /// All values are read in a critical section, and the MMC counters are
+    /// frozen while they are read, so the snapshot is consistent. Only the
+    /// missed frame counts ([`EthStats::missed_frames`]) are cleared by reading
+    /// them, as with [`EthernetDMA::missed_frame_counter`], so accumulate them
+    /// if they are also read elsewhere. The hardware does not count bytes.
+    pub fn stats(&mut self, mac: &EthernetMAC) -> EthStats {
+        cortex_m::interrupt::free(|_| EthStats {
+            mmc: mac.mmc_counters(),
+            missed_frames: self.missed_frame_counter(),
+            rx_low_water_mark: self.rx_ring.low_water_mark(),
+            tx_high_water_mark: self.tx_ring.high_water_mark(),
+            speed: mac.get_speed(),
+        })
+    }
+
+    /// Get the current fill level of the RX FIFO of the MAC.
+    ///
+    /// If the RX FIFO fills up while the RX DMA has free descriptors, the DMA
+    /// does not drain the FIFO quickly enough, e.g. because of bus contention.
+    /// If the RX FIFO is full while the RX DMA has no free descriptors, frames
+    /// are not received from the ring quickly enough. In both cases, frames
+    /// are dropped once the FIFO overflows, which is counted by the missed
+    /// frame and buffer overflow counter register of the DMA (see
+    /// [`EthernetDMA::missed_frame_counter`]).
+    ///
+    /// The level is read from the MAC debug register (`MACDBGR`), whose layout
+    /// is specific to the MAC of the STM32F4 and STM32F7. The MAC of the
+    /// STM32F107 has no debug register, so this function is not available
+    /// there.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub fn rx_fifo_level(&self) -> RxFifoLevel {
+        // SAFETY: we only perform an atomic read of `macdbgr`.
+        let eth_mac = unsafe { &*crate::peripherals::ETHERNET_MAC::ptr() };
+
+        match eth_mac.macdbgr.read().rffl().bits() {
+            0b00 => RxFifoLevel::Empty,
+            0b01 => RxFifoLevel::BelowDeactivationThreshold,
+            0b10 => RxFifoLevel::AboveActivationThreshold,
+            _ => RxFifoLevel::Full,
+        }
+    }
+
+    /// Try to send a frame that is stored in `'static` memory, without
+    /// copying it if possible.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// See [`TxRing::send_static`].
+    pub fn send_static(
+        &mut self,
+        frame: &'static [u8],
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+    ) -> Result<(), TxError> {
+        self.tx_ring
+            .send_static(frame, packet_id, checksum_insertion)
+    }
+
     /// Try to send a packet with data.
     ///
     /// If there are no free TX slots, this function will
     /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// No checksums are inserted by the hardware. Use
+    /// [`EthernetDMA::send_with_checksum`] to opt into checksum insertion.
     pub fn send<F>(
         &mut self,
         length: usize,
         packet_id: Option<PacketId>,
         f: F,
     ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        self.send_with_checksum(length, packet_id, ChecksumInsertion::None, f)
+    }
+
+    /// Try to send a packet with data, and have the hardware insert
+    /// the checksums selected by `checksum_insertion` into it.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// See [`TxPacket::set_checksum_insertion`].
+    pub fn send_with_checksum<F>(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+        f: F,
+    ) -> Result<(), TxError>
     where
         F: FnOnce(&mut [u8]),
     {
         let mut tx_packet = self.tx_ring.send_next(length, packet_id)?;
+        tx_packet.set_checksum_insertion(checksum_insertion);
         f(&mut tx_packet);
         tx_packet.send();
         Ok(())
     }
 
+    /// Try to send a packet with data, with the CRC and padding inserted
+    /// by the MAC as selected by `crc_pad_control`.
+    ///
+    /// Use this to send frames that already contain their padding or CRC,
+    /// or deliberately undersized test frames. No checksums are inserted by
+    /// the hardware.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// See [`CrcPadControl`] for the amount of bytes that are transmitted.
+    pub fn send_with_crc_pad_control<F>(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        crc_pad_control: CrcPadControl,
+        f: F,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut tx_packet = self.tx_ring.send_next(length, packet_id)?;
+        tx_packet.set_crc_pad_control(crc_pad_control);
+        f(&mut tx_packet);
+        tx_packet.send();
+        Ok(())
+    }
+
+    /// Try to send a packet with data, with the VLAN tags `outer` (e.g. an S-tag)
+    /// and `inner` (e.g. a C-tag) inserted after its source address (Q-in-Q).
+    ///
+    /// `f` writes the untagged frame, which is `length` bytes long, and
+    /// at least as long as the destination and source addresses (12 bytes). The
+    /// sent frame is `2 * VlanTag::LEN` bytes longer.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// # VLAN tag insertion
+    /// None of the supported MACs (STM32F107, STM32F4 and STM32F7) can insert
+    /// VLAN tags into transmitted frames: their descriptors have no VLAN fields,
+    /// and there are no context descriptors. The VLAN tag register only applies
+    /// to received frames. The tags are therefore written by software, which only
+    /// moves the 12 address bytes of the frame. Checksums are not inserted by the
+    /// hardware, as its checksum offload engine only supports frames with a
+    /// single VLAN tag.
+    ///
+    /// # Panics
+    /// This function panics if `length` is shorter than 12 bytes, or if the
+    /// tagged frame does not fit into a TX buffer.
+    pub fn send_with_vlans<F>(
+        &mut self,
+        outer: VlanTag,
+        inner: VlanTag,
+        length: usize,
+        packet_id: Option<PacketId>,
+        f: F,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        const ADDRESSES_LEN: usize = 12;
+        const TAGS_LEN: usize = 2 * VlanTag::LEN;
+
+        assert!(
+            length >= ADDRESSES_LEN,
+            "A frame must hold at least the destination and source addresses"
+        );
+
+        self.send(length + TAGS_LEN, packet_id, |buffer| {
+            f(&mut buffer[TAGS_LEN..]);
+
+            buffer.copy_within(TAGS_LEN..TAGS_LEN + ADDRESSES_LEN, 0);
+            buffer[ADDRESSES_LEN..ADDRESSES_LEN + VlanTag::LEN].copy_from_slice(&outer.to_bytes());
+            buffer[ADDRESSES_LEN + VlanTag::LEN..ADDRESSES_LEN + TAGS_LEN]
+                .copy_from_slice(&inner.to_bytes());
+        })
+    }
+
+    /// Start building a frame in the buffer of the next TX entry, writing its
+    /// data in multiple steps.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// See [`TxRing::build_frame`].
+    pub fn build_frame(
+        &mut self,
+        packet_id: Option<PacketId>,
+    ) -> Result<TxFrameBuilder<'_, 'tx>, TxError> {
+        self.tx_ring.build_frame(packet_id)
+    }
+
+    /// Send a packet with data, waiting for a TX slot to become available
+    /// if necessary.
+    ///
+    /// While no TX slot is available, `yield_fn` is called before checking
+    /// again. It can be used to wait for an interrupt (for instance using
+    /// [`cortex_m::asm::wfi`]), or to perform other work in the meantime. Slots
+    /// become available as soon as the DMA has transmitted the frames in them.
+    ///
+    /// If `max_attempts` is `Some`, at most that many attempts (but at least
+    /// one) are made to obtain a TX slot, and
+    /// [`Err(TxError::WouldBlock)`](TxError::WouldBlock) is returned if
+    /// none became available. Otherwise, this function blocks
+    /// until a slot is available, which is forever if the TX DMA has stopped.
+    ///
+    /// No checksums are inserted by the hardware, as for [`EthernetDMA::send`].
+    pub fn send_blocking<F, Y>(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        max_attempts: Option<u32>,
+        f: F,
+        mut yield_fn: Y,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+        Y: FnMut(),
+    {
+        let mut attempts = 0u32;
+
+        loop {
+            if self.tx_available() {
+                return self.send(length, packet_id, f);
+            }
+
+            attempts = attempts.saturating_add(1);
+            if max_attempts.is_some_and(|max| attempts >= max) {
+                return Err(TxError::WouldBlock);
+            }
+
+            yield_fn();
+        }
+    }
+
+    /// Poll to check if the frame with the given ID has been
+    /// transmitted, and get its [`TxStatus`] if so.
+    ///
+    /// See [`TxRing::poll_completion`].
+    pub fn poll_tx_completion(
+        &self,
+        packet_id: &PacketId,
+    ) -> Poll<Result<TxStatus, PacketIdNotFound>> {
+        self.tx_ring.poll_completion(packet_id)
+    }
+
+    /// Get the [`TxStatus`] of the most recently sent frame, if it has
+    /// been transmitted.
+    ///
+    /// See [`TxRing::last_status`].
+    pub fn last_tx_status(&self) -> Option<TxStatus> {
+        self.tx_ring.last_status()
+    }
+
+    /// Make sure that the DMA processes the RX and TX rings, e.g. once
+    /// a link has been established.
+    ///
+    /// See [`EthernetMAC::establish_link`](crate::mac::EthernetMAC::establish_link).
+    pub(crate) fn resume(&mut self) {
+        self.rx_ring.resume();
+        self.tx_ring.resume();
+    }
+
     /// Check if there is a packet available for reading.
     ///
     /// If this function returns true, it is guaranteed that the
     /// next call to [`EthernetDMA::recv_next`] will return [`Ok`].
     pub fn rx_available(&mut self) -> bool {
+        self.rx_ring.discard_filtered();
         self.rx_ring.next_entry_available()
     }
 
@@ -260,12 +1382,152 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
     }
 }
 
+impl EthernetDMA<'static, '_> {
+    /// Attach `queue` to the RX ring of this [`EthernetDMA`].
+    ///
+    /// See [`RxQueue::attach`].
+    pub fn attach_rx_queue<const DEPTH: usize>(&self, queue: &RxQueue<DEPTH>) {
+        queue.attach(&self.rx_ring);
+    }
+}
+
+/// How [`EthernetDMA::send_at`] releases a frame at its launch time.
+#[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LaunchTimeSupport {
+    /// The frame is held in the TX ring, and the DMA is polled for it by
+    /// the PTP target time interrupt.
+    ///
+    /// The frame starts transmitting after the interrupt latency, the time
+    /// it takes the DMA to fetch the descriptor and to fill the TX FIFO with
+    /// the frame (all of it, in store-and-forward mode), and the preamble.
+    /// This usually amounts to several microseconds, and varies with the load
+    /// on the bus matrix, other (higher priority) interrupts, and the size of
+    /// the frame. [`bench::loopback_rtt`] can be used to measure the part of
+    /// this delay that is caused by the DMA and the MAC.
+    Software,
+}
+
+#[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+impl EthernetDMA<'_, '_> {
+    /// How frames sent with [`EthernetDMA::send_at`] are released at their
+    /// launch time on this MCU.
+    ///
+    /// None of the MACs currently supported by this crate (STM32F4 and
+    /// STM32F7) support launch times in hardware, so this is always
+    /// [`LaunchTimeSupport::Software`].
+    pub const fn launch_time_support() -> LaunchTimeSupport {
+        LaunchTimeSupport::Software
+    }
+
+    /// Prepare a frame with `f`, and transmit it once the PTP time reaches
+    /// `launch_time`.
+    ///
+    /// If `launch_time` has already passed, the frame is sent immediately.
+    /// Otherwise, it is held in the TX ring, and the PTP target time interrupt
+    /// is configured for `launch_time` (see
+    /// [`EthernetPTP::configure_target_time_interrupt`]). The frame is then
+    /// released by [`EthernetPTP::interrupt_handler`], which must be called in
+    /// the `ETH` interrupt (as is done by
+    /// [`eth_interrupt_handler`](crate::eth_interrupt_handler)). See
+    /// [`LaunchTimeSupport`] for the accuracy of the launch time.
+    ///
+    /// The frame can only be held once all other frames have been
+    /// transmitted, and no other frames can be sent while it waits for its
+    /// launch time. As there is only one target time, the target time must
+    /// not be used for anything else (such as [`EthernetPTP::wait_until`]) in
+    /// the meantime. If frames are still waiting to be transmitted, or if a
+    /// frame already waits for its launch time (see
+    /// [`EthernetDMA::launch_pending`]), this function returns
+    /// [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// The frame is dropped if the DMA is stopped before its launch time,
+    /// e.g. by [`EthernetDMA::prepare_for_sleep`] or
+    /// [`EthernetDMA::set_config`].
+    ///
+    /// No checksums are inserted by the hardware.
+    pub fn send_at<F>(
+        &mut self,
+        ptp: &mut EthernetPTP,
+        launch_time: Timestamp,
+        length: usize,
+        packet_id: Option<PacketId>,
+        f: F,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        if self.tx_ring.launch_pending() {
+            return Err(TxError::WouldBlock);
+        }
+
+        if EthernetPTP::get_time().raw() >= launch_time.raw() {
+            return self.send(length, packet_id, f);
+        }
+
+        self.tx_ring.send_held(length, packet_id, f)?;
+
+        ptp.configure_target_time_interrupt(launch_time);
+
+        // The target time may have passed before the interrupt was
+        // configured.
+        if EthernetPTP::get_time().raw() >= launch_time.raw() {
+            TxRing::demand_poll();
+        }
+
+        Ok(())
+    }
+
+    /// Check whether a frame sent with [`EthernetDMA::send_at`] still waits
+    /// for its launch time, or is being transmitted.
+    pub fn launch_pending(&self) -> bool {
+        self.tx_ring.launch_pending()
+    }
+}
+
+impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
+    /// Stop all DMA actions, and release all buffers acquired from a
+    /// [`BufferProvider`].
+    //
+    // The buffers must not be released while the DMA may still access
+    // them, so we keep waiting for it to stop. After a fatal bus error,
+    // the DMA no longer accesses memory at all.
+    fn shut_down(&mut self) {
+        while let Err(DmaError::SuspendTimeout) = self.tx_ring.stop(&self.eth_dma) {}
+
+        while let Err(DmaError::SuspendTimeout) = self.rx_ring.stop(&self.eth_dma) {}
+
+        self.rx_ring.release_buffers();
+    }
+
+    /// Stop the DMA, and release the DMA peripheral and the entries
+    /// of both rings.
+    ///
+    /// See [`Parts::release`](crate::Parts::release).
+    pub(crate) fn release(self) -> (ETHERNET_DMA, &'rx mut [RxRingEntry], &'tx mut [TxRingEntry]) {
+        let mut me = core::mem::ManuallyDrop::new(self);
+        me.shut_down();
+
+        // SAFETY: `me` is never used or dropped afterwards, so every
+        // field is moved out exactly once.
+        let (eth_dma, rx_ring, tx_ring) = unsafe {
+            (
+                core::ptr::read(&me.eth_dma),
+                core::ptr::read(&me.rx_ring),
+                core::ptr::read(&me.tx_ring),
+            )
+        };
+
+        (eth_dma, rx_ring.into_entries(), tx_ring.into_entries())
+    }
+}
+
 impl Drop for EthernetDMA<'_, '_> {
     // On drop, stop all DMA actions.
     fn drop(&mut self) {
-        self.tx_ring.stop(&self.eth_dma);
-
-        self.rx_ring.stop(&self.eth_dma);
+        self.shut_down();
     }
 }
 
@@ -339,6 +1601,9 @@ impl EthernetDMA<'_, '_> {
     }
 
     /// Get the RX timestamp for the given packet ID.
+    ///
+    /// If multiple frames were received with the same packet ID, this is the
+    /// timestamp of the most recent one, see [`RxRing::timestamp`].
     pub fn rx_timestamp(
         &self,
         packet_id: &PacketId,
@@ -373,6 +1638,14 @@ impl EthernetDMA<'_, '_> {
         self.tx_ring.timestamp(packet_id).await
     }
 
+    /// Take the timestamps of all transmitted frames for which a timestamp
+    /// is available.
+    ///
+    /// See [`TxRing::drain_tx_timestamps`].
+    pub fn drain_tx_timestamps(&mut self) -> impl Iterator<Item = (PacketId, Timestamp)> + '_ {
+        self.tx_ring.drain_tx_timestamps()
+    }
+
     /// Get the next packet ID.
     pub fn next_packet_id(&mut self) -> PacketId {
         let id = PacketId(self.packet_id_counter);
@@ -392,4 +1665,98 @@ pub struct InterruptReasonSummary {
     pub is_tx: bool,
     /// The interrupt was caused by an error event.
     pub is_error: bool,
+    /// The interrupt was caused by an early receive event.
+    ///
+    /// See [`EthernetDMA::set_early_interrupts`].
+    pub is_early_rx: bool,
+    /// The interrupt was caused by an early transmit event.
+    ///
+    /// See [`EthernetDMA::set_early_interrupts`].
+    pub is_early_tx: bool,
+    /// The TX DMA was suspended because of a TX underflow.
+    ///
+    /// See [`EthernetDMA::set_tx_underflow_recovery`].
+    pub is_tx_underflow: bool,
+}
+
+/// The fill level of the RX FIFO of the MAC.
+///
+/// See [`EthernetDMA::rx_fifo_level`].
+///
+/// The thresholds are the thresholds for activating and deactivating
+/// hardware flow control.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxFifoLevel {
+    /// The RX FIFO is empty.
+    Empty,
+    /// The fill level is below the flow control deactivation threshold.
+    BelowDeactivationThreshold,
+    /// The fill level is above the flow control activation threshold.
+    AboveActivationThreshold,
+    /// The RX FIFO is full.
+    Full,
+}
+
+/// The counts of dropped received frames, read from the missed frame and
+/// buffer overflow counter register of the DMA.
+///
+/// See [`EthernetDMA::missed_frame_counter`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissedFrameCounts {
+    /// The amount of frames that were dropped because no RX descriptor
+    /// was available.
+    pub buffer_unavailable: u16,
+    /// `buffer_unavailable` overflowed, so more frames than reported
+    /// were dropped.
+    pub buffer_unavailable_overflow: bool,
+    /// The amount of frames that were dropped because the RX FIFO
+    /// overflowed (11 bits).
+    pub fifo_overflow: u16,
+    /// `fifo_overflow` overflowed, so more frames than reported
+    /// were dropped.
+    pub fifo_overflow_overflow: bool,
+}
+
+/// A snapshot of the statistics of the ethernet peripheral.
+///
+/// See [`EthernetDMA::stats`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthStats {
+    /// The frame counters of the MAC.
+    ///
+    /// These are not cleared by reading them.
+    pub mmc: MmcCounters,
+    /// The frames that were dropped by the DMA since the previous call
+    /// to [`EthernetDMA::stats`] or [`EthernetDMA::missed_frame_counter`].
+    ///
+    /// These are cleared by reading them.
+    pub missed_frames: MissedFrameCounts,
+    /// See [`RxRing::low_water_mark`].
+    pub rx_low_water_mark: usize,
+    /// See [`TxRing::high_water_mark`].
+    pub tx_high_water_mark: usize,
+    /// The speed and duplex mode that the MAC is configured for.
+    ///
+    /// This is not necessarily the state of the link: see
+    /// [`EthernetMAC::establish_link`].
+    pub speed: Speed,
+}
+
+/// The addresses of the descriptors and buffers that the DMA
+/// was processing when [`EthernetDMA::progress_markers`] was called.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressMarkers {
+    /// The address of the current RX descriptor.
+    pub rx_descriptor: u32,
+    /// The address of the current RX buffer.
+    pub rx_buffer: u32,
+    /// The address of the current TX descriptor.
+    pub tx_descriptor: u32,
+    /// The address of the current TX buffer.
+    pub tx_buffer: u32,
 }
@@ -0,0 +1,123 @@
+//! Configuring the memory used by the DMA as non-cacheable on the
+//! STM32F7.
+//!
+//! The Cortex-M7 core of the STM32F7 has a data cache, which the Ethernet
+//! DMA does not observe. If the data cache is enabled, and the descriptors and
+//! buffers of the [`RxRing`](super::RxRing) and [`TxRing`](super::TxRing)
+//! reside in cacheable memory, the CPU and the DMA may see different contents
+//! of the same memory.
+//!
+//! The simplest way to avoid this is to place all [`RxRingEntry`](super::RxRingEntry)s
+//! and [`TxRingEntry`](super::TxRingEntry)s in a dedicated memory region (for
+//! instance, using a `#[link_section]` that the linker script places at a
+//! suitably aligned address), and to configure that region as non-cacheable
+//! using [`configure_noncacheable_region`]. The data cache does not have to be
+//! cleaned or invalidated for this region afterwards.
+//!
+//! This is independent of the data cache itself: this module never enables,
+//! disables, cleans or invalidates it.
+
+use cortex_m::peripheral::MPU;
+
+/// Enable the MPU.
+const MPU_CTRL_ENABLE: u32 = 1 << 0;
+/// Use the default memory map for all memory not covered by a region.
+const MPU_CTRL_PRIVDEFENA: u32 = 1 << 2;
+
+/// Instruction fetches are not allowed.
+const MPU_RASR_XN: u32 = 1 << 28;
+/// Full access for privileged and unprivileged code.
+const MPU_RASR_AP_FULL_ACCESS: u32 = 0b011 << 24;
+/// Normal memory, non-cacheable (TEX = 0b001, C = 0, B = 0).
+const MPU_RASR_TEX_NORMAL_NONCACHEABLE: u32 = 0b001 << 19;
+/// Shareable
+const MPU_RASR_S: u32 = 1 << 18;
+/// Region size
+const MPU_RASR_SIZE_SHIFT: u32 = 1;
+/// Enable the region.
+const MPU_RASR_ENABLE: u32 = 1 << 0;
+
+/// The smallest size of an MPU region.
+const MIN_REGION_SIZE: u32 = 32;
+
+/// Errors that can occur while configuring an MPU region.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MpuRegionError {
+    /// The size of the region is not a power of two of at least 32 bytes.
+    InvalidSize,
+    /// The base address of the region is not aligned to its size.
+    Misaligned,
+    /// The MPU does not have a region with the given number.
+    InvalidRegion,
+}
+
+/// Configure MPU region `region` to cover the `size` bytes starting at
+/// `base` as shareable, non-cacheable normal memory, and enable the MPU.
+///
+/// `size` must be a power of two of at least 32 bytes, and `base` must be
+/// aligned to `size`. Instruction fetches from the region are not allowed.
+///
+/// If the MPU is not enabled yet, it is enabled with the default memory map
+/// for all memory that is not covered by a region, so this does not change
+/// the attributes of any other memory. If the MPU is enabled already, its
+/// configuration is left unchanged apart from `region`. Regions with a
+/// higher number take precedence over regions with a lower number where
+/// they overlap.
+///
+/// This must be called before the memory in the region is used by the
+/// DMA (that is, before calling [`new`](crate::new) or
+/// [`new_with_mii`](crate::new_with_mii)).
+pub fn configure_noncacheable_region(
+    mpu: &mut MPU,
+    region: u8,
+    base: u32,
+    size: u32,
+) -> Result<(), MpuRegionError> {
+    if !size.is_power_of_two() || size < MIN_REGION_SIZE {
+        return Err(MpuRegionError::InvalidSize);
+    }
+
+    if base & (size - 1) != 0 {
+        return Err(MpuRegionError::Misaligned);
+    }
+
+    let regions = (mpu._type.read() >> 8) & 0xFF;
+    if region as u32 >= regions {
+        return Err(MpuRegionError::InvalidRegion);
+    }
+
+    // The SIZE field encodes a region size of 2^(SIZE + 1) bytes.
+    let size_field = size.trailing_zeros() - 1;
+
+    let rasr = MPU_RASR_XN
+        | MPU_RASR_AP_FULL_ACCESS
+        | MPU_RASR_TEX_NORMAL_NONCACHEABLE
+        | MPU_RASR_S
+        | (size_field << MPU_RASR_SIZE_SHIFT)
+        | MPU_RASR_ENABLE;
+
+    // Ensure that all outstanding memory accesses use the old configuration.
+    cortex_m::asm::dmb();
+
+    // SAFETY: the region only changes the attributes of the memory it covers,
+    // and makes it accessible to all code. The default memory map stays in
+    // effect for all other memory.
+    unsafe {
+        mpu.rnr.write(region as u32);
+        mpu.rbar.write(base);
+        mpu.rasr.write(rasr);
+
+        let ctrl = mpu.ctrl.read();
+        if ctrl & MPU_CTRL_ENABLE != MPU_CTRL_ENABLE {
+            mpu.ctrl.write(MPU_CTRL_ENABLE | MPU_CTRL_PRIVDEFENA);
+        }
+    }
+
+    // Ensure that all following memory accesses and instruction fetches
+    // use the new configuration.
+    cortex_m::asm::dsb();
+    cortex_m::asm::isb();
+
+    Ok(())
+}
@@ -47,6 +47,8 @@ impl<T: RingDescriptor + Default> Default for RingEntry<T> {
 
 impl RingEntry<TxDescriptor> {
     /// The initial value of a TxRingDescriptor
+    // NOTE(allow): `INIT` is meant to be copied into arrays of entries.
+    #[allow(clippy::declare_interior_mutable_const)]
     pub const INIT: Self = Self::new();
 
     /// Creates a RingEntry with a TxDescriptor.
@@ -98,4 +100,36 @@ impl<T: RingDescriptor> RingEntry<T> {
     pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
         &mut (*self.buffer)[..]
     }
+
+    /// Describe the position of the entry with `index` for a dump of
+    /// `entries`, given the index of the next entry that the CPU uses and
+    /// the address of the current descriptor of the DMA.
+    #[cfg(feature = "defmt")]
+    pub(crate) fn dump_marker(
+        entries: &[Self],
+        index: usize,
+        next_entry: usize,
+        dma_addr: u32,
+    ) -> &'static str {
+        let is_dma = Self::index_of(entries, dma_addr) == Some(index);
+        match (index == next_entry, is_dma) {
+            (true, true) => " <- next, DMA",
+            (true, false) => " <- next",
+            (false, true) => " <- DMA",
+            (false, false) => "",
+        }
+    }
+
+    /// Get the index of the entry in `entries` whose descriptor is
+    /// located at `addr`, if any.
+    pub(crate) fn index_of(entries: &[Self], addr: u32) -> Option<usize> {
+        let offset = (addr as usize).checked_sub(entries.as_ptr() as usize)?;
+        let index = offset / core::mem::size_of::<Self>();
+
+        if offset % core::mem::size_of::<Self>() == 0 && index < entries.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
 }
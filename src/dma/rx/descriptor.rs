@@ -1,9 +1,14 @@
 use crate::dma::{
     desc::Descriptor,
+    fence,
     ring::{RingDescriptor, RingEntry},
 };
 
-use crate::dma::PacketId;
+#[cfg(feature = "raw-descriptors")]
+use crate::dma::DESCRIPTOR_WORDS;
+use crate::dma::{PacketId, MTU};
+
+use core::ptr::NonNull;
 
 #[cfg(feature = "ptp")]
 use crate::ptp::Timestamp;
@@ -16,6 +21,9 @@ pub(crate) enum RxDescriptorError {
     Truncated,
     /// An error occured with the DMA
     DmaError,
+    /// The descriptor was modified while owned by the DMA
+    #[cfg(feature = "descriptor-sentinel")]
+    Corruption,
 }
 
 /// RX timestamp valid
@@ -30,10 +38,38 @@ const RXDESC_0_FS: u32 = 1 << 9;
 const RXDESC_0_LS: u32 = 1 << 8;
 /// Error summary
 const RXDESC_0_ES: u32 = 1 << 15;
+/// CRC error
+const RXDESC_0_CE: u32 = 1 << 1;
+/// Dribble bit error
+const RXDESC_0_DBE: u32 = 1 << 2;
+/// Receive error
+const RXDESC_0_RE: u32 = 1 << 3;
+/// Receive watchdog timeout
+const RXDESC_0_RWT: u32 = 1 << 4;
+/// Late collision
+const RXDESC_0_LCO: u32 = 1 << 6;
+/// Overflow error
+const RXDESC_0_OE: u32 = 1 << 11;
+/// Descriptor error
+const RXDESC_0_DE: u32 = 1 << 14;
+/// Errors that indicate that the frame data is not usable.
+const RXDESC_0_HARD_ERRORS: u32 =
+    RXDESC_0_RE | RXDESC_0_RWT | RXDESC_0_LCO | RXDESC_0_OE | RXDESC_0_DE;
+/// Frame type: the length/type field is greater than or equal to 0x600
+const RXDESC_0_FT: u32 = 1 << 5;
+/// The minimum length of a frame, including its FCS. Shorter frames are runts.
+const MIN_FRAME_LEN: usize = 64;
 /// Frame length
 const RXDESC_0_FL_MASK: u32 = 0x3FFF;
 const RXDESC_0_FL_SHIFT: usize = 16;
 
+/// VLAN tagged frame
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_0_VLAN: u32 = 1 << 10;
+/// Extended status available in RDES4
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_0_ESA: u32 = 1 << 0;
+
 const RXDESC_1_RBS_SHIFT: usize = 0;
 const RXDESC_1_RBS_MASK: u32 = 0x0fff << RXDESC_1_RBS_SHIFT;
 /// Second address chained
@@ -41,17 +77,122 @@ const RXDESC_1_RCH: u32 = 1 << 14;
 /// End Of Ring
 const RXDESC_1_RER: u32 = 1 << 15;
 
+#[cfg(not(feature = "stm32f1xx-hal"))]
+mod extended_status {
+    /// IP payload type
+    pub const RXDESC_4_IPPT_MASK: u32 = 0b111;
+    /// IP header error
+    pub const RXDESC_4_IPHE: u32 = 1 << 3;
+    /// IP payload error
+    pub const RXDESC_4_IPPE: u32 = 1 << 4;
+    /// IPv4 packet received
+    pub const RXDESC_4_IPV4PR: u32 = 1 << 6;
+    /// IPv6 packet received
+    pub const RXDESC_4_IPV6PR: u32 = 1 << 7;
+    /// The offset of the PTP message type field
+    pub const RXDESC_4_PMT_SHIFT: u32 = 8;
+    /// The width of the PTP message type field
+    pub const RXDESC_4_PMT_MASK: u32 = 0b1111 << RXDESC_4_PMT_SHIFT;
+}
+#[cfg(not(feature = "stm32f1xx-hal"))]
+use extended_status::*;
+
+/// The type of the payload of a received IP packet, as
+/// determined by the checksum offload engine.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpPayloadType {
+    /// The payload is not UDP, TCP or ICMP, or the frame
+    /// is not an IP packet.
+    Unknown,
+    /// UDP
+    Udp,
+    /// TCP
+    Tcp,
+    /// ICMP
+    Icmp,
+}
+
+/// The type of a received PTP message, as recognized by the MAC.
+///
+/// The MAC reports the message types that an ordinary clock processes, as
+/// this crate leaves the clock node type of the MAC at its reset value
+/// (ordinary clock). Pdelay messages are only reported to peer-to-peer
+/// transparent clocks, in place of Announce, Management and Signaling
+/// messages, so they are never reported.
+///
+/// See [`RxPacket::ptp_message_type`](super::RxPacket::ptp_message_type).
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtpMessageType {
+    /// A Sync message.
+    Sync,
+    /// A Follow_Up message.
+    FollowUp,
+    /// A Delay_Req message.
+    DelayReq,
+    /// A Delay_Resp message.
+    DelayResp,
+    /// An Announce message.
+    Announce,
+    /// A Management message.
+    Management,
+    /// A Signaling message.
+    Signaling,
+    /// A PTP message type that the reference manual marks as reserved.
+    Reserved,
+}
+
+/// The extended status of a received frame, as reported by the
+/// checksum offload engine.
+///
+/// This status is only available on MCUs that use enhanced descriptors
+/// (STM32F4 and STM32F7). Unlike the descriptors of some other MACs, these
+/// do not report the length of the IP header or the presence of an inner
+/// VLAN tag.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtendedRxStatus {
+    /// The type of the IP payload.
+    pub payload_type: IpPayloadType,
+    /// The IP header checksum was invalid, or the IP header was malformed.
+    pub ip_header_error: bool,
+    /// The TCP, UDP or ICMP checksum was invalid, or the payload was truncated.
+    pub ip_payload_error: bool,
+    /// The frame contains an IPv4 packet.
+    pub ipv4: bool,
+    /// The frame contains an IPv6 packet.
+    pub ipv6: bool,
+    /// The frame has a VLAN tag.
+    pub vlan_tagged: bool,
+}
+
 #[repr(C)]
 /// An RX DMA Descriptor
 pub struct RxDescriptor {
     desc: Descriptor,
     buffer1: Option<u32>,
+    buffer1_len: usize,
+    /// The amount of padding to insert before frames that are received
+    /// once this descriptor is passed to the DMA again.
+    padding: usize,
+    /// The amount of padding before the frame that was received with
+    /// the current buffer address.
+    active_padding: usize,
+    external_buffer: Option<NonNull<[u8]>>,
     next_descriptor: Option<u32>,
     packet_id: Option<PacketId>,
     #[cfg(feature = "ptp")]
     cached_timestamp: Option<Timestamp>,
 }
 
+// SAFETY: the external buffer is exclusively owned by this descriptor
+// until it is replaced.
+unsafe impl Send for RxDescriptor {}
+
 impl Default for RxDescriptor {
     fn default() -> Self {
         Self::new()
@@ -64,6 +205,10 @@ impl RxDescriptor {
         Self {
             desc: Descriptor::new(),
             buffer1: None,
+            buffer1_len: 0,
+            padding: 0,
+            active_padding: 0,
+            external_buffer: None,
             next_descriptor: None,
             packet_id: None,
             #[cfg(feature = "ptp")]
@@ -83,19 +228,21 @@ impl RxDescriptor {
         self.write_buffer1();
         self.write_buffer2();
 
-        // "Preceding reads and writes cannot be moved past subsequent writes."
-        #[cfg(feature = "fence")]
-        core::sync::atomic::fence(core::sync::atomic::Ordering::Release);
-        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Release);
+        #[cfg(feature = "descriptor-sentinel")]
+        unsafe {
+            self.desc.write_sentinel();
+        }
+
+        fence::release();
 
         unsafe {
             self.desc.write(0, RXDESC_0_OWN);
         }
 
-        // Used to flush the store buffer as fast as possible to make the buffer available for the
-        // DMA.
-        #[cfg(feature = "fence")]
-        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+        // NOTE: the OWN bit must not be read back (e.g. to assert that the
+        // handoff succeeded): once it is written, the DMA may receive a
+        // frame into this descriptor and clear it again at any time.
+        fence::publish();
     }
 
     fn has_error(&self) -> bool {
@@ -112,7 +259,50 @@ impl RxDescriptor {
         (self.desc.read(0) & RXDESC_0_LS) == RXDESC_0_LS
     }
 
+    /// Check whether the frame has an alignment error (a dribble bit error,
+    /// optionally accompanied by the resulting CRC error), but no other errors.
+    ///
+    /// The data of such frames is intact, except for some bits at the end.
+    fn has_alignment_error_only(&self) -> bool {
+        let rdes0 = self.desc.read(0);
+        rdes0 & RXDESC_0_DBE == RXDESC_0_DBE && rdes0 & RXDESC_0_HARD_ERRORS == 0
+    }
+
+    /// Check whether the frame has a CRC error, optionally accompanied by an
+    /// alignment error, but no other errors.
+    fn has_crc_error_only(&self) -> bool {
+        let rdes0 = self.desc.read(0);
+        rdes0 & RXDESC_0_CE == RXDESC_0_CE && rdes0 & RXDESC_0_HARD_ERRORS == 0
+    }
+
+    /// Check whether the frame has a CRC error.
+    fn has_crc_error(&self) -> bool {
+        self.desc.read(0) & RXDESC_0_CE == RXDESC_0_CE
+    }
+
+    /// Check whether the frame is shorter than the minimum frame length.
+    fn is_runt(&self) -> bool {
+        self.get_frame_len() < MIN_FRAME_LEN
+    }
+
+    /// Check whether the frame has a dribble bit error.
+    fn has_dribble_bit_error(&self) -> bool {
+        self.desc.read(0) & RXDESC_0_DBE == RXDESC_0_DBE
+    }
+
     /// Get PTP timestamps if available
+    ///
+    /// The supported MACs never write separate context descriptors: the
+    /// DMA writes the timestamp of a frame into the descriptor that holds
+    /// the last segment of the frame, when it closes that descriptor.
+    /// Every received frame therefore uses exactly one descriptor:
+    ///
+    /// * On STM32F1, the timestamp replaces the buffer addresses in
+    ///   RDES2 and RDES3, so the buffer addresses are restored before the
+    ///   descriptor is passed back to the DMA.
+    /// * On STM32F4 and STM32F7, the enhanced descriptor format is used, and
+    ///   the timestamp is written into RDES6 and RDES7. RDES0 indicates whether
+    ///   the timestamp is valid.
     #[cfg(feature = "ptp")]
     pub fn timestamp(&self) -> Option<Timestamp> {
         #[cfg(not(feature = "stm32f1xx-hal"))]
@@ -138,24 +328,40 @@ impl RxDescriptor {
     ///
     /// This only has to be done on stm32f107. For f4 and f7, enhanced descriptors
     /// must be enabled for timestamping support, which we enable by default.
+    ///
+    /// The configured padding is applied to the address and the length
+    /// of the buffer.
+    ///
+    /// The MACs of the supported MCUs have no DMA register that holds the size
+    /// of the RX buffers (like the RBSZ field of newer MACs): the size of every
+    /// buffer is programmed into the RBS1 field of its own descriptor, here.
+    /// The buffers of a ring may therefore have different sizes (see
+    /// [`RegionBufferProvider`](super::RegionBufferProvider)).
     fn write_buffer1(&mut self) {
         let buffer_addr = self
             .buffer1
             .expect("Writing buffer1 of an RX descriptor, but `buffer_address` is None");
 
+        self.active_padding = self.padding;
+        let len = self.buffer1_len.saturating_sub(self.active_padding);
+        debug_assert!(
+            len as u32 <= RXDESC_1_RBS_MASK >> RXDESC_1_RBS_SHIFT,
+            "RX buffer length {} does not fit into RBS1",
+            len
+        );
+
         unsafe {
-            self.desc.write(2, buffer_addr);
+            self.desc.write(2, buffer_addr + self.active_padding as u32);
+            self.desc.modify(1, |w| {
+                (w & !RXDESC_1_RBS_MASK) | ((len as u32) << RXDESC_1_RBS_SHIFT)
+            });
         }
     }
 
     fn set_buffer1(&mut self, buffer: *const u8, len: usize) {
         self.buffer1 = Some(buffer as u32);
+        self.buffer1_len = len;
         self.write_buffer1();
-        unsafe {
-            self.desc.modify(1, |w| {
-                (w & !RXDESC_1_RBS_MASK) | ((len as u32) << RXDESC_1_RBS_SHIFT)
-            });
-        }
     }
 
     /// Rewrite buffer2 to the last value we wrote it to
@@ -189,6 +395,60 @@ impl RxDescriptor {
     fn get_frame_len(&self) -> usize {
         ((self.desc.read(0) >> RXDESC_0_FL_SHIFT) & RXDESC_0_FL_MASK) as usize
     }
+
+    /// Get the extended status of the received frame, if the DMA
+    /// has written it back.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub(super) fn extended_status(&self) -> Option<ExtendedRxStatus> {
+        let rdes0 = self.desc.read(0);
+
+        if rdes0 & RXDESC_0_ESA != RXDESC_0_ESA {
+            return None;
+        }
+
+        let rdes4 = self.desc.read(4);
+
+        let payload_type = match rdes4 & RXDESC_4_IPPT_MASK {
+            0b001 => IpPayloadType::Udp,
+            0b010 => IpPayloadType::Tcp,
+            0b011 => IpPayloadType::Icmp,
+            _ => IpPayloadType::Unknown,
+        };
+
+        Some(ExtendedRxStatus {
+            payload_type,
+            ip_header_error: rdes4 & RXDESC_4_IPHE == RXDESC_4_IPHE,
+            ip_payload_error: rdes4 & RXDESC_4_IPPE == RXDESC_4_IPPE,
+            ipv4: rdes4 & RXDESC_4_IPV4PR == RXDESC_4_IPV4PR,
+            ipv6: rdes4 & RXDESC_4_IPV6PR == RXDESC_4_IPV6PR,
+            vlan_tagged: rdes0 & RXDESC_0_VLAN == RXDESC_0_VLAN,
+        })
+    }
+
+    /// Get the type of the PTP message in the received frame, if the DMA
+    /// has written back the extended status and the MAC recognized one.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub(super) fn ptp_message_type(&self) -> Option<PtpMessageType> {
+        if self.desc.read(0) & RXDESC_0_ESA != RXDESC_0_ESA {
+            return None;
+        }
+
+        let message_type = match (self.desc.read(4) & RXDESC_4_PMT_MASK) >> RXDESC_4_PMT_SHIFT {
+            0b0000 => return None,
+            0b0001 => PtpMessageType::Sync,
+            0b0010 => PtpMessageType::FollowUp,
+            0b0011 => PtpMessageType::DelayReq,
+            0b0100 => PtpMessageType::DelayResp,
+            // Pdelay_Req, Pdelay_Resp and Pdelay_Resp_Follow_Up in a
+            // peer-to-peer transparent clock.
+            0b0101 => PtpMessageType::Announce,
+            0b0110 => PtpMessageType::Management,
+            0b0111 => PtpMessageType::Signaling,
+            _ => PtpMessageType::Reserved,
+        };
+
+        Some(message_type)
+    }
 }
 
 /// An RX DMA Ring Descriptor entry
@@ -200,7 +460,13 @@ impl RingDescriptor for RxDescriptor {
         unsafe {
             self.desc.write(1, RXDESC_1_RCH);
         }
-        self.set_buffer1(buffer, len);
+        // Keep using a buffer provided by a `BufferProvider`, if any.
+        match self.external_buffer {
+            Some(external) => {
+                self.set_buffer1(external.as_ptr() as *const u8, external.len().min(MTU))
+            }
+            None => self.set_buffer1(buffer, len),
+        }
         match next {
             Some(next) => self.set_buffer2(&next.desc as *const Descriptor as *const u8),
             None => {
@@ -221,16 +487,85 @@ impl RxRingEntry {
         !self.desc().is_owned()
     }
 
+    /// Log the decoded state of this entry, its raw descriptor, and the
+    /// first `bytes` bytes of its buffer.
+    #[cfg(feature = "defmt")]
+    pub(super) fn dump(&self, index: usize, marker: &str, bytes: usize) {
+        let desc = self.desc();
+        let buffer = self.buffer();
+
+        defmt::info!(
+            "RX {=usize}{=str}: owned={=bool} first={=bool} last={=bool} error={=bool} len={=usize} desc={:#010x} data={=[u8]:02x}",
+            index,
+            marker,
+            desc.is_owned(),
+            desc.is_first(),
+            desc.is_last(),
+            desc.has_error(),
+            desc.get_frame_len(),
+            desc.desc.words(),
+            buffer[..bytes.min(buffer.len())]
+        );
+    }
+
+    /// Check whether the entry at `entry` is available, without creating
+    /// a reference to it.
+    ///
+    /// # Safety
+    /// `entry` must point to a valid [`RxRingEntry`].
+    pub(super) unsafe fn is_available_raw(entry: *const Self) -> bool {
+        // NOTE: the first word of the descriptor is at the start of the
+        // entry, as all of the involved types are `repr(C)`.
+        (core::ptr::read_volatile(entry as *const u32) & RXDESC_0_OWN) == 0
+    }
+
     /// Only call this if [`RxRingEntry::is_available`]
-    pub(super) fn recv(&mut self, packet_id: Option<PacketId>) -> Result<usize, RxDescriptorError> {
-        if self.desc().has_error() {
+    ///
+    /// If `deliver_soft_errors` is set, frames with an alignment error are
+    /// returned instead of being discarded. If `deliver_crc_errors` is set, frames with a CRC error are returned
+    /// instead of being discarded.
+    ///
+    /// The timestamp of a returned frame is read before this function returns,
+    /// regardless of its errors.
+    ///
+    /// The MACs of the supported MCUs only write the status of a frame into
+    /// the descriptor that holds its last segment (LS set). They have no
+    /// per-descriptor status valid indicators (the RS0V, RS1V and RS2V bits
+    /// of newer MACs), so the status of all other descriptors is never read.
+    pub(super) fn recv(
+        &mut self,
+        packet_id: Option<PacketId>,
+        deliver_soft_errors: bool,
+        deliver_crc_errors: bool,
+    ) -> Result<usize, RxDescriptorError> {
+        #[cfg(feature = "descriptor-sentinel")]
+        if !self.desc().desc.sentinel_intact() {
+            #[cfg(feature = "defmt")]
+            defmt::error!(
+                "RX descriptor at {=u32:#010x} was modified while owned by the DMA",
+                &self.desc().desc as *const Descriptor as u32
+            );
+
+            self.desc_mut().set_owned();
+            return Err(RxDescriptorError::Corruption);
+        }
+
+        // The status of a frame (the error bits and the frame length) is only
+        // valid in the descriptor that holds its last segment. Frames that span
+        // more than one descriptor are discarded without reading the status of
+        // their other descriptors, which may be stale.
+        if !(self.desc().is_first() && self.desc().is_last()) {
+            self.desc_mut().set_owned();
+            return Err(RxDescriptorError::Truncated);
+        }
+
+        if !self.is_deliverable(deliver_soft_errors, deliver_crc_errors) {
             self.desc_mut().set_owned();
             Err(RxDescriptorError::DmaError)
-        } else if self.desc().is_first() && self.desc().is_last() {
+        } else {
             let frame_len = self.desc().get_frame_len();
 
-            // "Subsequent reads and writes cannot be moved ahead of preceding reads."
-            core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Acquire);
+            fence::acquire();
 
             #[cfg(feature = "ptp")]
             {
@@ -242,11 +577,175 @@ impl RxRingEntry {
             self.desc_mut().packet_id = packet_id;
 
             Ok(frame_len)
-        } else {
-            self.desc_mut().set_owned();
-            Err(RxDescriptorError::Truncated)
         }
     }
+
+    /// Get the length of the frame in this entry, if it is complete and would
+    /// be delivered by [`RxRingEntry::recv`], without modifying the entry.
+    ///
+    /// Only call this if [`RxRingEntry::is_available`]
+    pub(super) fn peek_len(
+        &self,
+        deliver_soft_errors: bool,
+        deliver_crc_errors: bool,
+    ) -> Option<usize> {
+        #[cfg(feature = "descriptor-sentinel")]
+        if !self.desc().desc.sentinel_intact() {
+            return None;
+        }
+
+        if !(self.desc().is_first() && self.desc().is_last()) {
+            return None;
+        }
+
+        if !self.is_deliverable(deliver_soft_errors, deliver_crc_errors) {
+            return None;
+        }
+
+        let frame_len = self.desc().get_frame_len();
+
+        fence::acquire();
+
+        Some(frame_len)
+    }
+
+    /// Check whether the complete frame in this entry has no errors, or only
+    /// errors that are tolerated according to `deliver_soft_errors` and
+    /// `deliver_crc_errors`.
+    ///
+    /// Runts need no exception: the MAC only forwards runts without errors
+    /// (see [`RxRing::set_accept_runt_frames`](super::RxRing::set_accept_runt_frames)).
+    fn is_deliverable(&self, deliver_soft_errors: bool, deliver_crc_errors: bool) -> bool {
+        let desc = self.desc();
+
+        !desc.has_error()
+            || (deliver_soft_errors && desc.has_alignment_error_only())
+            || (deliver_crc_errors && desc.has_crc_error_only())
+    }
+
+    /// Check whether the frame in this entry is shorter than the minimum
+    /// frame length of 64 bytes.
+    pub(super) fn is_runt(&self) -> bool {
+        self.desc().is_runt()
+    }
+
+    /// Check whether the frame in this entry has a CRC error.
+    pub(super) fn has_crc_error(&self) -> bool {
+        self.desc().has_crc_error()
+    }
+
+    /// Check whether the frame in this entry has a dribble bit (alignment) error.
+    pub(super) fn has_alignment_error(&self) -> bool {
+        self.desc().has_dribble_bit_error()
+    }
+
+    /// Check whether the length/type field of the frame in this entry
+    /// holds an EtherType (the Frame Type bit).
+    pub(super) fn is_ethertype_frame(&self) -> bool {
+        self.desc().desc.read(0) & RXDESC_0_FT == RXDESC_0_FT
+    }
+
+    /// Read the EtherType field of the frame in this entry.
+    ///
+    /// Returns `None` if this entry does not contain a complete,
+    /// error-free frame that is long enough to have an EtherType
+    /// field.
+    pub(super) fn ethertype(&self) -> Option<u16> {
+        let desc = self.desc();
+
+        if !self.is_available() || desc.has_error() || !(desc.is_first() && desc.is_last()) {
+            return None;
+        }
+
+        if desc.get_frame_len() < 14 {
+            return None;
+        }
+
+        fence::acquire();
+
+        let buffer = self.buffer();
+        Some(u16::from_be_bytes([buffer[12], buffer[13]]))
+    }
+
+    /// Check whether this entry uses a buffer acquired from a
+    /// `BufferProvider`.
+    pub(super) fn has_external_buffer(&self) -> bool {
+        self.desc().external_buffer.is_some()
+    }
+
+    /// The buffer that the DMA writes received frames to, starting at
+    /// the first byte of the frame.
+    pub(super) fn buffer(&self) -> &[u8] {
+        let padding = self.desc().active_padding;
+
+        match self.desc().external_buffer {
+            // SAFETY: the buffer is exclusively owned by this entry.
+            Some(external) => unsafe { &external.as_ref()[padding..] },
+            None => &self.as_slice()[padding..],
+        }
+    }
+
+    /// The buffer that the DMA writes received frames to, starting at
+    /// the first byte of the frame.
+    pub(super) fn buffer_mut(&mut self) -> &mut [u8] {
+        let padding = self.desc().active_padding;
+
+        match self.desc().external_buffer {
+            // SAFETY: the buffer is exclusively owned by this entry.
+            Some(mut external) => unsafe { &mut external.as_mut()[padding..] },
+            None => &mut self.as_mut_slice()[padding..],
+        }
+    }
+
+    /// The amount of padding before the frame in this entry.
+    pub(super) fn active_padding(&self) -> usize {
+        self.desc().active_padding
+    }
+
+    /// Insert `padding` bytes before all frames that are received once
+    /// this entry is passed to the DMA again.
+    pub(super) fn set_padding(&mut self, padding: usize) {
+        self.desc_mut().padding = padding;
+    }
+
+    /// Replace the buffer of this entry with `buffer`, or with the
+    /// buffer contained in this entry if `buffer` is `None`.
+    ///
+    /// Returns the previous buffer if it was not the buffer contained
+    /// in this entry.
+    ///
+    /// Only call this while the entry is not owned by the DMA. The new
+    /// buffer is only used by the DMA once [`RxDescriptor::set_owned`]
+    /// is called.
+    pub(super) fn replace_buffer(
+        &mut self,
+        buffer: Option<NonNull<[u8]>>,
+    ) -> Option<NonNull<[u8]>> {
+        let previous = core::mem::replace(&mut self.desc_mut().external_buffer, buffer);
+
+        let (addr, len) = match buffer {
+            Some(external) => (external.as_ptr() as *const u8, external.len().min(MTU)),
+            None => (self.as_slice().as_ptr(), self.as_slice().len()),
+        };
+        self.desc_mut().set_buffer1(addr, len);
+
+        previous
+    }
+
+    /// Pass this entry back to the DMA engine without delivering
+    /// the frame it contains.
+    pub(super) fn discard(&mut self) {
+        self.desc_mut().packet_id = None;
+        self.desc_mut().set_owned();
+    }
+}
+
+#[cfg(feature = "raw-descriptors")]
+impl RxRingEntry {
+    /// Read all words of this descriptor, as written back by the DMA.
+    pub(super) fn raw_words(&self) -> [u32; DESCRIPTOR_WORDS] {
+        self.desc().desc.words()
+    }
 }
 
 #[cfg(feature = "ptp")]
@@ -255,7 +754,142 @@ impl RxRingEntry {
         Some(id) == self.desc().packet_id.as_ref()
     }
 
+    /// The packet ID that the frame in this entry was received with.
+    pub(super) fn packet_id(&self) -> Option<&PacketId> {
+        self.desc().packet_id.as_ref()
+    }
+
+    /// Stop holding the packet ID of this entry.
+    ///
+    /// Returns `true` if a timestamp was held for it.
+    pub(super) fn forget_packet_id(&mut self) -> bool {
+        self.desc_mut().packet_id = None;
+        self.desc().cached_timestamp.is_some()
+    }
+
+    /// Stop holding the packet ID and the timestamp of this entry.
+    pub(super) fn forget_timestamp(&mut self) {
+        self.desc_mut().packet_id = None;
+        self.desc_mut().cached_timestamp = None;
+    }
+
     pub fn read_timestamp(&self) -> Option<Timestamp> {
         self.desc().cached_timestamp.clone()
     }
 }
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    fn entry(rdes0: u32) -> RxRingEntry {
+        let mut entry = RxRingEntry::new();
+        entry.setup(None);
+        unsafe { entry.desc_mut().desc.write(0, rdes0) };
+        entry
+    }
+
+    #[test]
+    fn status_only_from_single_descriptor_frames() {
+        let length = 60 << RXDESC_0_FL_SHIFT;
+
+        // The first and middle segments of a frame carry no valid status,
+        // even if stale error bits are set.
+        for rdes0 in [RXDESC_0_FS | RXDESC_0_ES | length, RXDESC_0_ES, 0] {
+            let mut entry = entry(rdes0);
+            assert_eq!(entry.peek_len(false, false), None);
+            assert_eq!(
+                entry.recv(None, false, false),
+                Err(RxDescriptorError::Truncated)
+            );
+        }
+
+        // The last segment carries the status of the whole frame, but the
+        // frame does not fit into a single descriptor.
+        let mut last = entry(RXDESC_0_LS | length);
+        assert_eq!(last.peek_len(false, false), None);
+        assert_eq!(
+            last.recv(None, false, false),
+            Err(RxDescriptorError::Truncated)
+        );
+
+        let mut single = entry(RXDESC_0_FS | RXDESC_0_LS | length);
+        assert_eq!(single.peek_len(false, false), Some(60));
+        assert_eq!(single.recv(None, false, false), Ok(60));
+
+        let mut error = entry(RXDESC_0_FS | RXDESC_0_LS | RXDESC_0_ES | RXDESC_0_OE | length);
+        assert_eq!(error.peek_len(false, false), None);
+        assert_eq!(
+            error.recv(None, false, false),
+            Err(RxDescriptorError::DmaError)
+        );
+    }
+
+    #[test]
+    fn runt_frames() {
+        // The MAC only forwards runts with a good CRC, and only if forwarding
+        // undersized good frames is enabled.
+        let mut runt = entry(RXDESC_0_FS | RXDESC_0_LS | 40 << RXDESC_0_FL_SHIFT);
+        assert!(runt.is_runt());
+        assert_eq!(runt.peek_len(false, false), Some(40));
+        assert_eq!(runt.recv(None, false, false), Ok(40));
+
+        let long = entry(RXDESC_0_FS | RXDESC_0_LS | 64 << RXDESC_0_FL_SHIFT);
+        assert!(!long.is_runt());
+    }
+
+    #[test]
+    fn crc_errors() {
+        let status = RXDESC_0_FS | RXDESC_0_LS | RXDESC_0_ES | RXDESC_0_CE;
+
+        let mut crc = entry(status | 100 << RXDESC_0_FL_SHIFT);
+        assert!(crc.has_crc_error());
+        assert_eq!(crc.peek_len(false, false), None);
+        assert_eq!(crc.peek_len(false, true), Some(100));
+        assert_eq!(crc.recv(None, false, true), Ok(100));
+
+        // Frames with hard errors are never delivered.
+        let mut overflow = entry(status | RXDESC_0_OE | 100 << RXDESC_0_FL_SHIFT);
+        assert_eq!(
+            overflow.recv(None, false, true),
+            Err(RxDescriptorError::DmaError)
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    fn ptp_message_types() {
+        let message_type = |rdes0: u32, pmt: u32| {
+            let mut entry = entry(rdes0);
+            unsafe { entry.desc_mut().desc.write(4, pmt << RXDESC_4_PMT_SHIFT) };
+            entry.desc().ptp_message_type()
+        };
+
+        let status = RXDESC_0_FS | RXDESC_0_LS | RXDESC_0_ESA;
+
+        assert_eq!(message_type(status, 0b0000), None);
+        assert_eq!(message_type(status, 0b0001), Some(PtpMessageType::Sync));
+        assert_eq!(message_type(status, 0b0010), Some(PtpMessageType::FollowUp));
+        assert_eq!(message_type(status, 0b0011), Some(PtpMessageType::DelayReq));
+        assert_eq!(
+            message_type(status, 0b0100),
+            Some(PtpMessageType::DelayResp)
+        );
+        assert_eq!(message_type(status, 0b0101), Some(PtpMessageType::Announce));
+        assert_eq!(
+            message_type(status, 0b0110),
+            Some(PtpMessageType::Management)
+        );
+        assert_eq!(
+            message_type(status, 0b0111),
+            Some(PtpMessageType::Signaling)
+        );
+
+        for pmt in 0b1000..=0b1111 {
+            assert_eq!(message_type(status, pmt), Some(PtpMessageType::Reserved));
+        }
+
+        // Without the extended status, RDES4 is not valid.
+        assert_eq!(message_type(status & !RXDESC_0_ESA, 0b0001), None);
+    }
+}
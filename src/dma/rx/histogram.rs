@@ -0,0 +1,111 @@
+//! A software histogram of the sizes of received frames.
+
+/// A range of frame sizes, as used by the RMON size-distribution
+/// counters (RFC 2819, `etherStatsPkts*Octets`).
+///
+/// Frames are bucketed by the length with which they are delivered by the
+/// [`RxRing`](super::RxRing). If CRC stripping is enabled (see
+/// [`EthernetMAC::set_crc_stripping`](crate::mac::EthernetMAC::set_crc_stripping)),
+/// this length does not include the FCS, so it is 4 bytes shorter than the
+/// length counted by RMON, and a frame of the minimum length (64 bytes,
+/// including the FCS) is counted in [`SizeBucket::UpTo64`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeBucket {
+    /// Frames of up to 64 bytes.
+    UpTo64,
+    /// Frames of 65 to 127 bytes.
+    UpTo127,
+    /// Frames of 128 to 255 bytes.
+    UpTo255,
+    /// Frames of 256 to 511 bytes.
+    UpTo511,
+    /// Frames of 512 to 1023 bytes.
+    UpTo1023,
+    /// Frames of 1024 to 1518 bytes.
+    UpTo1518,
+    /// Frames longer than 1518 bytes (e.g. frames with VLAN tags, or jumbo
+    /// frames).
+    Jumbo,
+}
+
+impl SizeBucket {
+    /// All buckets, from the shortest to the longest frames.
+    pub const ALL: [SizeBucket; 7] = [
+        SizeBucket::UpTo64,
+        SizeBucket::UpTo127,
+        SizeBucket::UpTo255,
+        SizeBucket::UpTo511,
+        SizeBucket::UpTo1023,
+        SizeBucket::UpTo1518,
+        SizeBucket::Jumbo,
+    ];
+
+    /// Get the bucket that a frame of `length` bytes is counted in.
+    pub const fn of(length: usize) -> Self {
+        match length {
+            0..=64 => SizeBucket::UpTo64,
+            65..=127 => SizeBucket::UpTo127,
+            128..=255 => SizeBucket::UpTo255,
+            256..=511 => SizeBucket::UpTo511,
+            512..=1023 => SizeBucket::UpTo1023,
+            1024..=1518 => SizeBucket::UpTo1518,
+            _ => SizeBucket::Jumbo,
+        }
+    }
+}
+
+/// The amount of received frames in every [`SizeBucket`].
+///
+/// See [`RxRing::size_histogram`](super::RxRing::size_histogram).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SizeHistogram {
+    counts: [u32; SizeBucket::ALL.len()],
+}
+
+impl SizeHistogram {
+    /// Get the amount of frames counted in `bucket`.
+    ///
+    /// The counters saturate at [`u32::MAX`].
+    pub fn get(&self, bucket: SizeBucket) -> u32 {
+        self.counts[bucket as usize]
+    }
+
+    /// Iterate over all buckets and their amount of frames, from the
+    /// shortest to the longest frames.
+    pub fn iter(&self) -> impl Iterator<Item = (SizeBucket, u32)> + '_ {
+        SizeBucket::ALL
+            .iter()
+            .map(move |&bucket| (bucket, self.get(bucket)))
+    }
+
+    /// The amount of frames counted in all buckets.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&count| u64::from(count)).sum()
+    }
+
+    /// Count a frame of `length` bytes.
+    pub(crate) fn record(&mut self, length: usize) {
+        let count = &mut self.counts[SizeBucket::of(length) as usize];
+        *count = count.saturating_add(1);
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        let mut histogram = SizeHistogram::default();
+
+        for length in [14, 60, 64, 65, 127, 128, 1023, 1024, 1518, 1522, 9000] {
+            histogram.record(length);
+        }
+
+        let counts: [u32; 7] = core::array::from_fn(|i| histogram.get(SizeBucket::ALL[i]));
+        assert_eq!(counts, [3, 2, 1, 0, 1, 2, 2]);
+        assert_eq!(histogram.total(), 11);
+    }
+}
@@ -2,12 +2,38 @@ pub(crate) use self::descriptor::RxDescriptor;
 
 use self::descriptor::RxDescriptorError;
 pub use self::descriptor::RxRingEntry;
+#[cfg(not(feature = "stm32f1xx-hal"))]
+pub use self::descriptor::{ExtendedRxStatus, IpPayloadType, PtpMessageType};
 
-use super::PacketId;
+#[cfg(feature = "raw-descriptors")]
+use super::DESCRIPTOR_WORDS;
+use super::{DmaError, PacketId};
 use crate::peripherals::ETHERNET_DMA;
 
+use core::ptr::NonNull;
+
 mod descriptor;
 
+mod queue;
+pub use self::queue::{RxQueue, RxQueueOverflow};
+
+mod region;
+pub use self::region::{RegionBufferProvider, RegionLayoutError};
+
+#[cfg(feature = "rx-size-histogram")]
+mod histogram;
+#[cfg(feature = "rx-size-histogram")]
+pub use self::histogram::{SizeBucket, SizeHistogram};
+
+/// The amount of padding inserted before received frames if
+/// [`RxRing::set_ip_header_alignment`] is enabled.
+pub const IP_HEADER_ALIGNMENT_PADDING: usize = 2;
+
+/// The amount of bytes at the start of a frame that are passed to the
+/// classifier of [`RxRing::recv_classified`]: the destination and source
+/// address, an 802.1Q tag, and the EtherType.
+pub const CLASSIFIER_HEADER_LEN: usize = 18;
+
 #[cfg(feature = "ptp")]
 use crate::{dma::PacketIdNotFound, ptp::Timestamp};
 
@@ -24,6 +50,15 @@ pub enum RxError {
     DmaError,
     /// Receiving would block
     WouldBlock,
+    /// The sentinel word of a descriptor was modified while the descriptor
+    /// was owned by the DMA. This indicates a missing memory barrier, a cache
+    /// coherency issue, or memory corruption.
+    ///
+    /// Only returned if the `descriptor-sentinel` feature is enabled.
+    Corruption,
+    /// The provided buffer is too small to hold the received frame.
+    /// The frame remains in the ring.
+    BufferTooSmall,
 }
 
 impl From<RxDescriptorError> for RxError {
@@ -31,27 +66,182 @@ impl From<RxDescriptorError> for RxError {
         match value {
             RxDescriptorError::Truncated => Self::Truncated,
             RxDescriptorError::DmaError => Self::DmaError,
+            #[cfg(feature = "descriptor-sentinel")]
+            RxDescriptorError::Corruption => Self::Corruption,
         }
     }
 }
 
+/// A source of RX buffers that are not contained in the [`RxRingEntry`]s
+/// of an [`RxRing`].
+///
+/// When an [`RxRing`] uses a [`BufferProvider`] (see
+/// [`EthernetDMA::set_rx_buffer_provider`](crate::dma::EthernetDMA::set_rx_buffer_provider)),
+/// it acquires a buffer for each of its entries, and acquires a new
+/// buffer whenever a received buffer is handed out with [`RxPacket::into_buffer`].
+///
+/// # Safety
+/// Every buffer returned by [`BufferProvider::acquire`] must
+/// - reside in memory that is accessible by the Ethernet DMA (see
+///   [`is_dma_accessible`](super::is_dma_accessible)),
+/// - be aligned to 4 bytes,
+/// - not be accessed in any way until it is returned by [`RxPacket::into_buffer`]
+///   or passed to [`BufferProvider::release`].
+///
+/// Buffers that violate one of the first two requirements are released to
+/// the provider again, and are never used.
+///
+/// Frames that do not fit in a buffer are dropped and reported as
+/// [`RxError::Truncated`], so buffers should be at least 1522 bytes long.
+/// At most 1522 bytes of each buffer are used.
+pub unsafe trait BufferProvider: Send {
+    /// Acquire a buffer, or return `None` if none is available.
+    fn acquire(&mut self) -> Option<NonNull<[u8]>>;
+
+    /// Release a buffer that was acquired from this provider, but that is
+    /// no longer used by the [`RxRing`].
+    fn release(&mut self, buffer: NonNull<[u8]>);
+}
+
 /// Rx DMA state
+///
+/// # Descriptor recycling
+/// Descriptors are passed back to the DMA synchronously: the descriptor of an
+/// [`RxPacket`] is recycled as soon as the packet is dropped (or freed with
+/// [`RxPacket::free`]), [`RxRing::recv_into`] recycles the descriptor before it
+/// returns, and [`RxPacket::into_buffer`] replaces the buffer of the descriptor
+/// and recycles it immediately. Frames with errors, and frames that do not match
+/// the EtherType filter, are recycled as soon as the ring reaches them while
+/// receiving.
+///
+/// Every received frame holds its descriptor until it is taken out of the
+/// ring, including the frames that are queued in an [`RxQueue`]. Besides
+/// those, the application holds:
+/// - with [`RxRing::recv_next`], [`RxRing::recv_queued`] (and the other
+///   functions that return an [`RxPacket`]): at most one descriptor, since an
+///   [`RxPacket`] borrows the ring.
+/// - with [`RxRing::recv_into`]: no descriptor once it returns.
+/// - with [`RxRing::recv_classified`]: all descriptors that held a frame when
+///   it was called, until it returns.
+///
+/// As long as the application takes frames out of the ring one at a time,
+/// at least as fast as they arrive, the DMA therefore has at least `len - 1`
+/// free descriptors (where `len` is the amount of entries of the ring), and
+/// all `len` descriptors while no [`RxPacket`] is held. No recycling is
+/// deferred, so there is no separate mode that pre-posts descriptors more
+/// eagerly.
 pub struct RxRing<'a> {
     entries: &'a mut [RxRingEntry],
     next_entry: usize,
+    ethertype_filter: Option<&'a [u16]>,
+    low_water_mark: usize,
+    buffer_provider: Option<&'a mut dyn BufferProvider>,
+    deliver_soft_errors: bool,
+    accept_runt_frames: bool,
+    deliver_crc_errors: bool,
+    ip_header_alignment: bool,
+    started: bool,
+    next_seq: u32,
+    #[cfg(feature = "ptp")]
+    lost_timestamps: u32,
+    #[cfg(feature = "rx-size-histogram")]
+    size_histogram: SizeHistogram,
 }
 
 impl<'a> RxRing<'a> {
     /// Allocate
     pub(crate) fn new(entries: &'a mut [RxRingEntry]) -> Self {
         RxRing {
+            low_water_mark: entries.len(),
             entries,
             next_entry: 0,
+            ethertype_filter: None,
+            buffer_provider: None,
+            deliver_soft_errors: false,
+            accept_runt_frames: false,
+            deliver_crc_errors: false,
+            ip_header_alignment: false,
+            started: false,
+            next_seq: 0,
+            #[cfg(feature = "ptp")]
+            lost_timestamps: 0,
+            #[cfg(feature = "rx-size-histogram")]
+            size_histogram: SizeHistogram::default(),
+        }
+    }
+
+    /// Replace the buffer provider of this ring.
+    ///
+    /// All buffers acquired from the previous provider are released to it,
+    /// and the entries of this ring acquire a buffer from `provider`
+    /// instead. Entries for which `provider` cannot provide a buffer use
+    /// the buffer contained in them.
+    ///
+    /// Only call this while the RX DMA is stopped, and call [`RxRing::start`]
+    /// afterwards.
+    ///
+    /// If `provider` provides a buffer that the DMA cannot access or that is
+    /// misaligned, all of its buffers are released to it again, the ring does
+    /// not use it, and [`DmaError::InaccessibleBuffer`] or
+    /// [`DmaError::MisalignedBuffer`] is returned.
+    pub(crate) fn set_buffer_provider(
+        &mut self,
+        provider: Option<&'a mut dyn BufferProvider>,
+    ) -> Result<(), DmaError> {
+        self.release_buffers();
+
+        self.buffer_provider = provider;
+
+        if let Some(provider) = self.buffer_provider.as_deref_mut() {
+            for entry in self.entries.iter_mut() {
+                let buffer = provider.acquire();
+
+                if let Some(buffer) = buffer {
+                    if let Err(error) = check_buffer(buffer) {
+                        provider.release(buffer);
+                        self.release_buffers();
+                        self.buffer_provider = None;
+                        return Err(error);
+                    }
+                }
+
+                entry.replace_buffer(buffer);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Release all buffers of this ring to the buffer provider, and
+    /// use the buffers contained in the entries instead.
+    ///
+    /// Only call this while the RX DMA is stopped.
+    pub(crate) fn release_buffers(&mut self) {
+        for entry in self.entries.iter_mut() {
+            if let Some(buffer) = entry.replace_buffer(None) {
+                if let Some(provider) = self.buffer_provider.as_deref_mut() {
+                    provider.release(buffer);
+                }
+            }
         }
     }
 
+    /// Take the entries out of this ring.
+    ///
+    /// Only call this while the DMA is stopped.
+    pub(crate) fn into_entries(self) -> &'a mut [RxRingEntry] {
+        self.entries
+    }
+
     /// Setup the DMA engine (**required**)
     pub(crate) fn start(&mut self, eth_dma: &ETHERNET_DMA) {
+        // The timestamps of frames received before the ring was stopped
+        // are not retained across a restart.
+        #[cfg(feature = "ptp")]
+        for entry in self.entries.iter_mut() {
+            entry.forget_timestamp();
+        }
+
         // Setup ring
         {
             let mut previous: Option<&mut RxRingEntry> = None;
@@ -66,6 +256,7 @@ impl<'a> RxRing<'a> {
             }
         }
         self.next_entry = 0;
+        self.next_seq = 0;
         let ring_ptr = self.entries[0].desc() as *const RxDescriptor;
 
         // Register RxDescriptor
@@ -77,22 +268,37 @@ impl<'a> RxRing<'a> {
 
         // Start receive
         eth_dma.dmaomr.modify(|_, w| w.sr().set_bit());
+        self.started = true;
 
         self.demand_poll();
     }
 
     /// Stop the RX DMA
-    pub(crate) fn stop(&self, eth_dma: &ETHERNET_DMA) {
+    pub(crate) fn stop(&mut self, eth_dma: &ETHERNET_DMA) -> Result<(), DmaError> {
+        self.started = false;
         eth_dma.dmaomr.modify(|_, w| w.sr().clear_bit());
 
         // DMA accesses do not stop before the running state
         // of the DMA has changed to something other than
         // running.
-        while self.running_state().is_running() {}
+        super::wait_until(eth_dma, DmaError::SuspendTimeout, || {
+            !self.running_state().is_running()
+        })
+    }
+
+    /// Undo [`RxRing::stop`], e.g. if it timed out.
+    ///
+    /// The DMA continues at the descriptor at which it stopped, so, unlike
+    /// [`RxRing::start`], this does not drop any received frames.
+    pub(crate) fn cancel_stop(&mut self, eth_dma: &ETHERNET_DMA) {
+        eth_dma.dmaomr.modify(|_, w| w.sr().set_bit());
+        self.started = true;
+
+        self.demand_poll();
     }
 
     /// Demand that the DMA engine polls the current `RxDescriptor`
-    /// (when in [`RunningState::Stopped`].)
+    /// (when in [`RunningState::Suspended`].)
     fn demand_poll(&self) {
         // SAFETY: we only perform an atomic write to `dmarpdr`.
         let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
@@ -111,7 +317,7 @@ impl<'a> RxRing<'a> {
             //  Waiting for receive packet
             0b011 => RunningState::Running,
             //  Receive descriptor unavailable
-            0b100 => RunningState::Stopped,
+            0b100 => RunningState::Suspended,
             //  Closing receive descriptor
             0b101 => RunningState::Running,
             //  Transferring the receive packet data from receive buffer to host memory
@@ -120,13 +326,335 @@ impl<'a> RxRing<'a> {
         }
     }
 
+    /// Check whether the RX DMA is stopped.
+    ///
+    /// The RX DMA is stopped after a reset, after a stop command (e.g. by
+    /// [`EthernetDMA::prepare_for_sleep`](super::EthernetDMA::prepare_for_sleep)),
+    /// and after a fatal bus error. Unlike a suspended RX DMA (see
+    /// [`RunningState::Suspended`]), a stopped RX DMA does not resume when new
+    /// descriptors become available.
+    ///
+    /// If the RX DMA stopped by itself while the ring is in use, every call
+    /// that receives frames from this ring (and [`RxRing::next_entry_available`])
+    /// restarts it by setting the start receive (SR) bit again, unless a
+    /// fatal bus error occurred, which requires re-initializing the DMA.
+    pub fn is_stopped(&self) -> bool {
+        self.running_state() == RunningState::Stopped
+    }
+
+    /// Make sure that the RX DMA keeps receiving frames into this ring.
+    ///
+    /// A suspended RX DMA is resumed with a poll demand, and a stopped RX
+    /// DMA is restarted, unless it was stopped by [`RxRing::stop`] or a
+    /// fatal bus error.
+    pub(super) fn resume(&self) {
+        match self.running_state() {
+            RunningState::Running => {}
+            RunningState::Stopped => {
+                // SAFETY: we only perform an atomic read of `dmasr` and
+                // modify the SR bit of `dmaomr`, which only affects the RX DMA.
+                let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+
+                if self.started && eth_dma.dmasr.read().fbes().bit_is_clear() {
+                    eth_dma.dmaomr.modify(|_, w| w.sr().clear_bit());
+                    eth_dma.dmaomr.modify(|_, w| w.sr().set_bit());
+                    self.demand_poll();
+                }
+            }
+            RunningState::Suspended | RunningState::Unknown => self.demand_poll(),
+        }
+    }
+
     /// Check if we can receive a new packet
+    ///
+    /// If an EtherType filter is configured (see [`RxRing::set_ethertype_filter`])
+    /// and the next frame does not match it, this function returns `false` until
+    /// the frame is discarded by a call to [`RxRing::recv_next`].
     pub fn next_entry_available(&self) -> bool {
-        if !self.running_state().is_running() {
-            self.demand_poll();
+        self.resume();
+
+        let entry = &self.entries[self.next_entry];
+        entry.is_available() && !self.is_filtered(entry)
+    }
+
+    /// Only deliver frames with one of the EtherTypes in `ethertypes`.
+    ///
+    /// Received frames whose EtherType field is not in `ethertypes` are passed
+    /// back to the DMA without being returned from [`RxRing::recv_next`]. An
+    /// empty `ethertypes` discards all frames.
+    ///
+    /// The EtherType field is read from bytes 12 and 13 of the frame, so VLAN
+    /// tagged frames are matched on their TPID (`0x8100`).
+    ///
+    /// # Note
+    /// The hardware cannot filter on arbitrary EtherTypes, so filtering is done
+    /// in software: the CPU reads two bytes of every received frame and performs
+    /// a linear search through `ethertypes`. Discarded frames still occupy a
+    /// descriptor until the next call to [`RxRing::recv_next`].
+    pub fn set_ethertype_filter(&mut self, ethertypes: &'a [u16]) {
+        self.ethertype_filter = Some(ethertypes);
+    }
+
+    /// Deliver frames that have an alignment error instead of discarding them.
+    ///
+    /// An alignment error (indicated by the dribble bit) occurs when a frame
+    /// does not end on a byte boundary, which usually also causes a CRC error.
+    /// Apart from the trailing bits, the data of such frames is often still
+    /// usable, which is useful for lenient or diagnostic receivers. Frames that
+    /// are delivered despite an alignment error can be identified with
+    /// [`RxPacket::has_alignment_error`].
+    ///
+    /// Frames with any other error (such as a CRC error without a dribble bit,
+    /// an overflow, a late collision or a watchdog timeout) are discarded, and
+    /// reported as [`RxError::DmaError`]. Frames with a CRC error can be
+    /// delivered with [`RxRing::set_deliver_crc_errors`].
+    ///
+    /// Frames with errors are only passed to the DMA because forward error
+    /// frames is enabled by [`new`](crate::new) and [`new_with_mii`](crate::new_with_mii).
+    /// Otherwise, the MAC drops them before they reach the ring.
+    ///
+    /// The dribble bit is only valid in MII mode (see [`Interface`](crate::Interface)),
+    /// so this has no effect in RMII mode.
+    pub fn set_deliver_soft_errors(&mut self, deliver: bool) {
+        self.deliver_soft_errors = deliver;
+    }
+
+    /// Check whether frames with an alignment error are delivered.
+    ///
+    /// See [`RxRing::set_deliver_soft_errors`].
+    pub fn deliver_soft_errors(&self) -> bool {
+        self.deliver_soft_errors
+    }
+
+    /// Deliver runts (frames that are shorter than the minimum frame length
+    /// of 64 bytes, including the FCS) instead of discarding them.
+    ///
+    /// Runts are usually fragments of frames that were cut short, e.g. by
+    /// a collision or by a noisy link, so delivering them is mostly useful to
+    /// analyze such links. Delivered runts can be identified with
+    /// [`RxPacket::is_runt`].
+    ///
+    /// If enabled, the MAC forwards undersized frames with a good CRC to the
+    /// DMA (forward undersized good frames), which it drops otherwise. Runts
+    /// with a CRC, alignment or any other error are always dropped by the MAC:
+    /// forward error frames, which is enabled by [`new`](crate::new) and
+    /// [`new_with_mii`](crate::new_with_mii), forwards all error frames except
+    /// for runts.
+    ///
+    /// If FCS stripping is enabled (see
+    /// [`EthernetMAC::set_crc_stripping`](crate::mac::EthernetMAC::set_crc_stripping)),
+    /// the length of the frames that the FCS is removed from no longer
+    /// includes it, so [`RxPacket::is_runt`] then also reports such frames
+    /// with 64 to 67 bytes. Disable FCS stripping to classify every frame
+    /// by its exact length.
+    ///
+    /// By default, runts are discarded.
+    pub fn set_accept_runt_frames(&mut self, accept: bool) {
+        // SAFETY: only the FUGF bit is modified, which does not affect
+        // the running TX DMA.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+        eth_dma.dmaomr.modify(|_, w| w.fugf().bit(accept));
+
+        self.accept_runt_frames = accept;
+    }
+
+    /// Check whether runts are delivered.
+    ///
+    /// See [`RxRing::set_accept_runt_frames`].
+    pub fn accept_runt_frames(&self) -> bool {
+        self.accept_runt_frames
+    }
+
+    /// Deliver frames that have a CRC error instead of discarding them.
+    ///
+    /// This is mostly useful to analyze a marginal link, e.g. to measure the
+    /// jitter of the ingress timestamps of all frames, including the corrupted
+    /// ones. The timestamp of a delivered frame is read from its descriptor
+    /// while the frame is received, before the descriptor is passed back to
+    /// the DMA, so it is available with [`RxPacket::timestamp`] and
+    /// [`RxRing::timestamp`] as for any other frame. Frames that are delivered
+    /// despite a CRC error can be identified with [`RxPacket::has_crc_error`].
+    ///
+    /// The timestamp of a frame with a CRC error is less trustworthy than that
+    /// of a good frame:
+    /// - The timestamp is captured when the start of frame delimiter is
+    ///   detected. If the corruption affected the preamble, the capture point
+    ///   (and with it the timestamp) may be off by a few bit times.
+    /// - On STM32F4 and STM32F7, whether a timestamp is captured at all depends
+    ///   on the contents of the frame if only PTP messages are timestamped.
+    ///   A corrupted frame may therefore lack a timestamp, or have one although
+    ///   it is not a PTP message.
+    /// - On STM32F107, the descriptor does not indicate whether the timestamp
+    ///   is valid.
+    ///
+    /// Frames with a CRC error and an alignment error are delivered as well.
+    /// Frames with any other error (see [`RxRing::set_deliver_soft_errors`])
+    /// are always discarded. Frames with a CRC error only reach the ring
+    /// because forward error frames is enabled by [`new`](crate::new) and
+    /// [`new_with_mii`](crate::new_with_mii).
+    ///
+    /// By default, frames with a CRC error are discarded.
+    pub fn set_deliver_crc_errors(&mut self, deliver: bool) {
+        self.deliver_crc_errors = deliver;
+    }
+
+    /// Check whether frames with a CRC error are delivered.
+    ///
+    /// See [`RxRing::set_deliver_crc_errors`].
+    pub fn deliver_crc_errors(&self) -> bool {
+        self.deliver_crc_errors
+    }
+
+    /// Insert [`IP_HEADER_ALIGNMENT_PADDING`] bytes of padding before every
+    /// received frame, so that the IP header (which follows the 14 byte
+    /// Ethernet header) is word-aligned.
+    ///
+    /// The padding is not part of the received packets: [`RxPacket`] and
+    /// [`RxRing::recv_into`] still start at the first byte of the frame.
+    /// The buffer returned by [`RxPacket::into_buffer`] does contain the
+    /// padding, see [`RxPacket::padding`].
+    ///
+    /// This reduces the maximum size of frames that can be received by the
+    /// size of the padding: the buffers of [`RxRingEntry`] can hold 1520
+    /// bytes, which is enough for all frames without a VLAN tag (including
+    /// their CRC), but not for the largest VLAN tagged frames.
+    ///
+    /// The setting applies to every entry once it is passed to the DMA
+    /// again, so frames that are already being received are not affected.
+    pub fn set_ip_header_alignment(&mut self, align: bool) {
+        self.ip_header_alignment = align;
+
+        let padding = if align {
+            IP_HEADER_ALIGNMENT_PADDING
+        } else {
+            0
+        };
+
+        self.entries
+            .iter_mut()
+            .for_each(|entry| entry.set_padding(padding));
+    }
+
+    /// Check whether padding is inserted to word-align IP headers.
+    ///
+    /// See [`RxRing::set_ip_header_alignment`].
+    pub fn ip_header_alignment(&self) -> bool {
+        self.ip_header_alignment
+    }
+
+    /// Remove the EtherType filter, delivering all frames.
+    pub fn clear_ethertype_filter(&mut self) {
+        self.ethertype_filter = None;
+    }
+
+    /// Check whether the frame in `entry` must be discarded
+    /// according to the EtherType filter.
+    fn is_filtered(&self, entry: &RxRingEntry) -> bool {
+        match (self.ethertype_filter, entry.ethertype()) {
+            (Some(filter), Some(ethertype)) => !filter.contains(&ethertype),
+            _ => false,
         }
+    }
 
-        self.entries[self.next_entry].is_available()
+    /// Pass all frames at the front of the ring that do not match the
+    /// EtherType filter back to the DMA.
+    pub(crate) fn discard_filtered(&mut self) {
+        if self.ethertype_filter.is_none() {
+            return;
+        }
+
+        let entries_len = self.entries.len();
+
+        for _ in 0..entries_len {
+            let entry = &self.entries[self.next_entry];
+            if !self.is_filtered(entry) {
+                break;
+            }
+
+            self.entries[self.next_entry].discard();
+            self.next_entry = (self.next_entry + 1) % entries_len;
+        }
+    }
+
+    /// Get the smallest number of entries that were owned by the DMA, and
+    /// that it could receive new frames into, at the same time.
+    ///
+    /// The other entries hold received frames that were waiting to be
+    /// processed by the CPU. If this reaches 0, the DMA has run out of
+    /// descriptors at least once, and frames may have been dropped.
+    ///
+    /// The value is sampled whenever a frame is received, at which point
+    /// the application holds no entries besides those of the waiting frames
+    /// (see [Descriptor recycling](RxRing#descriptor-recycling)). It is
+    /// determined using the current descriptor address of the DMA, and is
+    /// not updated while the descriptor list base address is changed.
+    pub fn low_water_mark(&self) -> usize {
+        self.low_water_mark
+    }
+
+    /// Reset the value returned by [`RxRing::low_water_mark`] to the
+    /// length of the ring.
+    pub fn reset_low_water_mark(&mut self) {
+        self.low_water_mark = self.entries.len();
+    }
+
+    /// Get the distribution of the sizes of the frames that were
+    /// received since this ring was created, or since
+    /// [`RxRing::reset_size_histogram`] was called.
+    ///
+    /// Every frame that is delivered by this ring is counted once, with the
+    /// length it is delivered with (see [`SizeBucket`]). Frames that are
+    /// dropped by the DMA, that have errors, or that do not match the
+    /// EtherType filter are not counted.
+    ///
+    /// Only available with the `rx-size-histogram` feature, as it adds a
+    /// counter update to the receive path of every frame.
+    #[cfg(feature = "rx-size-histogram")]
+    pub fn size_histogram(&self) -> &SizeHistogram {
+        &self.size_histogram
+    }
+
+    /// Reset all counters of [`RxRing::size_histogram`] to 0.
+    #[cfg(feature = "rx-size-histogram")]
+    pub fn reset_size_histogram(&mut self) {
+        self.size_histogram = SizeHistogram::default();
+    }
+
+    /// Log the state of all entries of this ring.
+    ///
+    /// See [`EthernetDMA::dump_rings`](super::EthernetDMA::dump_rings).
+    #[cfg(feature = "defmt")]
+    pub(crate) fn dump(&self, bytes: usize) {
+        // SAFETY: we only perform an atomic read of `dmachrdr`.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+        let current = eth_dma.dmachrdr.read().bits();
+
+        defmt::info!(
+            "RX ring: {=usize} entries, running={=bool}, DMA at {=u32:#010x}",
+            self.entries.len(),
+            self.running_state().is_running(),
+            current
+        );
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let marker = RxRingEntry::dump_marker(self.entries, index, self.next_entry, current);
+            entry.dump(index, marker, bytes);
+        }
+    }
+
+    /// Update the low-water mark with the amount of entries that
+    /// are owned by the DMA.
+    ///
+    /// Only call this if the next entry is available.
+    fn update_low_water_mark(&mut self) {
+        // SAFETY: we only perform an atomic read of `dmachrdr`.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+        let current = eth_dma.dmachrdr.read().bits();
+
+        if let Some(dma_entry) = RxRingEntry::index_of(self.entries, current) {
+            let owned = dma_owned_entries(self.next_entry, dma_entry, self.entries.len());
+            self.low_water_mark = self.low_water_mark.min(owned);
+        }
     }
 
     /// Receive the next packet (if any is ready).
@@ -139,16 +667,24 @@ impl<'a> RxRing<'a> {
         // NOTE(allow): packet_id is unused if ptp is disabled.
         #[allow(unused_variables)] packet_id: Option<PacketId>,
     ) -> Result<(usize, usize), RxError> {
-        if !self.running_state().is_running() {
-            self.demand_poll();
-        }
+        self.discard_filtered();
+
+        self.resume();
 
         let entries_len = self.entries.len();
         let entry_num = self.next_entry;
-        let entry = &mut self.entries[entry_num];
 
-        if entry.is_available() {
-            let length = entry.recv(packet_id)?;
+        if self.entries[entry_num].is_available() {
+            self.update_low_water_mark();
+
+            let length = self.entries[entry_num].recv(
+                packet_id,
+                self.deliver_soft_errors,
+                self.deliver_crc_errors,
+            )?;
+
+            #[cfg(feature = "ptp")]
+            self.claim_packet_id(entry_num);
 
             self.next_entry = (self.next_entry + 1) % entries_len;
 
@@ -162,10 +698,244 @@ impl<'a> RxRing<'a> {
     /// immediately.
     pub fn recv_next(&mut self, packet_id: Option<PacketId>) -> Result<RxPacket, RxError> {
         let (entry, length) = self.recv_next_impl(packet_id.map(|p| p.into()))?;
-        Ok(RxPacket {
+        Ok(self.packet(entry, length))
+    }
+
+    /// Receive the next packet (if any is ready), together with the raw
+    /// status words of its descriptor, or return [`Err`] immediately.
+    ///
+    /// The words are all words of the descriptor (RDES0 onwards), as written
+    /// back by the DMA, including any reserved or undocumented bits. Their
+    /// amount and layout are specific to the MCU family (see [`DESCRIPTOR_WORDS`]):
+    /// - The STM32F4 and STM32F7 use enhanced descriptors of 8 words. RDES4
+    ///   holds the extended status, and RDES6 and RDES7 hold the timestamp of
+    ///   the frame, if one was captured.
+    /// - The STM32F107 uses normal descriptors of 4 words. RDES2 and RDES3
+    ///   hold the timestamp of the frame instead of the buffer addresses, if
+    ///   one was captured. With the `descriptor-sentinel` feature, a fifth word
+    ///   holds the sentinel.
+    ///
+    /// See the description of the RX descriptors in the reference manual of
+    /// the MCU family.
+    ///
+    /// The descriptor is not modified: like for [`RxRing::recv_next`], it is
+    /// passed back to the DMA once the returned [`RxPacket`] is dropped.
+    ///
+    /// Only available with the `raw-descriptors` feature, as the descriptor
+    /// layout is not part of the stable API of this crate.
+    #[cfg(feature = "raw-descriptors")]
+    pub fn recv_next_raw(
+        &mut self,
+        packet_id: Option<PacketId>,
+    ) -> Result<(RxPacket<'_>, [u32; DESCRIPTOR_WORDS]), RxError> {
+        let (entry, length) = self.recv_next_impl(packet_id)?;
+        let words = self.entries[entry].raw_words();
+        Ok((self.packet(entry, length), words))
+    }
+
+    /// Receive the next frame that was queued in `queue` (if any), or
+    /// return [`Err`] immediately.
+    ///
+    /// Frames that `queue` dropped since the last call are passed back to
+    /// the DMA before the next queued frame is taken. Frames that do not match the EtherType filter are
+    /// passed back to the DMA as well, and are skipped.
+    ///
+    /// If `queue` is not attached to this ring, or no frame is queued, this
+    /// function returns [`Err(RxError::WouldBlock)`](RxError::WouldBlock).
+    pub fn recv_queued<const DEPTH: usize>(
+        &mut self,
+        queue: &RxQueue<DEPTH>,
+        packet_id: Option<PacketId>,
+    ) -> Result<RxPacket<'_>, RxError> {
+        if !queue.is_attached_to(self.entries) {
+            return Err(RxError::WouldBlock);
+        }
+
+        let entries_len = self.entries.len();
+
+        loop {
+            self.resume();
+
+            queue.refill();
+            let (entry, dropped) = queue.pop().ok_or(RxError::WouldBlock)?;
+
+            // The dropped frames are in the entries directly before `entry`.
+            for offset in (1..=dropped).rev() {
+                self.entries[(entry + entries_len - offset) % entries_len].discard();
+                queue.retire();
+            }
+            queue.retire();
+
+            self.next_entry = entry;
+            self.update_low_water_mark();
+            self.next_entry = (entry + 1) % entries_len;
+
+            if self.is_filtered(&self.entries[entry]) {
+                self.entries[entry].discard();
+                continue;
+            }
+
+            let length = self.entries[entry].recv(
+                packet_id,
+                self.deliver_soft_errors,
+                self.deliver_crc_errors,
+            )?;
+
+            #[cfg(feature = "ptp")]
+            self.claim_packet_id(entry);
+
+            return Ok(self.packet(entry, length));
+        }
+    }
+
+    fn packet(&mut self, entry: usize, length: usize) -> RxPacket<'_> {
+        #[cfg(feature = "rx-size-histogram")]
+        self.size_histogram.record(length);
+
+        let seq = self.next_seq;
+        self.next_seq = seq.wrapping_add(1);
+
+        RxPacket {
             entry: &mut self.entries[entry],
             length,
-        })
+            seq,
+            buffer_provider: self.buffer_provider.as_deref_mut().map(|p| p as _),
+        }
+    }
+
+    /// Copy the next packet (if any is ready) into `buffer`, or return
+    /// [`Err`] immediately.
+    ///
+    /// On success, the length of the packet is returned and the descriptor
+    /// is passed back to the DMA.
+    ///
+    /// If `buffer` is too small to hold the packet,
+    /// [`Err(RxError::BufferTooSmall)`](RxError::BufferTooSmall) is returned and
+    /// the packet is left in the ring, so it can be received with a larger buffer.
+    ///
+    /// The DMA always receives into the buffers of the ring, so `buffer` may
+    /// reside in any memory, including memories that the DMA cannot access
+    /// (see [`is_dma_accessible`](super::is_dma_accessible)).
+    pub fn recv_into(&mut self, buffer: &mut [u8]) -> Result<usize, RxError> {
+        let (entry, length) = self.recv_next_impl(None)?;
+
+        if length > buffer.len() {
+            // The entry has not been passed back to the DMA, so it
+            // will be received again.
+            self.next_entry = entry;
+            return Err(RxError::BufferTooSmall);
+        }
+
+        #[cfg(feature = "rx-size-histogram")]
+        self.size_histogram.record(length);
+
+        let entry = &mut self.entries[entry];
+        buffer[..length].copy_from_slice(&entry.buffer()[..length]);
+        entry.desc_mut().set_owned();
+
+        Ok(length)
+    }
+
+    /// Receive all frames that are ready, grouped by `classify`.
+    ///
+    /// `classify` is called with the first [`CLASSIFIER_HEADER_LEN`] bytes of
+    /// every frame (or the whole frame, if it is shorter), and returns the bucket
+    /// that the frame belongs to. `deliver` is then called once for every frame
+    /// with its bucket and data, so that all frames of a bucket are delivered one
+    /// after the other. The buckets are delivered in the order in which their first
+    /// frame was received, and the frames of a bucket in the order in which they
+    /// were received. Returns the amount of delivered frames.
+    ///
+    /// Grouping the frames does not require any memory besides the ring:
+    /// `classify` is called more than once for most frames, so it must always
+    /// return the same bucket for the same header, and should be cheap.
+    ///
+    /// Only the frames that are ready when this function is called are
+    /// delivered. All of their descriptors are held until every frame was
+    /// delivered, and are then passed back to the DMA before this function
+    /// returns, so the frame data can not be kept after `deliver` returns. While
+    /// the descriptors are held, the DMA can not receive into them, so frames
+    /// may be dropped if the ring is nearly full. Frames with errors, and frames
+    /// that do not match the EtherType filter, are not delivered, and are passed
+    /// back to the DMA along with the others.
+    pub fn recv_classified<B, C, F>(&mut self, classify: C, mut deliver: F) -> usize
+    where
+        B: PartialEq,
+        C: Fn(&[u8]) -> B,
+        F: FnMut(&B, &[u8]),
+    {
+        self.discard_filtered();
+
+        self.resume();
+
+        let entries_len = self.entries.len();
+        let start = self.next_entry;
+        let ready = (0..entries_len)
+            .take_while(|i| self.entries[(start + i) % entries_len].is_available())
+            .count();
+
+        if ready == 0 {
+            return 0;
+        }
+
+        self.update_low_water_mark();
+
+        // The frames are counted into a copy, as `frame` borrows `self`.
+        #[cfg(feature = "rx-size-histogram")]
+        let mut size_histogram = self.size_histogram;
+
+        let frame = |i: usize| {
+            let entry = &self.entries[(start + i) % entries_len];
+
+            if self.is_filtered(entry) {
+                return None;
+            }
+
+            let length = entry.peek_len(self.deliver_soft_errors, self.deliver_crc_errors)?;
+            Some(&entry.buffer()[..length])
+        };
+        let bucket = |frame: &[u8]| classify(&frame[..frame.len().min(CLASSIFIER_HEADER_LEN)]);
+
+        let mut delivered = 0;
+
+        for first in 0..ready {
+            let first_frame = match frame(first) {
+                Some(first_frame) => first_frame,
+                None => continue,
+            };
+            let first_bucket = bucket(first_frame);
+
+            // The bucket was delivered along with an earlier frame.
+            if (0..first).any(|i| frame(i).is_some_and(|f| bucket(f) == first_bucket)) {
+                continue;
+            }
+
+            deliver(&first_bucket, first_frame);
+            delivered += 1;
+            #[cfg(feature = "rx-size-histogram")]
+            size_histogram.record(first_frame.len());
+
+            for i in first + 1..ready {
+                if let Some(f) = frame(i).filter(|f| bucket(f) == first_bucket) {
+                    deliver(&first_bucket, f);
+                    delivered += 1;
+                    #[cfg(feature = "rx-size-histogram")]
+                    size_histogram.record(f.len());
+                }
+            }
+        }
+
+        for i in 0..ready {
+            self.entries[(start + i) % entries_len].discard();
+        }
+        self.next_entry = (start + ready) % entries_len;
+
+        #[cfg(feature = "rx-size-histogram")]
+        {
+            self.size_histogram = size_histogram;
+        }
+
+        delivered
     }
 
     /// Receive the next packet.
@@ -187,16 +957,19 @@ impl<'a> RxRing<'a> {
         })
         .await;
 
-        RxPacket {
-            entry: &mut self.entries[entry],
-            length,
-        }
+        self.packet(entry, length)
     }
 }
 
 #[cfg(feature = "ptp")]
 impl<'a> RxRing<'a> {
     /// Get the timestamp for a specific ID
+    ///
+    /// A packet ID is held by at most one entry: if a frame is received with
+    /// a packet ID that an earlier frame in the ring was received with, the
+    /// earlier frame loses its packet ID, and its timestamp is lost (see
+    /// [`RxRing::lost_timestamps`]). Afterwards, the timestamp for that packet
+    /// ID is always the one of the most recent frame.
     pub fn timestamp(&self, id: &PacketId) -> Result<Option<Timestamp>, PacketIdNotFound> {
         let entry = self.entries.iter().find(|e| e.has_packet_id(id));
 
@@ -204,17 +977,63 @@ impl<'a> RxRing<'a> {
 
         Ok(entry.read_timestamp())
     }
+
+    /// Get the number of RX timestamps that were lost because a frame was
+    /// received with a packet ID that was still held by an earlier frame.
+    ///
+    /// RX timestamps are stored in the RX descriptors, and remain available
+    /// until their descriptor receives another frame. The ring does not track
+    /// whether a timestamp was read, so every timestamp that is still held by
+    /// an earlier frame when its packet ID is reused is counted, see
+    /// [`RxRing::timestamp`]. Use [`EthernetDMA::next_packet_id`](crate::dma::EthernetDMA::next_packet_id)
+    /// to get unique packet IDs.
+    ///
+    /// The counter saturates at [`u32::MAX`].
+    pub fn lost_timestamps(&self) -> u32 {
+        self.lost_timestamps
+    }
+
+    /// Reset the value returned by [`RxRing::lost_timestamps`] to 0.
+    pub fn reset_lost_timestamps(&mut self) {
+        self.lost_timestamps = 0;
+    }
+
+    /// Remove the packet ID of the frame that was just received into
+    /// `entry` from all other entries.
+    fn claim_packet_id(&mut self, entry: usize) {
+        let (before, rest) = self.entries.split_at_mut(entry);
+        let (received, after) = match rest.split_first_mut() {
+            Some(split) => split,
+            None => return,
+        };
+
+        let id = match received.packet_id() {
+            Some(id) => id,
+            None => return,
+        };
+
+        for other in before.iter_mut().chain(after.iter_mut()) {
+            if other.has_packet_id(id) && other.forget_packet_id() {
+                self.lost_timestamps = self.lost_timestamps.saturating_add(1);
+            }
+        }
+    }
 }
 
 /// Running state of the `RxRing`
 #[derive(PartialEq, Eq, Debug)]
+#[non_exhaustive]
 pub enum RunningState {
     /// Running state is unknown.
     Unknown,
-    /// The RX DMA is stopped.
+    /// The RX DMA is stopped (after a reset, a stop command, or a fatal
+    /// bus error), and does not receive frames until it is started again.
     Stopped,
     /// The RX DMA is running.
     Running,
+    /// The RX DMA is suspended, because no receive descriptor is available.
+    /// It resumes once a descriptor is passed back to it and polled.
+    Suspended,
 }
 
 impl RunningState {
@@ -231,19 +1050,21 @@ impl RunningState {
 pub struct RxPacket<'a> {
     entry: &'a mut RxRingEntry,
     length: usize,
+    seq: u32,
+    buffer_provider: Option<&'a mut dyn BufferProvider>,
 }
 
 impl<'a> core::ops::Deref for RxPacket<'a> {
     type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        &self.entry.as_slice()[0..self.length]
+        &self.entry.buffer()[0..self.length]
     }
 }
 
 impl<'a> core::ops::DerefMut for RxPacket<'a> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.entry.as_mut_slice()[0..self.length]
+        &mut self.entry.buffer_mut()[0..self.length]
     }
 }
 
@@ -259,9 +1080,234 @@ impl<'a> RxPacket<'a> {
         drop(self)
     }
 
+    /// The sequence number of this packet.
+    ///
+    /// The ring numbers the packets that it delivers as [`RxPacket`]s in
+    /// the order in which they are handed out. The first packet has sequence
+    /// number 0, and every subsequent packet has the sequence number of the
+    /// previous one plus 1 (wrapping around on overflow), so frames that are
+    /// kept around longer (e.g. with [`RxPacket::into_buffer`]) can be put
+    /// back into the order in which they were delivered.
+    ///
+    /// The number is assigned when the packet is handed out, so it is
+    /// increasing by construction, and can not be used to detect whether
+    /// the ring delivers frames in a different order than they arrived in.
+    ///
+    /// Frames that are received with [`RxRing::recv_into`] or
+    /// [`RxRing::recv_classified`], filtered or discarded frames do not
+    /// consume a sequence number. The sequence numbers are internal to
+    /// this ring, and restart at 0 whenever the ring is restarted (e.g. by
+    /// [`EthernetDMA::wake`](super::EthernetDMA::wake) or
+    /// [`EthernetDMA::set_rx_buffer_provider`](super::EthernetDMA::set_rx_buffer_provider)).
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// The amount of padding before this packet in the buffer of its
+    /// entry.
+    ///
+    /// See [`RxRing::set_ip_header_alignment`].
+    pub fn padding(&self) -> usize {
+        self.entry.active_padding()
+    }
+
+    /// Take the buffer containing this packet out of the ring, and
+    /// return it together with the length of the packet.
+    ///
+    /// The packet starts after [`RxPacket::padding`] bytes.
+    ///
+    /// The entry of this packet acquires a new buffer from the
+    /// [`BufferProvider`] of the ring and is passed back to the DMA. The
+    /// returned buffer is no longer used by the ring, and can be returned
+    /// to the provider when it is no longer needed.
+    ///
+    /// Returns `Err(self)` if the ring has no [`BufferProvider`], if this
+    /// packet is not stored in a buffer acquired from it, or if the provider
+    /// cannot provide a new buffer that the DMA can access.
+    pub fn into_buffer(mut self) -> Result<(NonNull<[u8]>, usize), Self> {
+        if !self.entry.has_external_buffer() {
+            return Err(self);
+        }
+
+        let provider = match self.buffer_provider.as_deref_mut() {
+            Some(provider) => provider,
+            None => return Err(self),
+        };
+
+        let replacement = match provider.acquire() {
+            Some(replacement) if check_buffer(replacement).is_ok() => replacement,
+            Some(replacement) => {
+                provider.release(replacement);
+                return Err(self);
+            }
+            None => return Err(self),
+        };
+
+        let buffer = self
+            .entry
+            .replace_buffer(Some(replacement))
+            .expect("Entry has an external buffer");
+        let length = self.length;
+
+        // Passes the entry with its new buffer back to the DMA.
+        drop(self);
+
+        Ok((buffer, length))
+    }
+
     /// Get the timestamp associated with this packet
     #[cfg(feature = "ptp")]
     pub fn timestamp(&self) -> Option<Timestamp> {
         self.entry.read_timestamp()
     }
+
+    /// Get the extended status of this packet.
+    ///
+    /// Returns `None` if the DMA did not provide an extended status
+    /// for this packet.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub fn extended_status(&self) -> Option<ExtendedRxStatus> {
+        self.entry.desc().extended_status()
+    }
+
+    /// Get the type of the PTP message in this packet, as recognized by the MAC.
+    ///
+    /// This allows dispatching PTP messages without parsing their header.
+    /// Returns `None` if the packet does not contain a PTP message, or if the
+    /// DMA did not provide an extended status for this packet.
+    ///
+    /// The MAC only recognizes PTP messages while timestamping is enabled
+    /// (which `EthernetPTP` does if the `ptp` feature is enabled), and only
+    /// those that are sent over Ethernet, UDP/IPv4 or UDP/IPv6, and that match
+    /// the PTP version that the MAC is configured to process.
+    ///
+    /// The message type is decoded from the extended status of the enhanced
+    /// descriptors of the STM32F4 and STM32F7. The descriptors of the STM32F107
+    /// do not report it, so this function is not available there.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub fn ptp_message_type(&self) -> Option<PtpMessageType> {
+        self.entry.desc().ptp_message_type()
+    }
+
+    /// Check whether this packet has an alignment error.
+    ///
+    /// Packets with an alignment error are only delivered if enabled with
+    /// [`RxRing::set_deliver_soft_errors`]. The last bytes of such a packet
+    /// may be incomplete.
+    pub fn has_alignment_error(&self) -> bool {
+        self.entry.has_alignment_error()
+    }
+
+    /// Check whether this packet has a CRC error.
+    ///
+    /// Packets with a CRC error are only delivered if enabled with
+    /// [`RxRing::set_deliver_crc_errors`], or if they also have an alignment
+    /// error and [`RxRing::set_deliver_soft_errors`] is enabled.
+    pub fn has_crc_error(&self) -> bool {
+        self.entry.has_crc_error()
+    }
+
+    /// Check whether this packet is a runt, i.e. shorter than the minimum
+    /// frame length of 64 bytes.
+    ///
+    /// Runts are only delivered if enabled with
+    /// [`RxRing::set_accept_runt_frames`], and only if their CRC is good.
+    pub fn is_runt(&self) -> bool {
+        self.entry.is_runt()
+    }
+
+    /// Check whether this packet is a MAC control frame, such as a
+    /// pause frame.
+    ///
+    /// This is the case if the DMA classified the length/type field of the
+    /// frame as an EtherType (the Frame Type bit), and that EtherType is the
+    /// MAC control EtherType (`0x8808`).
+    ///
+    /// With flow control enabled, the MAC consumes pause frames, and only
+    /// passes them on if the Pass Control Frames setting of its frame filter
+    /// allows it. MAC control frames thus usually only reach software if flow
+    /// control is disabled, or the MAC is configured to forward them, e.g. in
+    /// promiscuous or receive-all mode (see
+    /// [`EthernetMAC::set_receive_all`](crate::mac::EthernetMAC::set_receive_all)).
+    pub fn is_control_frame(&self) -> bool {
+        /// The EtherType of MAC control frames.
+        const MAC_CONTROL_ETHERTYPE: [u8; 2] = [0x88, 0x08];
+
+        self.entry.is_ethertype_frame() && self.get(12..14) == Some(&MAC_CONTROL_ETHERTYPE)
+    }
+
+    /// Get the hardware-computed flow hash of this packet, if any.
+    ///
+    /// Some Ethernet MACs can compute a hash over the addresses and
+    /// ports of a received frame and store it in the RX descriptor,
+    /// which can be used to dispatch frames of the same flow to the same
+    /// queue or task.
+    ///
+    /// None of the MACs currently supported by this crate (STM32F107,
+    /// STM32F4 and STM32F7) compute such a hash, so this always returns
+    /// `None`.
+    pub fn flow_hash(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Check whether the DMA can use `buffer`, which was acquired from
+/// a [`BufferProvider`].
+fn check_buffer(buffer: NonNull<[u8]>) -> Result<(), DmaError> {
+    let address = buffer.as_ptr() as *const u8;
+
+    if !super::is_dma_accessible(address, buffer.len()) {
+        Err(DmaError::InaccessibleBuffer)
+    } else if !(address as usize).is_multiple_of(4) {
+        Err(DmaError::MisalignedBuffer)
+    } else {
+        Ok(())
+    }
+}
+
+/// The amount of entries of a ring with `entries_len` entries that are
+/// owned by the DMA, if the DMA is at `dma_entry`, the entry at `next_entry`
+/// (the next one to be received) holds a frame, and all entries from there
+/// up to `dma_entry` hold received frames.
+fn dma_owned_entries(next_entry: usize, dma_entry: usize, entries_len: usize) -> usize {
+    // If the DMA is at the next entry while it holds a frame, the DMA
+    // has filled all entries and is waiting for one to be freed.
+    match (dma_entry + entries_len - next_entry) % entries_len {
+        0 => 0,
+        pending => entries_len - pending,
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buffer_checks() {
+        let buffer = |address: usize, len: usize| {
+            NonNull::new(core::ptr::slice_from_raw_parts_mut(address as *mut u8, len)).unwrap()
+        };
+
+        assert_eq!(check_buffer(buffer(0x2000_0000, 1536)), Ok(()));
+        assert_eq!(
+            check_buffer(buffer(0x2000_0002, 1536)),
+            Err(DmaError::MisalignedBuffer)
+        );
+        assert_eq!(
+            check_buffer(buffer(0x1000_0000, 1536)),
+            Err(DmaError::InaccessibleBuffer)
+        );
+    }
+
+    #[test]
+    fn dma_owned_entries_wrap_around() {
+        // The DMA has filled entries 1 and 2, and waits at entry 3.
+        assert_eq!(dma_owned_entries(1, 3, 4), 2);
+        // The DMA has filled entry 3, and waits at entry 0.
+        assert_eq!(dma_owned_entries(3, 0, 4), 3);
+        // The DMA has filled entries 3 and 0, and waits at entry 1.
+        assert_eq!(dma_owned_entries(3, 1, 4), 2);
+        // The DMA has filled all entries, and waits for entry 2 to be freed.
+        assert_eq!(dma_owned_entries(2, 2, 4), 0);
+    }
 }
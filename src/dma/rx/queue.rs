@@ -0,0 +1,431 @@
+//! A queue of received frames that is filled from the `ETH` interrupt.
+
+use core::sync::atomic::{AtomicPtr, AtomicU32, AtomicUsize, Ordering};
+
+use cortex_m::interrupt;
+
+use super::{RxRing, RxRingEntry};
+
+/// What an [`RxQueue`] does if a frame is ready while the queue is full.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxQueueOverflow {
+    /// Stop draining the [`RxRing`].
+    ///
+    /// The frame, and all frames received after it, stay in the ring until
+    /// queued frames are received. Once all descriptors of the ring are
+    /// filled, the DMA drops new frames.
+    StopDraining,
+    /// Drop the oldest queued frame to make room for the new one.
+    ///
+    /// Dropped frames are passed back to the DMA during the next call
+    /// to [`RxRing::recv_queued`].
+    DropOldest,
+}
+
+/// A single-producer, single-consumer queue of frames that are ready
+/// in an [`RxRing`].
+///
+/// The `ETH` interrupt handler calls [`RxQueue::interrupt_handler`], which
+/// records which entries of the ring hold a received frame. No data is
+/// copied. The main loop then takes these frames out of the queue, in the
+/// order in which they were received, with [`RxRing::recv_queued`] (or
+/// [`EthernetDMA::recv_queued`](super::super::EthernetDMA::recv_queued)).
+/// The returned [`RxPacket`](super::RxPacket)s pass their entry back to the
+/// DMA when they are dropped, as usual.
+///
+/// The queue holds at most `DEPTH` frames. If the interrupt handler finds
+/// a frame while the queue is full, it acts according to the
+/// [`RxQueueOverflow`] passed to [`RxQueue::new`]. The queue never holds
+/// more frames than the ring has entries, so a `DEPTH` that is not smaller
+/// than the length of the ring only limits the queue through the ring.
+///
+/// ```rust
+/// use stm32_eth::dma::{RxQueue, RxQueueOverflow};
+///
+/// static RX_QUEUE: RxQueue<4> = RxQueue::new(RxQueueOverflow::DropOldest);
+/// ```
+///
+/// Attach the queue to an [`RxRing`] with [`RxQueue::attach`] after the
+/// DMA has been started. While it is attached, frames must only be received
+/// from that ring through the queue, and the ring must have at least 2
+/// entries.
+pub struct RxQueue<const DEPTH: usize> {
+    overflow: RxQueueOverflow,
+    entries: AtomicPtr<RxRingEntry>,
+    entries_len: AtomicUsize,
+    /// The index of the entry that the producer checks next.
+    next_scan: AtomicUsize,
+    /// The indices of the queued entries.
+    slots: [AtomicUsize; DEPTH],
+    /// The amount of frames that were taken out of the queue, either
+    /// by the consumer or by dropping them.
+    head: AtomicUsize,
+    /// The amount of frames that were put into the queue.
+    tail: AtomicUsize,
+    /// The amount of queued frames that the consumer has passed on or
+    /// back to the DMA.
+    ///
+    /// The frames between this and `head` were dropped from the queue, but
+    /// have not been passed back to the DMA yet.
+    retired: AtomicUsize,
+    overflows: AtomicU32,
+}
+
+impl<const DEPTH: usize> RxQueue<DEPTH> {
+    // NOTE(allow): this constant is only used to initialize `slots`, so
+    // every slot is a fresh atomic.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY_SLOT: AtomicUsize = AtomicUsize::new(0);
+
+    /// Create a new, detached [`RxQueue`].
+    ///
+    /// # Panics
+    /// This function panics if `DEPTH` is zero.
+    pub const fn new(overflow: RxQueueOverflow) -> Self {
+        assert!(DEPTH > 0, "An RxQueue must have a depth of at least 1");
+
+        Self {
+            overflow,
+            entries: AtomicPtr::new(core::ptr::null_mut()),
+            entries_len: AtomicUsize::new(0),
+            next_scan: AtomicUsize::new(0),
+            slots: [Self::EMPTY_SLOT; DEPTH],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            retired: AtomicUsize::new(0),
+            overflows: AtomicU32::new(0),
+        }
+    }
+
+    /// Attach this queue to `ring`, emptying it.
+    ///
+    /// Frames that are already waiting in `ring` are queued by the next call
+    /// to [`RxQueue::interrupt_handler`] or [`RxRing::recv_queued`].
+    pub fn attach(&self, ring: &RxRing<'static>) {
+        interrupt::free(|_| self.attach_entries(ring.entries, ring.next_entry))
+    }
+
+    /// Attach this queue to the ring with `entries`, starting at the
+    /// entry with index `next_entry`.
+    ///
+    /// Must not be preempted by [`RxQueue::fill`].
+    fn attach_entries(&self, entries: &[RxRingEntry], next_entry: usize) {
+        self.entries
+            .store(entries.as_ptr() as *mut RxRingEntry, Ordering::Relaxed);
+        self.entries_len.store(entries.len(), Ordering::Relaxed);
+        self.next_scan.store(next_entry, Ordering::Relaxed);
+        self.head.store(0, Ordering::Relaxed);
+        self.tail.store(0, Ordering::Relaxed);
+        self.retired.store(0, Ordering::Relaxed);
+    }
+
+    /// Detach this queue from its ring.
+    ///
+    /// Frames that are still queued remain in the ring, and can be
+    /// received with [`RxRing::recv_next`].
+    pub fn detach(&self) {
+        interrupt::free(|_| {
+            self.entries.store(core::ptr::null_mut(), Ordering::Relaxed);
+            self.entries_len.store(0, Ordering::Relaxed);
+        })
+    }
+
+    /// Check whether this queue is attached to the ring with `entries`.
+    pub(super) fn is_attached_to(&self, entries: &[RxRingEntry]) -> bool {
+        core::ptr::eq(self.entries.load(Ordering::Relaxed), entries.as_ptr())
+    }
+
+    /// Queue all frames that are ready in the attached ring.
+    ///
+    /// Call this from the `ETH` interrupt handler, after
+    /// [`EthernetDMA::interrupt_handler`](super::super::EthernetDMA::interrupt_handler).
+    /// Returns the amount of frames that were queued.
+    pub fn interrupt_handler(&self) -> usize {
+        self.fill()
+    }
+
+    /// The amount of frames that are currently queued.
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Check whether no frames are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The amount of times that a frame was ready while the queue was full.
+    ///
+    /// With [`RxQueueOverflow::DropOldest`], this is the amount of dropped
+    /// frames.
+    pub fn overflows(&self) -> u32 {
+        self.overflows.load(Ordering::Relaxed)
+    }
+
+    /// Reset the value returned by [`RxQueue::overflows`] to 0.
+    pub fn reset_overflows(&self) {
+        self.overflows.store(0, Ordering::Relaxed);
+    }
+
+    /// The producer side of the queue.
+    ///
+    /// Must not be preempted by another call to this function.
+    fn fill(&self) -> usize {
+        let entries = self.entries.load(Ordering::Relaxed);
+        let entries_len = self.entries_len.load(Ordering::Relaxed);
+
+        if entries.is_null() {
+            return 0;
+        }
+
+        let mut queued = 0;
+
+        loop {
+            let tail = self.tail.load(Ordering::Relaxed);
+
+            // The consumer may still hold the most recently retired frame, so
+            // at most `entries_len - 1` frames may be queued, dropped, or
+            // held at the same time for `next_scan` to never point at one.
+            if tail.wrapping_sub(self.retired.load(Ordering::Acquire)) + 1 >= entries_len {
+                break;
+            }
+
+            let index = self.next_scan.load(Ordering::Relaxed);
+
+            // SAFETY: `entries` points to the `'static` entries of the ring
+            // that this queue was attached to, and `index` is smaller than
+            // their length.
+            if !unsafe { RxRingEntry::is_available_raw(entries.add(index)) } {
+                break;
+            }
+
+            let head = self.head.load(Ordering::Acquire);
+
+            if tail.wrapping_sub(head) >= DEPTH {
+                self.overflows.fetch_add(1, Ordering::Relaxed);
+
+                match self.overflow {
+                    RxQueueOverflow::StopDraining => break,
+                    RxQueueOverflow::DropOldest => {
+                        let dropped = self.head.compare_exchange(
+                            head,
+                            head.wrapping_add(1),
+                            Ordering::AcqRel,
+                            Ordering::Relaxed,
+                        );
+
+                        if dropped.is_err() {
+                            // The consumer took a frame out of the queue.
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            self.slots[tail % DEPTH].store(index, Ordering::Relaxed);
+            self.tail.store(tail.wrapping_add(1), Ordering::Release);
+            self.next_scan
+                .store((index + 1) % entries_len, Ordering::Relaxed);
+
+            queued += 1;
+        }
+
+        queued
+    }
+
+    /// Queue the frames that are ready in the attached ring, if no frames
+    /// are queued, so that frames that were left in the ring are not missed.
+    pub(super) fn refill(&self) {
+        if self.is_empty() {
+            interrupt::free(|_| self.fill());
+        }
+    }
+
+    /// Take the oldest queued frame out of the queue.
+    ///
+    /// Returns the index of its entry, and the amount of frames that were
+    /// dropped from the queue since the previous frame was taken out of it.
+    /// The dropped frames are the ones in the entries directly before the
+    /// returned entry, as frames are queued in the order of the ring.
+    ///
+    /// The consumer must [`retire`](RxQueue::retire) the dropped frames and
+    /// the returned frame before calling this function again.
+    pub(super) fn pop(&self) -> Option<(usize, usize)> {
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+
+            if head == self.tail.load(Ordering::Acquire) {
+                return None;
+            }
+
+            let index = self.slots[head % DEPTH].load(Ordering::Relaxed);
+
+            // If the producer dropped this frame in the meantime, the slot
+            // may have been overwritten, and the exchange fails.
+            if self
+                .head
+                .compare_exchange(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                // All frames before `head` that the consumer has not retired
+                // yet were dropped. The producer only drops frames at `head`,
+                // so none can be dropped between the exchange and this load.
+                let dropped = head.wrapping_sub(self.retired.load(Ordering::Relaxed));
+                return Some((index, dropped));
+            }
+        }
+    }
+
+    /// Record that the consumer has passed a queued or dropped frame on
+    /// or back to the DMA.
+    pub(super) fn retire(&self) {
+        self.retired.fetch_add(1, Ordering::Release);
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    /// Take the next frame out of `queue` like [`RxRing::recv_queued`], and
+    /// pass it and the frames that were dropped before it back to the DMA.
+    fn recv<const DEPTH: usize>(
+        queue: &RxQueue<DEPTH>,
+        entries: &mut [RxRingEntry],
+    ) -> Option<(usize, usize)> {
+        let (entry, dropped) = queue.pop()?;
+
+        for offset in (1..=dropped).rev() {
+            entries[(entry + entries.len() - offset) % entries.len()].discard();
+            queue.retire();
+        }
+        entries[entry].discard();
+        queue.retire();
+
+        Some((entry, dropped))
+    }
+
+    /// Let the DMA receive a frame into the entry at `index`.
+    fn receive(entries: &mut [RxRingEntry], index: usize) {
+        // The DMA clears the OWN bit in the first word of the descriptor,
+        // which is at the start of the entry (see `is_available_raw`).
+        let entry = &mut entries[index] as *mut RxRingEntry as *mut u32;
+        unsafe { core::ptr::write_volatile(entry, 0) };
+    }
+
+    /// A ring in which all entries have a buffer and are owned by the DMA.
+    fn ring<const LEN: usize>() -> [RxRingEntry; LEN] {
+        let mut entries = [RxRingEntry::INIT; LEN];
+        entries.iter_mut().for_each(|entry| {
+            entry.setup(None);
+            entry.discard();
+        });
+        entries
+    }
+
+    #[test]
+    fn fill_and_pop() {
+        let mut entries = ring::<4>();
+        let queue = RxQueue::<8>::new(RxQueueOverflow::StopDraining);
+        queue.attach_entries(&entries, 0);
+
+        assert_eq!(queue.fill(), 0);
+        assert_eq!(queue.pop(), None);
+
+        receive(&mut entries, 0);
+        receive(&mut entries, 1);
+        assert_eq!(queue.fill(), 2);
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(recv(&queue, &mut entries), Some((0, 0)));
+        assert_eq!(recv(&queue, &mut entries), Some((1, 0)));
+        assert_eq!(recv(&queue, &mut entries), None);
+        assert!(queue.is_empty());
+        assert_eq!(queue.overflows(), 0);
+    }
+
+    #[test]
+    fn never_queues_whole_ring() {
+        let mut entries = ring::<4>();
+        let queue = RxQueue::<8>::new(RxQueueOverflow::StopDraining);
+        queue.attach_entries(&entries, 0);
+
+        (0..4).for_each(|index| receive(&mut entries, index));
+
+        // The consumer may still hold the most recently retired frame.
+        assert_eq!(queue.fill(), 3);
+        assert_eq!(queue.fill(), 0);
+
+        assert_eq!(recv(&queue, &mut entries), Some((0, 0)));
+        assert_eq!(queue.fill(), 1);
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn stop_draining() {
+        let mut entries = ring::<4>();
+        let queue = RxQueue::<2>::new(RxQueueOverflow::StopDraining);
+        queue.attach_entries(&entries, 0);
+
+        (0..3).for_each(|index| receive(&mut entries, index));
+
+        assert_eq!(queue.fill(), 2);
+        assert_eq!(queue.overflows(), 1);
+
+        // The frame that did not fit stays in the ring.
+        assert_eq!(recv(&queue, &mut entries), Some((0, 0)));
+        assert_eq!(queue.fill(), 1);
+        assert_eq!(recv(&queue, &mut entries), Some((1, 0)));
+        assert_eq!(recv(&queue, &mut entries), Some((2, 0)));
+        assert!(entries.iter().all(|entry| !entry.is_available()));
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut entries = ring::<4>();
+        let queue = RxQueue::<1>::new(RxQueueOverflow::DropOldest);
+        queue.attach_entries(&entries, 0);
+
+        (0..3).for_each(|index| receive(&mut entries, index));
+
+        assert_eq!(queue.fill(), 3);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.overflows(), 2);
+
+        // The dropped frames are passed back before the queued one.
+        assert_eq!(recv(&queue, &mut entries), Some((2, 2)));
+        assert!(entries.iter().all(|entry| !entry.is_available()));
+
+        // Frames that are dropped later are counted from the most recently
+        // taken frame.
+        receive(&mut entries, 3);
+        receive(&mut entries, 0);
+        assert_eq!(queue.fill(), 2);
+        assert_eq!(queue.overflows(), 3);
+        assert_eq!(recv(&queue, &mut entries), Some((0, 1)));
+        assert!(entries.iter().all(|entry| !entry.is_available()));
+    }
+
+    #[test]
+    fn wraparound() {
+        let mut entries = ring::<3>();
+        let queue = RxQueue::<2>::new(RxQueueOverflow::DropOldest);
+        queue.attach_entries(&entries, 1);
+
+        for frame in 0..10 {
+            let index = (1 + frame) % 3;
+            receive(&mut entries, index);
+            assert_eq!(queue.fill(), 1);
+            assert_eq!(recv(&queue, &mut entries), Some((index, 0)));
+        }
+
+        assert_eq!(queue.overflows(), 0);
+    }
+}
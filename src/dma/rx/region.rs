@@ -0,0 +1,212 @@
+//! A [`BufferProvider`] that carves RX buffers out of a single memory region.
+
+use core::{marker::PhantomData, ptr::NonNull};
+
+use super::BufferProvider;
+
+/// The alignment of the region, and of the length of every buffer in it.
+const ALIGNMENT: usize = 4;
+
+/// Errors that can occur while creating a [`RegionBufferProvider`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionLayoutError {
+    /// The region does not start at a 4 byte boundary.
+    Misaligned,
+    /// The buffer with the given index has a length of zero, or a
+    /// length that is not a multiple of 4.
+    InvalidLength(usize),
+    /// The sum of the lengths of all buffers exceeds the length of
+    /// the region.
+    RegionTooSmall,
+    /// The layout describes more than
+    /// [`RegionBufferProvider::MAX_BUFFERS`] buffers.
+    TooManyBuffers,
+}
+
+/// A [`BufferProvider`] that divides one contiguous region of memory into
+/// RX buffers of (possibly) different sizes.
+///
+/// The layout lists the length of every buffer. The buffers are placed in the
+/// region back to back, in the order of the layout, starting at its first byte.
+/// The layout must follow these rules:
+/// - the region must start at a 4 byte boundary, so that every buffer is
+///   aligned to 4 bytes, as required by [`BufferProvider`],
+/// - the length of every buffer must be a non-zero multiple of 4,
+/// - the sum of all lengths must not exceed the length of the region (any
+///   remaining bytes are not used),
+/// - at most [`RegionBufferProvider::MAX_BUFFERS`] buffers can be described.
+///
+/// At most 1522 bytes of each buffer are used, so longer buffers waste memory.
+/// The RX DMA writes a frame into the buffer of the next descriptor, whatever
+/// its size: a frame that does not fit is dropped and reported as
+/// [`RxError::Truncated`](super::RxError::Truncated). Small buffers should
+/// therefore only be used on links on which large frames are rare, and the
+/// layout should contain enough buffers of 1522 bytes to hold all large frames
+/// that may arrive in a burst.
+///
+/// Buffers are always acquired from the start of the layout: the first buffer
+/// that is not in use is acquired. When the provider is passed to
+/// [`EthernetDMA::set_rx_buffer_provider`](crate::dma::EthernetDMA::set_rx_buffer_provider),
+/// the first entry of the ring therefore receives the first buffer of the
+/// layout, the second entry the second buffer, and so on. If the layout
+/// describes fewer buffers than the ring has entries, the remaining entries
+/// use the buffers contained in them.
+///
+/// Every [`RxRingEntry`](super::RxRingEntry) still contains a buffer of 1522
+/// bytes, which is not used while the entry holds a buffer of this provider, so
+/// this does not reduce the memory occupied by the ring itself. Buffers of this
+/// provider that are not held by an entry replace those that are handed out
+/// with [`RxPacket::into_buffer`](super::RxPacket::into_buffer).
+///
+/// The region must be accessible by the DMA (see
+/// [`is_dma_accessible`](crate::dma::is_dma_accessible)).
+pub struct RegionBufferProvider<'a> {
+    region: NonNull<u8>,
+    layout: &'a [usize],
+    in_use: u64,
+    _region: PhantomData<&'a mut [u8]>,
+}
+
+// SAFETY: the provider has exclusive access to its region.
+unsafe impl Send for RegionBufferProvider<'_> {}
+
+impl<'a> RegionBufferProvider<'a> {
+    /// The maximum amount of buffers in a layout.
+    pub const MAX_BUFFERS: usize = 64;
+
+    /// Create a new [`RegionBufferProvider`] that divides `region` into
+    /// buffers with the lengths listed in `layout`.
+    ///
+    /// See [`RegionBufferProvider`] for the rules that `region` and
+    /// `layout` must follow.
+    pub fn new(region: &'a mut [u8], layout: &'a [usize]) -> Result<Self, RegionLayoutError> {
+        if !(region.as_ptr() as usize).is_multiple_of(ALIGNMENT) {
+            return Err(RegionLayoutError::Misaligned);
+        }
+
+        if layout.len() > Self::MAX_BUFFERS {
+            return Err(RegionLayoutError::TooManyBuffers);
+        }
+
+        let mut total: usize = 0;
+        for (index, &len) in layout.iter().enumerate() {
+            if len == 0 || !len.is_multiple_of(ALIGNMENT) {
+                return Err(RegionLayoutError::InvalidLength(index));
+            }
+
+            total = total
+                .checked_add(len)
+                .ok_or(RegionLayoutError::RegionTooSmall)?;
+        }
+
+        if total > region.len() {
+            return Err(RegionLayoutError::RegionTooSmall);
+        }
+
+        Ok(Self {
+            region: NonNull::from(region).cast(),
+            layout,
+            in_use: 0,
+            _region: PhantomData,
+        })
+    }
+
+    /// The amount of bytes of the region that are used by the layout.
+    pub fn used_len(&self) -> usize {
+        self.layout.iter().sum()
+    }
+
+    /// The amount of buffers that are currently acquired.
+    pub fn acquired(&self) -> usize {
+        self.in_use.count_ones() as usize
+    }
+
+    /// Get the offset of the buffer with `index` from the start of the region.
+    fn offset(&self, index: usize) -> usize {
+        self.layout[..index].iter().sum()
+    }
+}
+
+// SAFETY: all buffers lie within the region, which is accessed by nothing but
+// this provider, and do not overlap. The region and all lengths are aligned to
+// 4 bytes, so every buffer is aligned to 4 bytes. A buffer is only handed out
+// again after it was released.
+unsafe impl BufferProvider for RegionBufferProvider<'_> {
+    fn acquire(&mut self) -> Option<NonNull<[u8]>> {
+        let index = (0..self.layout.len()).find(|i| self.in_use & (1 << i) == 0)?;
+        self.in_use |= 1 << index;
+
+        // SAFETY: `new` checked that the buffer lies within the region.
+        let start = unsafe { NonNull::new_unchecked(self.region.as_ptr().add(self.offset(index))) };
+
+        Some(NonNull::slice_from_raw_parts(start, self.layout[index]))
+    }
+
+    fn release(&mut self, buffer: NonNull<[u8]>) {
+        let offset =
+            (buffer.as_ptr() as *mut u8 as usize).wrapping_sub(self.region.as_ptr() as usize);
+
+        let index = (0..self.layout.len()).find(|&i| self.offset(i) == offset);
+
+        if let Some(index) = index {
+            self.in_use &= !(1 << index);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[repr(align(4))]
+    struct Region([u8; 256]);
+
+    #[test]
+    fn layout_validation() {
+        let mut region = Region([0; 256]);
+
+        assert_eq!(
+            RegionBufferProvider::new(&mut region.0[1..], &[64]).err(),
+            Some(RegionLayoutError::Misaligned)
+        );
+        assert_eq!(
+            RegionBufferProvider::new(&mut region.0, &[64, 62]).err(),
+            Some(RegionLayoutError::InvalidLength(1))
+        );
+        assert_eq!(
+            RegionBufferProvider::new(&mut region.0, &[128, 132]).err(),
+            Some(RegionLayoutError::RegionTooSmall)
+        );
+        assert_eq!(
+            RegionBufferProvider::new(&mut region.0, &[4; 65]).err(),
+            Some(RegionLayoutError::TooManyBuffers)
+        );
+        assert!(RegionBufferProvider::new(&mut region.0, &[128, 128]).is_ok());
+    }
+
+    #[test]
+    fn acquire_and_release() {
+        let mut region = Region([0; 256]);
+        let base = region.0.as_ptr() as usize;
+        let mut provider = RegionBufferProvider::new(&mut region.0, &[64, 64, 128]).unwrap();
+
+        let address = |buffer: NonNull<[u8]>| buffer.as_ptr() as *mut u8 as usize - base;
+
+        let first = provider.acquire().unwrap();
+        let second = provider.acquire().unwrap();
+        let third = provider.acquire().unwrap();
+        assert!(provider.acquire().is_none());
+
+        assert_eq!((address(first), first.len()), (0, 64));
+        assert_eq!((address(second), second.len()), (64, 64));
+        assert_eq!((address(third), third.len()), (128, 128));
+        assert_eq!(provider.acquired(), 3);
+
+        // The first free buffer is acquired next.
+        provider.release(second);
+        provider.release(first);
+        assert_eq!(address(provider.acquire().unwrap()), 0);
+        assert_eq!(provider.acquired(), 2);
+    }
+}
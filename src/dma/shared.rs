@@ -0,0 +1,114 @@
+//! Sharing an [`EthernetDMA`] between the main loop and interrupt handlers.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::{self, Mutex};
+
+use super::{EthernetDMA, InterruptReasonSummary, PacketId, RxError, RxPacket, TxError};
+
+/// An [`EthernetDMA`] that can be stored in a `static`, and accessed from
+/// both the main loop and interrupt handlers.
+///
+/// Create the `static` with [`SharedDma::new`], and move the [`EthernetDMA`]
+/// into it with [`SharedDma::init`]. Since the [`EthernetDMA`] borrows its
+/// rings, the rings must be `'static` as well.
+///
+/// ```rust
+/// use stm32_eth::dma::SharedDma;
+///
+/// static DMA: SharedDma<'static, 'static> = SharedDma::new();
+/// ```
+///
+/// Afterwards, the `ETH` interrupt handler can call `DMA.interrupt_handler()`,
+/// while the main loop calls `DMA.recv_next(None, |packet| ...)`.
+///
+/// # Locking
+/// Every method that accesses the [`EthernetDMA`] does so in a single critical
+/// section (using [`cortex_m::interrupt::free`]), during which all interrupts
+/// are disabled. For the methods that take a closure, the closure also runs in
+/// that critical section, so it should be kept short to avoid delaying other
+/// interrupts.
+///
+/// [`SharedDma::interrupt_handler`] does not take the lock, as it only accesses
+/// registers that are safe to access concurrently.
+pub struct SharedDma<'rx, 'tx> {
+    dma: Mutex<RefCell<Option<EthernetDMA<'rx, 'tx>>>>,
+}
+
+impl<'rx, 'tx> SharedDma<'rx, 'tx> {
+    /// Create a new, empty [`SharedDma`].
+    pub const fn new() -> Self {
+        Self {
+            dma: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Move `dma` into this [`SharedDma`].
+    ///
+    /// Returns the [`EthernetDMA`] that was stored previously, if any.
+    pub fn init(&self, dma: EthernetDMA<'rx, 'tx>) -> Option<EthernetDMA<'rx, 'tx>> {
+        interrupt::free(|cs| self.dma.borrow(cs).replace(Some(dma)))
+    }
+
+    /// Take the [`EthernetDMA`] out of this [`SharedDma`].
+    pub fn take(&self) -> Option<EthernetDMA<'rx, 'tx>> {
+        interrupt::free(|cs| self.dma.borrow(cs).take())
+    }
+
+    /// Run `f` with exclusive access to the [`EthernetDMA`].
+    ///
+    /// Returns `None` if no [`EthernetDMA`] is stored in this [`SharedDma`].
+    ///
+    /// # Panics
+    /// This function panics if it is called from within `f`.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut EthernetDMA<'rx, 'tx>) -> R) -> Option<R> {
+        interrupt::free(|cs| self.dma.borrow(cs).borrow_mut().as_mut().map(f))
+    }
+
+    /// Try to receive a packet, and process it with `f`.
+    ///
+    /// The packet is passed back to the DMA once `f` returns.
+    ///
+    /// If no packet is available, or no [`EthernetDMA`] is stored in this
+    /// [`SharedDma`], this function returns [`Err(RxError::WouldBlock)`](RxError::WouldBlock).
+    pub fn recv_next<R>(
+        &self,
+        packet_id: Option<PacketId>,
+        f: impl FnOnce(&RxPacket) -> R,
+    ) -> Result<R, RxError> {
+        self.lock(|dma| {
+            let packet = dma.recv_next(packet_id)?;
+            let result = f(&packet);
+            packet.free();
+            Ok(result)
+        })
+        .unwrap_or(Err(RxError::WouldBlock))
+    }
+
+    /// Try to send a packet with data.
+    ///
+    /// If there are no free TX slots, or no [`EthernetDMA`] is stored in this
+    /// [`SharedDma`], this function returns [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    pub fn send(
+        &self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        f: impl FnOnce(&mut [u8]),
+    ) -> Result<(), TxError> {
+        self.lock(|dma| dma.send(length, packet_id, f))
+            .unwrap_or(Err(TxError::WouldBlock))
+    }
+
+    /// Handle the DMA parts of the `ETH` interrupt.
+    ///
+    /// See [`EthernetDMA::interrupt_handler`].
+    pub fn interrupt_handler(&self) -> InterruptReasonSummary {
+        EthernetDMA::interrupt_handler()
+    }
+}
+
+impl Default for SharedDma<'_, '_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
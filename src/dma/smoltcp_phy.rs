@@ -1,5 +1,5 @@
 use super::rx::RxRing;
-use super::tx::TxRing;
+use super::tx::{ChecksumInsertion, TxRing};
 use super::EthernetDMA;
 
 #[cfg(feature = "ptp")]
@@ -114,6 +114,8 @@ impl<'dma, 'tx> TxToken for EthTxToken<'dma, 'tx> {
         // NOTE(unwrap): an `EthTxToken` is only created if
         // there is a descriptor available for sending.
         let mut tx_packet = self.tx_ring.send_next(len, meta).ok().unwrap();
+        // smoltcp leaves all checksums to the hardware (see `capabilities`).
+        tx_packet.set_checksum_insertion(ChecksumInsertion::Full);
         let res = f(&mut tx_packet);
         tx_packet.send();
         res
@@ -0,0 +1,139 @@
+//! Building a frame in the buffer of a TX ring entry incrementally.
+
+use super::{checksum_insertion_for, ChecksumInsertion, CrcPadControl, SendState, TxRing};
+use crate::dma::PacketId;
+
+/// An error returned by [`TxFrameBuilder::write`] if the data does
+/// not fit into the remaining space of the buffer.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxBufferFull;
+
+/// A frame that is written into the buffer of a TX ring entry in
+/// multiple steps, e.g. by a streaming serializer.
+///
+/// Data is appended to the frame with [`TxFrameBuilder::write`], and the
+/// frame is passed to the DMA with [`TxFrameBuilder::finish`]. The data is
+/// written directly into the buffer of the entry, so no staging buffer is
+/// required.
+///
+/// # Aborting
+/// If a [`TxFrameBuilder`] is dropped without calling
+/// [`TxFrameBuilder::finish`], the frame is aborted: it is not transmitted,
+/// and the entry is used for the next frame that is sent from the ring. The
+/// data that was written so far is discarded.
+///
+/// This is unlike [`TxPacket`](super::TxPacket), which sends its frame
+/// when it is dropped.
+pub struct TxFrameBuilder<'borrow, 'ring> {
+    ring: &'borrow mut TxRing<'ring>,
+    idx: usize,
+    length: usize,
+    packet_id: Option<PacketId>,
+    checksum_insertion: ChecksumInsertion,
+    crc_pad_control: CrcPadControl,
+    rollback: SendState,
+    finished: bool,
+}
+
+impl<'borrow, 'ring> TxFrameBuilder<'borrow, 'ring> {
+    pub(super) fn new(
+        ring: &'borrow mut TxRing<'ring>,
+        idx: usize,
+        packet_id: Option<PacketId>,
+        rollback: SendState,
+    ) -> Self {
+        Self {
+            ring,
+            idx,
+            length: 0,
+            packet_id,
+            checksum_insertion: ChecksumInsertion::None,
+            crc_pad_control: CrcPadControl::InsertCrcAndPad,
+            rollback,
+            finished: false,
+        }
+    }
+
+    /// Append `data` to the frame.
+    ///
+    /// If `data` does not fit into the remaining space of the buffer
+    /// (see [`TxFrameBuilder::remaining`]), nothing is written and
+    /// [`TxBufferFull`] is returned.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), TxBufferFull> {
+        if data.len() > self.remaining() {
+            return Err(TxBufferFull);
+        }
+
+        let end = self.length + data.len();
+        self.ring.entries[self.idx].buffer_mut()[self.length..end].copy_from_slice(data);
+        self.length = end;
+
+        Ok(())
+    }
+
+    /// The amount of bytes that have been written to the frame.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Check whether no bytes have been written to the frame.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The amount of bytes that can still be written to the frame.
+    pub fn remaining(&self) -> usize {
+        self.ring.entries[self.idx].buffer().len() - self.length
+    }
+
+    /// The data that has been written to the frame so far.
+    pub fn data(&self) -> &[u8] {
+        &self.ring.entries[self.idx].buffer()[..self.length]
+    }
+
+    /// Configure which checksums the hardware should insert into this frame.
+    ///
+    /// See [`TxPacket::set_checksum_insertion`](super::TxPacket::set_checksum_insertion).
+    pub fn set_checksum_insertion(&mut self, checksum_insertion: ChecksumInsertion) {
+        self.checksum_insertion = checksum_insertion;
+    }
+
+    /// Configure whether the MAC should append the CRC (FCS) to this frame,
+    /// and pad it to the minimum frame length.
+    ///
+    /// See [`TxPacket::set_crc_pad_control`](super::TxPacket::set_crc_pad_control).
+    pub fn set_crc_pad_control(&mut self, crc_pad_control: CrcPadControl) {
+        self.crc_pad_control = crc_pad_control;
+    }
+
+    /// Pass the frame, consisting of all data written so far, to the DMA.
+    pub fn finish(mut self) {
+        let checksum_insertion = checksum_insertion_for(self.data(), self.checksum_insertion);
+        let (length, packet_id, crc_pad_control) =
+            (self.length, self.packet_id.clone(), self.crc_pad_control);
+
+        self.ring.send_entry(self.idx, |entry| {
+            entry.send(length, packet_id, checksum_insertion, crc_pad_control)
+        });
+        self.ring.update_high_water_mark();
+        TxRing::demand_poll();
+
+        self.finished = true;
+    }
+}
+
+impl Drop for TxFrameBuilder<'_, '_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            // The entry was never passed to the DMA, so it can be used
+            // for the next frame. No other entry can have been taken from
+            // the ring in the meantime, as it is borrowed by this builder.
+            //
+            // The completion flag of the previous frame in the entry stays
+            // set, as that frame was transmitted. The high-water mark is only
+            // sampled by `finish`, so it does not need to be rolled back.
+            self.ring.restore_send_state(self.rollback);
+        }
+    }
+}
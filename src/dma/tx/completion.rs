@@ -0,0 +1,295 @@
+//! Signalling the transmission of individual frames from the `ETH` interrupt.
+//!
+//! See [`TxPacket::set_completion_flag`](super::TxPacket::set_completion_flag).
+//!
+//! The flags are not stored in the entries of the ring, which the
+//! [`TxRing`](super::TxRing) borrows mutably, but in a fixed amount of slots
+//! that are shared with the interrupt handler. A slot records the entry that
+//! transmits its frame, and the interrupt handler only checks the entries
+//! that are recorded in a slot, by reading the first word of their
+//! descriptor (which the DMA writes as well).
+
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+use super::TxRingEntry;
+
+/// The amount of frames with a completion flag that can be in flight
+/// at the same time.
+pub const TX_COMPLETION_SLOTS: usize = 8;
+
+/// The completion flags of the frames in the running TX ring.
+pub(crate) static COMPLETIONS: Completions = Completions::new();
+
+/// An error returned by [`TxPacket::set_completion_flag`](super::TxPacket::set_completion_flag)
+/// if [`TX_COMPLETION_SLOTS`] frames with a completion flag are in flight already.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionSlotsFull;
+
+pub(crate) struct Completions {
+    slots: [Slot; TX_COMPLETION_SLOTS],
+}
+
+struct Slot {
+    /// The flag of the frame, or null if the slot is free.
+    flag: AtomicPtr<AtomicBool>,
+    /// The entry that transmits the frame, or null if the frame was not
+    /// passed to the DMA yet.
+    entry: AtomicPtr<TxRingEntry>,
+}
+
+impl Slot {
+    // NOTE(allow): this constant is only used to initialize `slots`, so
+    // every slot is a fresh pair of atomics.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const FREE: Self = Self {
+        flag: AtomicPtr::new(core::ptr::null_mut()),
+        entry: AtomicPtr::new(core::ptr::null_mut()),
+    };
+
+    /// Free this slot, and set its flag if `transmitted` is set.
+    ///
+    /// Whoever takes the entry out of the slot frees it, so the flag is set
+    /// at most once, even if this is preempted by the interrupt handler.
+    fn finish(&self, transmitted: bool) {
+        if self
+            .entry
+            .swap(core::ptr::null_mut(), Ordering::AcqRel)
+            .is_null()
+        {
+            return;
+        }
+
+        let flag = self.flag.swap(core::ptr::null_mut(), Ordering::AcqRel);
+
+        // SAFETY: only `&'static AtomicBool`s are stored in `flag`.
+        if let (true, Some(flag)) = (transmitted, unsafe { flag.as_ref() }) {
+            flag.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl Completions {
+    pub(super) const fn new() -> Self {
+        Self {
+            slots: [Slot::FREE; TX_COMPLETION_SLOTS],
+        }
+    }
+
+    /// Reserve a slot for `flag`, and clear `flag`.
+    ///
+    /// Returns the index of the slot.
+    pub(super) fn reserve(&self, flag: &'static AtomicBool) -> Result<usize, CompletionSlotsFull> {
+        let flag = flag as *const AtomicBool as *mut AtomicBool;
+
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| {
+                slot.flag
+                    .compare_exchange(
+                        core::ptr::null_mut(),
+                        flag,
+                        Ordering::AcqRel,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            })
+            .ok_or(CompletionSlotsFull)?;
+
+        // SAFETY: `flag` was created from a `&'static AtomicBool` above.
+        unsafe { (*flag).store(false, Ordering::Relaxed) };
+
+        Ok(index)
+    }
+
+    /// Free the slot `index`, which was reserved but never attached to
+    /// an entry.
+    pub(super) fn unreserve(&self, index: usize) {
+        self.slots[index]
+            .flag
+            .store(core::ptr::null_mut(), Ordering::Release);
+    }
+
+    /// Attach the reserved slot `index` to `entry`, which has just been
+    /// passed to the DMA.
+    ///
+    /// Must not be preempted by [`Completions::signal`] between passing
+    /// the entry to the DMA and this call.
+    pub(super) fn attach(&self, index: usize, entry: &TxRingEntry) {
+        self.slots[index].entry.store(
+            entry as *const TxRingEntry as *mut TxRingEntry,
+            Ordering::Release,
+        );
+    }
+
+    /// Set the flags of all frames that have been transmitted.
+    ///
+    /// Called from [`EthernetDMA::interrupt_handler`](crate::dma::EthernetDMA::interrupt_handler).
+    pub(crate) fn signal(&self) {
+        for slot in &self.slots {
+            let entry = slot.entry.load(Ordering::Acquire);
+
+            if entry.is_null() {
+                continue;
+            }
+
+            // SAFETY: `entry` points to an entry of the TX ring, which are
+            // valid until the ring is stopped, which calls `detach` first.
+            if unsafe { TxRingEntry::is_available_raw(entry) } {
+                slot.finish(true);
+            }
+        }
+    }
+
+    /// Set the flag of the frame in `entry` (if any), which has
+    /// been transmitted.
+    pub(super) fn complete(&self, entry: &TxRingEntry) {
+        for slot in &self.slots {
+            if core::ptr::eq(slot.entry.load(Ordering::Acquire), entry) {
+                slot.finish(true);
+            }
+        }
+    }
+
+    /// Free all slots that are attached to one of `entries`.
+    ///
+    /// The flags of frames that have been transmitted are set, and those of
+    /// frames that are discarded are never set. Only call this while the DMA
+    /// is stopped.
+    pub(super) fn detach(&self, entries: &[TxRingEntry]) {
+        for slot in &self.slots {
+            let attached = slot.entry.load(Ordering::Acquire);
+
+            if let Some(entry) = entries.iter().find(|entry| core::ptr::eq(*entry, attached)) {
+                slot.finish(entry.is_available());
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+    use crate::dma::{ChecksumInsertion, CrcPadControl, TxRing};
+
+    /// Pass `entry` to the DMA, and attach `slot` to it.
+    fn send(completions: &Completions, slot: Option<usize>, entry: &mut TxRingEntry) {
+        entry.send(64, None, ChecksumInsertion::None, CrcPadControl::default());
+        if let Some(slot) = slot {
+            completions.attach(slot, entry);
+        }
+    }
+
+    /// Pretend that the DMA has transmitted the frame in `entry`.
+    fn transmit(entry: &mut TxRingEntry) {
+        // TDES0, which holds the OWN bit, is the first word of the entry,
+        // see `TxRingEntry::is_available_raw`.
+        unsafe { core::ptr::write_volatile(entry as *mut TxRingEntry as *mut u32, 0) };
+    }
+
+    #[test]
+    fn completion_fires_once() {
+        static FLAG: AtomicBool = AtomicBool::new(true);
+        let completions = Completions::new();
+
+        let mut entry = TxRingEntry::INIT;
+        entry.setup(None);
+        let slot = completions.reserve(&FLAG).unwrap();
+        assert!(!FLAG.load(Ordering::Relaxed));
+        send(&completions, Some(slot), &mut entry);
+
+        // The frame is still owned by the DMA.
+        completions.signal();
+        assert!(!FLAG.load(Ordering::Relaxed));
+
+        transmit(&mut entry);
+        completions.signal();
+        assert!(FLAG.load(Ordering::Relaxed));
+
+        // Neither another interrupt nor reusing the entry set it again.
+        FLAG.store(false, Ordering::Relaxed);
+        completions.signal();
+        completions.complete(&entry);
+        assert!(!FLAG.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn slots_are_freed() {
+        static FLAG: AtomicBool = AtomicBool::new(false);
+        let completions = Completions::new();
+
+        let mut entry = TxRingEntry::INIT;
+        entry.setup(None);
+
+        for _ in 0..TX_COMPLETION_SLOTS {
+            completions.reserve(&FLAG).unwrap();
+        }
+        assert_eq!(completions.reserve(&FLAG), Err(CompletionSlotsFull));
+
+        completions.unreserve(0);
+        let slot = completions.reserve(&FLAG).unwrap();
+        send(&completions, Some(slot), &mut entry);
+        assert_eq!(completions.reserve(&FLAG), Err(CompletionSlotsFull));
+
+        transmit(&mut entry);
+        completions.signal();
+        assert_eq!(completions.reserve(&FLAG), Ok(slot));
+    }
+
+    #[test]
+    fn completion_fires_on_reuse() {
+        static FLAG: AtomicBool = AtomicBool::new(false);
+
+        let mut entries = [TxRingEntry::INIT; 2];
+        let mut ring = TxRing::new(&mut entries);
+        ring.reset();
+
+        let slot = COMPLETIONS.reserve(&FLAG).unwrap();
+        let index = ring.send_next_impl().unwrap();
+        send(&COMPLETIONS, Some(slot), &mut ring.entries[index]);
+        transmit(&mut ring.entries[index]);
+
+        let other = ring.send_next_impl().unwrap();
+        send(&COMPLETIONS, None, &mut ring.entries[other]);
+        assert!(!FLAG.load(Ordering::Relaxed));
+
+        // The interrupt handler has not run yet, so the flag is set
+        // once the entry is taken for the next frame.
+        assert_eq!(ring.send_next_impl(), Ok(index));
+        assert!(FLAG.load(Ordering::Relaxed));
+
+        ring.reset();
+    }
+
+    #[test]
+    fn completion_forgotten_on_restart() {
+        static TRANSMITTED: AtomicBool = AtomicBool::new(false);
+        static DISCARDED: AtomicBool = AtomicBool::new(false);
+
+        let mut entries = [TxRingEntry::INIT; 2];
+        let mut ring = TxRing::new(&mut entries);
+        ring.reset();
+
+        let slot = COMPLETIONS.reserve(&TRANSMITTED).unwrap();
+        let transmitted = ring.send_next_impl().unwrap();
+        send(&COMPLETIONS, Some(slot), &mut ring.entries[transmitted]);
+        transmit(&mut ring.entries[transmitted]);
+
+        let slot = COMPLETIONS.reserve(&DISCARDED).unwrap();
+        let discarded = ring.send_next_impl().unwrap();
+        send(&COMPLETIONS, Some(slot), &mut ring.entries[discarded]);
+
+        ring.reset();
+        assert!(TRANSMITTED.load(Ordering::Relaxed));
+        assert!(!DISCARDED.load(Ordering::Relaxed));
+
+        // The slot of the discarded frame was freed, so reusing its
+        // entry does not set the flag either.
+        let index = ring.send_next_impl().unwrap();
+        send(&COMPLETIONS, None, &mut ring.entries[index]);
+        transmit(&mut ring.entries[index]);
+        assert_eq!(ring.send_next_impl(), Ok(discarded));
+        assert!(!DISCARDED.load(Ordering::Relaxed));
+    }
+}
@@ -1,11 +1,14 @@
 use crate::dma::{
     desc::Descriptor,
+    fence,
     ring::{RingDescriptor, RingEntry},
-    PacketId,
+    ChecksumInsertion, CrcPadControl, PacketId,
 };
 
 #[cfg(feature = "ptp")]
 use crate::ptp::Timestamp;
+#[cfg(feature = "ptp")]
+use core::cell::Cell;
 
 /// Owned by DMA engine
 const TXDESC_0_OWN: u32 = 1 << 31;
@@ -15,9 +18,18 @@ const TXDESC_0_IC: u32 = 1 << 30;
 const TXDESC_0_FS: u32 = 1 << 28;
 /// Last segment of frame
 const TXDESC_0_LS: u32 = 1 << 29;
-/// Checksum insertion control
-const TXDESC_0_CIC0: u32 = 1 << 23;
-const TXDESC_0_CIC1: u32 = 1 << 22;
+/// Checksum insertion control (CIC, bits 23:22): IP header checksum only
+const TXDESC_0_CIC_IP_HEADER: u32 = 0b01 << 22;
+/// Checksum insertion control: IP header and payload checksum, without
+/// the pseudo-header
+const TXDESC_0_CIC_IP_HEADER_AND_PAYLOAD: u32 = 0b10 << 22;
+/// Checksum insertion control: IP header and payload checksum, including
+/// the pseudo-header
+const TXDESC_0_CIC_FULL: u32 = 0b11 << 22;
+/// Disable CRC
+const TXDESC_0_DC: u32 = 1 << 27;
+/// Disable pad
+const TXDESC_0_DP: u32 = 1 << 26;
 /// Timestamp this packet
 const TXDESC_0_TIMESTAMP_ENABLE: u32 = 1 << 25;
 /// This descriptor contains a timestamp
@@ -30,18 +42,86 @@ const TXDESC_0_TER: u32 = 1 << 21;
 const TXDESC_0_TCH: u32 = 1 << 20;
 /// Error status
 const TXDESC_0_ES: u32 = 1 << 15;
+/// Late collision
+const TXDESC_0_LCO: u32 = 1 << 9;
+/// Excessive collision
+const TXDESC_0_EC: u32 = 1 << 8;
+/// Collision count
+const TXDESC_0_CC_SHIFT: u32 = 3;
+const TXDESC_0_CC_MASK: u32 = 0b1111 << TXDESC_0_CC_SHIFT;
+/// Excessive deferral
+const TXDESC_0_ED: u32 = 1 << 2;
+/// Underflow error
+const TXDESC_0_UF: u32 = 1 << 1;
+/// Deferred bit
+const TXDESC_0_DB: u32 = 1 << 0;
 /// TX done bit
 const TXDESC_1_TBS_SHIFT: usize = 0;
 const TXDESC_1_TBS_MASK: u32 = 0x0fff << TXDESC_1_TBS_SHIFT;
 
+/// The largest amount of bytes that a descriptor can transmit from
+/// a single buffer.
+pub(super) const MAX_BUFFER_LEN: usize = (TXDESC_1_TBS_MASK >> TXDESC_1_TBS_SHIFT) as usize;
+
+/// The status of a transmitted frame, as written back by the DMA.
+///
+/// Collisions and deferrals only occur on half-duplex links. On full-duplex
+/// links, all of these fields are always zero or `false`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxStatus {
+    /// The number of collisions that occurred before the frame was
+    /// transmitted.
+    ///
+    /// This is not valid if [`TxStatus::excessive_collisions`] is set.
+    pub collision_count: u8,
+    /// The transmission was aborted after 16 successive collisions.
+    pub excessive_collisions: bool,
+    /// The transmission was aborted because a collision occurred after
+    /// the collision window (64 bytes, including the preamble) had passed.
+    pub late_collision: bool,
+    /// The transmission was deferred because the medium was busy.
+    pub deferred: bool,
+    /// The transmission was aborted because it was deferred for more than
+    /// 24,288 bit times.
+    pub excessive_deferral: bool,
+    /// The transmission was aborted because the DMA could not transfer
+    /// the frame into the TX FIFO quickly enough.
+    ///
+    /// See [`EthernetDMA::set_tx_underflow_recovery`](crate::dma::EthernetDMA::set_tx_underflow_recovery).
+    pub underflow: bool,
+    /// An error occurred during transmission, and the frame was not
+    /// transmitted successfully.
+    pub error: bool,
+}
+
+impl TxStatus {
+    fn from_tdes0(tdes0: u32) -> Self {
+        Self {
+            collision_count: ((tdes0 & TXDESC_0_CC_MASK) >> TXDESC_0_CC_SHIFT) as u8,
+            excessive_collisions: tdes0 & TXDESC_0_EC == TXDESC_0_EC,
+            late_collision: tdes0 & TXDESC_0_LCO == TXDESC_0_LCO,
+            deferred: tdes0 & TXDESC_0_DB == TXDESC_0_DB,
+            excessive_deferral: tdes0 & TXDESC_0_ED == TXDESC_0_ED,
+            underflow: tdes0 & TXDESC_0_UF == TXDESC_0_UF,
+            error: tdes0 & TXDESC_0_ES == TXDESC_0_ES,
+        }
+    }
+}
+
 /// A TX DMA Ring Descriptor
 #[repr(C)]
 pub struct TxDescriptor {
     desc: Descriptor,
     packet_id: Option<PacketId>,
+    /// A slot of the packet ID map of the ring, see [`super::id_map`].
+    id_slot: usize,
     buffer1: u32,
     next_descriptor: u32,
     is_last: bool,
+    interrupt_on_completion: bool,
+    #[cfg(feature = "ptp")]
+    timestamp_retrieved: Cell<bool>,
 }
 
 impl Default for TxDescriptor {
@@ -56,38 +136,80 @@ impl TxDescriptor {
         Self {
             desc: Descriptor::new(),
             packet_id: None,
+            id_slot: super::id_map::EMPTY,
             buffer1: 0,
             next_descriptor: 0,
             is_last: false,
+            interrupt_on_completion: true,
+            #[cfg(feature = "ptp")]
+            timestamp_retrieved: Cell::new(false),
         }
     }
 
-    #[allow(unused)]
-    fn has_error(&self) -> bool {
-        (self.desc.read(0) & TXDESC_0_ES) == TXDESC_0_ES
-    }
-
     /// Is owned by the DMA engine?
     fn is_owned(&self) -> bool {
         (self.desc.read(0) & TXDESC_0_OWN) == TXDESC_0_OWN
     }
 
-    // NOTE(allow): packet_id is unused if ptp is disabled.
-    #[allow(dead_code)]
     fn is_last(&self) -> bool {
         self.desc.read(0) & TXDESC_0_LS == TXDESC_0_LS
     }
 
-    /// Pass ownership to the DMA engine
-    fn set_owned(&mut self, length: usize, packet_id: Option<PacketId>) {
+    /// The status of the frame last transmitted by this descriptor, or
+    /// `None` if the descriptor is owned by the DMA or was never used.
+    fn status(&self) -> Option<TxStatus> {
+        if !self.is_owned() && self.is_last() {
+            Some(TxStatus::from_tdes0(self.desc.read(0)))
+        } else {
+            None
+        }
+    }
+
+    /// Pass ownership to the DMA engine, transmitting `length` bytes
+    /// from `buffer1`.
+    fn set_owned(
+        &mut self,
+        buffer1: u32,
+        length: usize,
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+        crc_pad_control: CrcPadControl,
+    ) {
+        let tdes0 = self.prepare(
+            buffer1,
+            length,
+            packet_id,
+            checksum_insertion,
+            crc_pad_control,
+        );
+
+        unsafe { self.desc.write(0, TXDESC_0_OWN | tdes0) }
+
+        // NOTE: the OWN bit must not be read back (e.g. to assert that the
+        // handoff succeeded): once it is written, the DMA may transmit the
+        // frame and clear it again at any time.
+        fence::publish();
+    }
+
+    /// Write all words of the descriptor except for TDES0, and return
+    /// the value of TDES0 without the OWN bit.
+    fn prepare(
+        &mut self,
+        buffer1: u32,
+        length: usize,
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+        crc_pad_control: CrcPadControl,
+    ) -> u32 {
         // Reconfigure packet ID
         self.packet_id = packet_id;
+        #[cfg(feature = "ptp")]
+        self.timestamp_retrieved.set(false);
 
         self.set_buffer1_len(length);
 
         // These descriptor values are sometimes overwritten by
         // timestamp data, so we rewrite this data.
-        let buffer1 = self.buffer1;
         unsafe {
             self.desc.write(2, buffer1);
         }
@@ -97,10 +219,7 @@ impl TxDescriptor {
             self.desc.write(3, buffer2);
         }
 
-        // "Preceding reads and writes cannot be moved past subsequent writes."
-        #[cfg(feature = "fence")]
-        core::sync::atomic::fence(core::sync::atomic::Ordering::Release);
-        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Release);
+        fence::release();
 
         let mut extra_flags = 0;
 
@@ -112,24 +231,24 @@ impl TxDescriptor {
             extra_flags |= TXDESC_0_TER;
         }
 
-        unsafe {
-            self.desc.write(
-                0,
-                TXDESC_0_OWN
-                    | TXDESC_0_TCH
-                    | TXDESC_0_FS
-                    | TXDESC_0_LS
-                    | TXDESC_0_CIC0
-                    | TXDESC_0_CIC1
-                    | TXDESC_0_IC
-                    | extra_flags,
-            )
+        if self.interrupt_on_completion {
+            extra_flags |= TXDESC_0_IC;
         }
 
-        // Used to flush the store buffer as fast as possible to make the buffer available for the
-        // DMA.
-        #[cfg(feature = "fence")]
-        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+        extra_flags |= match checksum_insertion {
+            ChecksumInsertion::None => 0,
+            ChecksumInsertion::IpHeader => TXDESC_0_CIC_IP_HEADER,
+            ChecksumInsertion::IpHeaderAndPayload => TXDESC_0_CIC_IP_HEADER_AND_PAYLOAD,
+            ChecksumInsertion::Full => TXDESC_0_CIC_FULL,
+        };
+
+        extra_flags |= match crc_pad_control {
+            CrcPadControl::InsertCrcAndPad => 0,
+            CrcPadControl::InsertCrc => TXDESC_0_DP,
+            CrcPadControl::Disabled => TXDESC_0_DC | TXDESC_0_DP,
+        };
+
+        TXDESC_0_TCH | TXDESC_0_FS | TXDESC_0_LS | extra_flags
     }
 
     fn set_buffer1_len(&mut self, len: usize) {
@@ -143,17 +262,31 @@ impl TxDescriptor {
     }
 
     #[cfg(feature = "ptp")]
-    fn timestamp(&self) -> Option<Timestamp> {
+    fn contains_timestamp(&self) -> bool {
         let tdes0 = self.desc.read(0);
 
         let contains_timestamp = (tdes0 & TXDESC_0_TIMESTAMP_STATUS) == TXDESC_0_TIMESTAMP_STATUS;
 
-        if !self.is_owned() && contains_timestamp && self.is_last() {
-            Timestamp::from_descriptor(&self.desc)
+        !self.is_owned() && contains_timestamp && self.is_last()
+    }
+
+    #[cfg(feature = "ptp")]
+    fn timestamp(&self) -> Option<Timestamp> {
+        if self.contains_timestamp() {
+            let timestamp = Timestamp::from_descriptor(&self.desc);
+            self.timestamp_retrieved.set(timestamp.is_some());
+            timestamp
         } else {
             None
         }
     }
+
+    /// Whether this descriptor holds the timestamp of a frame that
+    /// is associated with a packet ID, and that was never retrieved.
+    #[cfg(feature = "ptp")]
+    fn has_unretrieved_timestamp(&self) -> bool {
+        self.packet_id.is_some() && self.contains_timestamp() && !self.timestamp_retrieved.get()
+    }
 }
 
 /// A TX DMA Ring Descriptor entry
@@ -165,6 +298,11 @@ impl RingDescriptor for TxDescriptor {
             self.desc.clear();
         }
 
+        #[cfg(feature = "descriptor-sentinel")]
+        unsafe {
+            self.desc.write_sentinel();
+        }
+
         // Defer this initialization to this function, so we can have `RingEntry` on bss.
         let next_desc_addr = if let Some(next) = next {
             &next.desc as *const Descriptor as *const u8 as u32
@@ -179,13 +317,141 @@ impl RingDescriptor for TxDescriptor {
 }
 
 impl TxRingEntry {
+    /// Check whether the sentinel of this entry survived its last hand-off
+    /// to the DMA. A damaged sentinel is stamped again.
+    #[cfg(feature = "descriptor-sentinel")]
+    pub(super) fn check_sentinel(&mut self) -> bool {
+        if self.desc().desc.sentinel_intact() {
+            return true;
+        }
+
+        #[cfg(feature = "defmt")]
+        defmt::error!(
+            "TX descriptor at {=u32:#010x} was modified while owned by the DMA",
+            &self.desc().desc as *const Descriptor as u32
+        );
+
+        unsafe {
+            self.desc_mut().desc.write_sentinel();
+        }
+        false
+    }
+
     pub(super) fn is_available(&self) -> bool {
         !self.desc().is_owned()
     }
 
+    /// Check whether the entry at `entry` is available, without creating
+    /// a reference to it.
+    ///
+    /// # Safety
+    /// `entry` must point to a valid [`TxRingEntry`].
+    pub(super) unsafe fn is_available_raw(entry: *const Self) -> bool {
+        // NOTE: the first word of the descriptor is at the start of the
+        // entry, as all of the involved types are `repr(C)`.
+        (core::ptr::read_volatile(entry as *const u32) & TXDESC_0_OWN) == 0
+    }
+
+    /// Take a frame that was passed to the DMA back, without transmitting it.
+    ///
+    /// Afterwards, the entry has no [`TxStatus`], like an entry that was
+    /// never used.
+    ///
+    /// Only call this while the DMA is suspended in front of this entry, and
+    /// cannot be polled.
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    pub(super) fn take_back(&mut self) {
+        unsafe {
+            self.desc_mut().desc.write(0, 0);
+        }
+    }
+
+    /// Log the decoded state of this entry, its raw descriptor, and the
+    /// first `bytes` bytes of its buffer.
+    #[cfg(feature = "defmt")]
+    pub(super) fn dump(&self, index: usize, marker: &str, bytes: usize) {
+        let desc = self.desc();
+        let buffer = self.buffer();
+
+        defmt::info!(
+            "TX {=usize}{=str}: owned={=bool} status={} desc={:#010x} data={=[u8]:02x}",
+            index,
+            marker,
+            desc.is_owned(),
+            self.status(),
+            desc.desc.words(),
+            buffer[..bytes.min(buffer.len())]
+        );
+    }
+
+    /// Configure whether the DMA raises a TX interrupt once it has
+    /// transmitted the next frame sent from this entry.
+    pub(super) fn set_interrupt_on_completion(&mut self, interrupt: bool) {
+        self.desc_mut().interrupt_on_completion = interrupt;
+    }
+
     /// Only call this if [`TxRingEntry::is_available`]
-    pub(super) fn send(&mut self, length: usize, packet_id: Option<PacketId>) {
-        self.desc_mut().set_owned(length, packet_id);
+    pub(super) fn send(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+        crc_pad_control: CrcPadControl,
+    ) {
+        let buffer1 = self.desc().buffer1;
+        self.desc_mut().set_owned(
+            buffer1,
+            length,
+            packet_id,
+            checksum_insertion,
+            crc_pad_control,
+        );
+    }
+
+    /// Transmit `frame` directly, instead of the buffer of this entry.
+    ///
+    /// Only call this if [`TxRingEntry::is_available`], and only if `frame`
+    /// is accessible by the DMA.
+    pub(super) fn send_external(
+        &mut self,
+        frame: &'static [u8],
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+        crc_pad_control: CrcPadControl,
+    ) {
+        self.desc_mut().set_owned(
+            frame.as_ptr() as u32,
+            frame.len(),
+            packet_id,
+            checksum_insertion,
+            crc_pad_control,
+        );
+    }
+
+    pub fn has_packet_id(&self, packet_id: &PacketId) -> bool {
+        self.desc().packet_id.as_ref() == Some(packet_id)
+    }
+
+    pub(super) fn packet_id(&self) -> Option<&PacketId> {
+        self.desc().packet_id.as_ref()
+    }
+
+    /// Stop associating this entry with its packet ID, if any.
+    pub(super) fn forget_packet_id(&mut self) {
+        self.desc_mut().packet_id = None;
+    }
+
+    pub(super) fn id_slot(&self) -> usize {
+        self.desc().id_slot
+    }
+
+    pub(super) fn set_id_slot(&mut self, entry: usize) {
+        self.desc_mut().id_slot = entry;
+    }
+
+    /// The status of the frame last transmitted from this entry.
+    pub fn status(&self) -> Option<TxStatus> {
+        self.desc().status()
     }
 
     /// Only call this if [`TxRingEntry::is_available`]
@@ -201,11 +467,28 @@ impl TxRingEntry {
 
 #[cfg(feature = "ptp")]
 impl TxRingEntry {
-    pub fn has_packet_id(&self, packet_id: &PacketId) -> bool {
-        self.desc().packet_id.as_ref() == Some(packet_id)
-    }
-
     pub fn timestamp(&self) -> Option<Timestamp> {
         self.desc().timestamp().clone()
     }
+
+    /// Whether this entry holds a timestamp that was never retrieved, and
+    /// would be lost if the entry is used to transmit a new frame.
+    pub fn has_unretrieved_timestamp(&self) -> bool {
+        self.desc().has_unretrieved_timestamp()
+    }
+
+    /// Take the packet ID and timestamp of the frame in this entry, if
+    /// the frame has been transmitted and a timestamp was captured for it.
+    ///
+    /// Afterwards, this entry is no longer associated with the packet ID.
+    pub fn take_timestamp(&mut self) -> Option<(PacketId, Timestamp)> {
+        if !self.is_available() || self.desc().packet_id.is_none() {
+            return None;
+        }
+
+        let timestamp = self.timestamp()?;
+        let packet_id = self.desc_mut().packet_id.take()?;
+
+        Some((packet_id, timestamp))
+    }
 }
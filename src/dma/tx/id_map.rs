@@ -0,0 +1,215 @@
+//! A map from [`PacketId`]s to the entries of a [`TxRing`](super::TxRing)
+//! that hold them.
+//!
+//! The map is a hash table with open addressing and linear probing. Its slots
+//! are stored in the entries of the ring themselves, one slot per entry, so
+//! its capacity is always equal to the size of the ring: every entry can hold
+//! a packet ID, and every packet ID that is held by an entry is mapped.
+//!
+//! The map is only modified through a `&mut TxRing`, while a frame is
+//! passed to the DMA or a timestamp is taken out of the ring. The DMA
+//! writes the status and timestamp of a transmitted frame into its
+//! descriptor, so the completion of a frame (and the interrupt that is
+//! raised for it) never needs to modify the map.
+
+use super::TxRingEntry;
+use crate::dma::PacketId;
+
+/// The value of a slot that does not map a packet ID.
+pub(super) const EMPTY: usize = usize::MAX;
+
+/// The slot in which the search for `id` starts.
+fn home(id: &PacketId, len: usize) -> usize {
+    id.0 as usize % len
+}
+
+/// The slot in which the search for the packet ID held by `entry` starts.
+///
+/// Only call this for entries that are stored in a slot.
+fn home_of(entries: &[TxRingEntry], entry: usize) -> usize {
+    match entries[entry].packet_id() {
+        Some(id) => home(id, entries.len()),
+        None => unreachable!("A slot maps an entry without a packet ID"),
+    }
+}
+
+/// Find the entry that holds `id`.
+pub(super) fn find(entries: &[TxRingEntry], id: &PacketId) -> Option<usize> {
+    let len = entries.len();
+    let mut slot = home(id, len);
+
+    for _ in 0..len {
+        let entry = entries[slot].id_slot();
+
+        if entry == EMPTY {
+            return None;
+        } else if entries[entry].has_packet_id(id) {
+            return Some(entry);
+        }
+
+        slot = (slot + 1) % len;
+    }
+
+    None
+}
+
+/// Map the packet ID held by `entry`, if any.
+///
+/// If another entry already holds the same packet ID, that entry no longer
+/// holds it afterwards. Returns `true` if the timestamp of that entry was
+/// lost because of that.
+///
+/// Only call this if `entry` is not mapped.
+pub(super) fn insert(entries: &mut [TxRingEntry], entry: usize) -> bool {
+    let id = match entries[entry].packet_id() {
+        Some(id) => id.clone(),
+        None => return false,
+    };
+
+    let lost = match find(entries, &id) {
+        Some(other) => {
+            #[cfg(feature = "ptp")]
+            let lost = !entries[other].is_available() || entries[other].has_unretrieved_timestamp();
+            #[cfg(not(feature = "ptp"))]
+            let lost = false;
+
+            remove(entries, other, &id);
+            entries[other].forget_packet_id();
+            lost
+        }
+        None => false,
+    };
+
+    let len = entries.len();
+    let mut slot = home(&id, len);
+
+    // At most one packet ID is held per entry, so a free slot always exists.
+    while entries[slot].id_slot() != EMPTY {
+        slot = (slot + 1) % len;
+    }
+
+    entries[slot].set_id_slot(entry);
+
+    lost
+}
+
+/// Remove the mapping of `id`, which is held by `entry`.
+///
+/// `entry` may already have stopped holding `id`, but no other entry
+/// may have been changed since `id` was mapped.
+pub(super) fn remove(entries: &mut [TxRingEntry], entry: usize, id: &PacketId) {
+    let len = entries.len();
+    let mut hole = home(id, len);
+
+    for _ in 0..len {
+        match entries[hole].id_slot() {
+            e if e == entry => break,
+            EMPTY => return,
+            _ => hole = (hole + 1) % len,
+        }
+    }
+
+    // Move every entry of the probe sequence that follows the hole back
+    // into it, if its search would otherwise no longer reach it.
+    let mut slot = hole;
+    for _ in 1..len {
+        slot = (slot + 1) % len;
+
+        let moved = entries[slot].id_slot();
+        if moved == EMPTY {
+            break;
+        }
+
+        let home = home_of(entries, moved);
+        if (slot + len - home) % len >= (slot + len - hole) % len {
+            entries[hole].set_id_slot(moved);
+            hole = slot;
+        }
+    }
+
+    entries[hole].set_id_slot(EMPTY);
+}
+
+/// Map the packet IDs held by all of `entries`, discarding the
+/// current mapping.
+///
+/// Of multiple entries that hold the same packet ID, only the last one
+/// keeps it.
+pub(super) fn rebuild(entries: &mut [TxRingEntry]) {
+    for entry in entries.iter_mut() {
+        entry.set_id_slot(EMPTY);
+    }
+
+    for entry in 0..entries.len() {
+        insert(entries, entry);
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+    use crate::dma::{ChecksumInsertion, CrcPadControl};
+
+    fn send(entries: &mut [TxRingEntry], entry: usize, id: Option<u32>) -> bool {
+        if let Some(id) = entries[entry].packet_id().cloned() {
+            remove(entries, entry, &id);
+        }
+
+        entries[entry].send(
+            64,
+            id.map(PacketId),
+            ChecksumInsertion::None,
+            CrcPadControl::default(),
+        );
+
+        insert(entries, entry)
+    }
+
+    fn found(entries: &[TxRingEntry], id: u32) -> Option<usize> {
+        find(entries, &PacketId(id))
+    }
+
+    #[test]
+    fn colliding_ids() {
+        let mut entries = [TxRingEntry::INIT; 4];
+        rebuild(&mut entries);
+
+        // The searches for 1, 5 and 9 all start in the same slot.
+        assert!(!send(&mut entries, 0, Some(1)));
+        assert!(!send(&mut entries, 1, Some(5)));
+        assert!(!send(&mut entries, 2, Some(9)));
+        assert!(!send(&mut entries, 3, Some(2)));
+
+        assert_eq!(found(&entries, 1), Some(0));
+        assert_eq!(found(&entries, 5), Some(1));
+        assert_eq!(found(&entries, 9), Some(2));
+        assert_eq!(found(&entries, 2), Some(3));
+        assert_eq!(found(&entries, 13), None);
+
+        // Reusing entries moves the remaining IDs back into their slots.
+        send(&mut entries, 0, None);
+        send(&mut entries, 1, Some(3));
+
+        assert_eq!(found(&entries, 1), None);
+        assert_eq!(found(&entries, 5), None);
+        assert_eq!(found(&entries, 9), Some(2));
+        assert_eq!(found(&entries, 2), Some(3));
+        assert_eq!(found(&entries, 3), Some(1));
+    }
+
+    #[test]
+    fn reused_id() {
+        let mut entries = [TxRingEntry::INIT; 4];
+        rebuild(&mut entries);
+
+        assert!(!send(&mut entries, 0, Some(7)));
+        // The first frame is still owned by the DMA, so its timestamp is lost.
+        assert_eq!(send(&mut entries, 1, Some(7)), cfg!(feature = "ptp"));
+
+        assert_eq!(found(&entries, 7), Some(1));
+        assert!(entries[0].packet_id().is_none());
+
+        rebuild(&mut entries);
+        assert_eq!(found(&entries, 7), Some(1));
+    }
+}
@@ -1,26 +1,71 @@
-use super::PacketId;
+use super::{DmaError, PacketId, PacketIdNotFound};
 use crate::peripherals::ETHERNET_DMA;
 
 #[cfg(feature = "ptp")]
-use super::{PacketIdNotFound, Timestamp};
+use super::Timestamp;
 
 mod descriptor;
-pub use descriptor::{TxDescriptor, TxRingEntry};
+pub use descriptor::{TxDescriptor, TxRingEntry, TxStatus};
 
-#[cfg(any(feature = "ptp", feature = "async-await"))]
-use core::task::Poll;
+mod builder;
+pub use builder::{TxBufferFull, TxFrameBuilder};
+
+mod id_map;
+
+pub(super) mod completion;
+use completion::COMPLETIONS;
+pub use completion::{CompletionSlotsFull, TX_COMPLETION_SLOTS};
+
+use core::{sync::atomic::AtomicBool, task::Poll};
 
 /// Errors that can occur during Ethernet TX
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, PartialEq)]
 pub enum TxError {
     /// Ring buffer is full
     WouldBlock,
+    /// The sentinel word of a descriptor was modified while the descriptor
+    /// was owned by the DMA. This indicates a missing memory barrier, a cache
+    /// coherency issue, or memory corruption.
+    ///
+    /// The sentinel is stamped again, so the next send attempt can use the
+    /// descriptor.
+    ///
+    /// Only returned if the `descriptor-sentinel` feature is enabled.
+    Corruption,
+    /// The frame is too long to be transmitted, see [`TxRing::send_static`].
+    FrameTooLong,
+}
+
+/// The state of a [`TxRing`] that is advanced whenever an entry is
+/// taken from it, see [`TxRing::send_state`].
+#[derive(Debug, Clone, Copy)]
+struct SendState {
+    next_entry: usize,
+    since_interrupt: usize,
+    sent_frames: u32,
+    #[cfg(feature = "ptp")]
+    lost_timestamps: u32,
 }
 
 /// Tx DMA state
+///
+/// The MACs of all supported MCUs (STM32F107, STM32F4 and STM32F7) have
+/// a single transmit queue, so frames are always transmitted in the order
+/// in which they are sent.
 pub struct TxRing<'a> {
     entries: &'a mut [TxRingEntry],
     next_entry: usize,
+    high_water_mark: usize,
+    interrupt_coalescing: usize,
+    since_interrupt: usize,
+    sent_frames: u32,
+    #[cfg(feature = "ptp")]
+    lost_timestamps: u32,
+    /// The entry that holds the frame that waits for its launch time,
+    /// see [`TxRing::send_held`].
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    launch: Option<usize>,
 }
 
 impl<'ring> TxRing<'ring> {
@@ -28,14 +73,60 @@ impl<'ring> TxRing<'ring> {
     ///
     /// `start()` will be needed before `send()`
     pub(crate) fn new(entries: &'ring mut [TxRingEntry]) -> Self {
+        id_map::rebuild(entries);
+
         TxRing {
             entries,
             next_entry: 0,
+            high_water_mark: 0,
+            interrupt_coalescing: 1,
+            since_interrupt: 0,
+            sent_frames: 0,
+            #[cfg(feature = "ptp")]
+            lost_timestamps: 0,
+            #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+            launch: None,
         }
     }
 
+    /// Take the entries out of this ring.
+    ///
+    /// Only call this while the DMA is stopped.
+    pub(crate) fn into_entries(self) -> &'ring mut [TxRingEntry] {
+        self.entries
+    }
+
     /// Start the Tx DMA engine
     pub(crate) fn start(&mut self, eth_dma: &ETHERNET_DMA) {
+        self.reset();
+
+        let ring_ptr = self.entries[0].desc() as *const TxDescriptor;
+        // Register TxDescriptor
+        eth_dma
+            .dmatdlar
+            // Note: unsafe block required for `stm32f107`.
+            .write(|w| unsafe { w.stl().bits(ring_ptr as u32) });
+
+        super::fence::release();
+
+        // Start transmission
+        eth_dma.dmaomr.modify(|_, w| w.st().set_bit());
+    }
+
+    /// Set up all entries of this ring, and rewind it to the first entry,
+    /// where the DMA starts once the descriptor list address is written.
+    fn reset(&mut self) {
+        // Frames that were not transmitted before the ring was stopped are
+        // discarded, so their completion flags must never be set.
+        COMPLETIONS.detach(self.entries);
+
+        for entry in self.entries.iter_mut() {
+            // The timestamps of frames sent before the ring was stopped
+            // are not retained across a restart.
+            entry.forget_packet_id();
+        }
+        id_map::rebuild(self.entries);
+
         // Setup ring
         {
             let mut previous: Option<&mut TxRingEntry> = None;
@@ -50,32 +141,49 @@ impl<'ring> TxRing<'ring> {
             }
         }
 
-        let ring_ptr = self.entries[0].desc() as *const TxDescriptor;
-        // Register TxDescriptor
-        eth_dma
-            .dmatdlar
-            // Note: unsafe block required for `stm32f107`.
-            .write(|w| unsafe { w.stl().bits(ring_ptr as u32) });
+        self.next_entry = 0;
+        self.since_interrupt = 0;
 
-        // "Preceding reads and writes cannot be moved past subsequent writes."
-        #[cfg(feature = "fence")]
-        core::sync::atomic::fence(core::sync::atomic::Ordering::Release);
-
-        // We don't need a compiler fence here because all interactions with `Descriptor` are
-        // volatiles
-
-        // Start transmission
-        eth_dma.dmaomr.modify(|_, w| w.st().set_bit());
+        // A frame that waited for its launch time was discarded with
+        // the others.
+        #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+        {
+            self.launch = None;
+        }
     }
 
     /// Stop the TX DMA
-    pub(crate) fn stop(&self, eth_dma: &ETHERNET_DMA) {
+    pub(crate) fn stop(&self, eth_dma: &ETHERNET_DMA) -> Result<(), DmaError> {
         eth_dma.dmaomr.modify(|_, w| w.st().clear_bit());
 
         // DMA accesses do not stop before the running state
         // of the DMA has changed to something other than
         // running.
-        while self.is_running() {}
+        super::wait_until(eth_dma, DmaError::SuspendTimeout, || !self.is_running())?;
+
+        // The interrupt handler must not look at the entries once
+        // they may be released.
+        COMPLETIONS.detach(self.entries);
+        Ok(())
+    }
+
+    /// Undo [`TxRing::stop`], e.g. if it timed out.
+    ///
+    /// The DMA continues at the descriptor at which it stopped, so, unlike
+    /// [`TxRing::start`], this does not discard any frames.
+    pub(crate) fn cancel_stop(&self, eth_dma: &ETHERNET_DMA) {
+        eth_dma.dmaomr.modify(|_, w| w.st().set_bit());
+    }
+
+    /// Check whether all frames in this ring have been transmitted.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.iter().all(|entry| entry.is_available())
+    }
+
+    /// The amount of frames that were taken from this ring for sending,
+    /// wrapping around on overflow.
+    pub(crate) fn sent_frames(&self) -> u32 {
+        self.sent_frames
     }
 
     /// If this returns `true`, the next `send` will succeed.
@@ -83,17 +191,155 @@ impl<'ring> TxRing<'ring> {
         self.entries[self.next_entry].is_available()
     }
 
+    /// Get the largest number of frames that were waiting to be
+    /// transmitted by the DMA at the same time.
+    ///
+    /// If this reaches the length of the ring, sending has had to wait for
+    /// the DMA to free an entry at least once.
+    ///
+    /// The value is sampled whenever a frame is sent, and is determined
+    /// using the current descriptor address of the DMA. It is not updated
+    /// while the descriptor list base address is changed.
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    /// Reset the value returned by [`TxRing::high_water_mark`] to 0.
+    pub fn reset_high_water_mark(&mut self) {
+        self.high_water_mark = 0;
+    }
+
+    /// Only request a TX interrupt for every `count`th frame.
+    ///
+    /// See [`EthernetDMA::set_tx_interrupt_coalescing`](super::EthernetDMA::set_tx_interrupt_coalescing).
+    pub(crate) fn set_interrupt_coalescing(&mut self, count: usize) {
+        self.interrupt_coalescing = count.max(1);
+        self.since_interrupt = 0;
+    }
+
+    /// The amount of frames per requested TX interrupt.
+    pub(crate) fn interrupt_coalescing(&self) -> usize {
+        self.interrupt_coalescing
+    }
+
+    /// Log the state of all entries of this ring.
+    ///
+    /// See [`EthernetDMA::dump_rings`](super::EthernetDMA::dump_rings).
+    #[cfg(feature = "defmt")]
+    pub(crate) fn dump(&self, bytes: usize) {
+        // SAFETY: we only perform an atomic read of `dmachtdr`.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+        let current = eth_dma.dmachtdr.read().bits();
+
+        defmt::info!(
+            "TX ring: {=usize} entries, running={=bool}, DMA at {=u32:#010x}",
+            self.entries.len(),
+            self.is_running(),
+            current
+        );
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let marker = TxRingEntry::dump_marker(self.entries, index, self.next_entry, current);
+            entry.dump(index, marker, bytes);
+        }
+    }
+
+    /// Update the high-water mark with the amount of entries that
+    /// are owned by the DMA.
+    ///
+    /// Only call this directly after passing an entry to the DMA.
+    fn update_high_water_mark(&mut self) {
+        // SAFETY: we only perform an atomic read of `dmachtdr`.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+        let current = eth_dma.dmachtdr.read().bits();
+
+        if let Some(dma_entry) = TxRingEntry::index_of(self.entries, current) {
+            let pending = pending_frames(
+                self.next_entry,
+                dma_entry,
+                self.entries.len(),
+                self.entries[self.next_entry].is_available(),
+            );
+            self.high_water_mark = self.high_water_mark.max(pending);
+        }
+    }
+
+    /// Save the state that [`TxRing::send_next_impl`] advances.
+    fn send_state(&self) -> SendState {
+        SendState {
+            next_entry: self.next_entry,
+            since_interrupt: self.since_interrupt,
+            sent_frames: self.sent_frames,
+            #[cfg(feature = "ptp")]
+            lost_timestamps: self.lost_timestamps,
+        }
+    }
+
+    /// Undo all calls to [`TxRing::send_next_impl`] since `state` was saved
+    /// with [`TxRing::send_state`].
+    ///
+    /// None of the entries that were taken may have been passed to the DMA.
+    fn restore_send_state(&mut self, state: SendState) {
+        let SendState {
+            next_entry,
+            since_interrupt,
+            sent_frames,
+            #[cfg(feature = "ptp")]
+            lost_timestamps,
+        } = state;
+
+        self.next_entry = next_entry;
+        self.since_interrupt = since_interrupt;
+        self.sent_frames = sent_frames;
+        #[cfg(feature = "ptp")]
+        {
+            self.lost_timestamps = lost_timestamps;
+        }
+    }
+
     /// Check if we can send the next TX entry.
     ///
     /// If [`Ok(res)`] is returned, the caller of must ensure
     /// that [`self.entries[res].send()`](TxRingEntry::send) is called
     /// before a new invocation of `send_next_impl`.
     fn send_next_impl(&mut self) -> Result<usize, TxError> {
+        // Polling the DMA for the next frame would also release the frame
+        // that waits for its launch time.
+        #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+        {
+            if self.launch_pending() {
+                return Err(TxError::WouldBlock);
+            }
+            self.launch = None;
+        }
+
         let entries_len = self.entries.len();
         let entry_num = self.next_entry;
         let entry = &mut self.entries[entry_num];
 
         if entry.is_available() {
+            #[cfg(feature = "descriptor-sentinel")]
+            if !entry.check_sentinel() {
+                return Err(TxError::Corruption);
+            }
+
+            // The interrupt handler may not have seen that the previous
+            // frame in this entry was transmitted yet.
+            COMPLETIONS.complete(entry);
+
+            #[cfg(feature = "ptp")]
+            if entry.has_unretrieved_timestamp() {
+                self.lost_timestamps = self.lost_timestamps.saturating_add(1);
+            }
+
+            self.since_interrupt += 1;
+            let interrupt = self.since_interrupt >= self.interrupt_coalescing;
+            if interrupt {
+                self.since_interrupt = 0;
+            }
+            entry.set_interrupt_on_completion(interrupt);
+
+            self.sent_frames = self.sent_frames.wrapping_add(1);
             self.next_entry = (self.next_entry + 1) % entries_len;
             Ok(entry_num)
         } else {
@@ -101,6 +347,28 @@ impl<'ring> TxRing<'ring> {
         }
     }
 
+    /// Pass the entry at `index` to the DMA with `send`, and map the
+    /// packet ID that it is sent with.
+    fn send_entry<F>(&mut self, index: usize, send: F)
+    where
+        F: FnOnce(&mut TxRingEntry),
+    {
+        if let Some(id) = self.entries[index].packet_id().cloned() {
+            id_map::remove(self.entries, index, &id);
+        }
+
+        send(&mut self.entries[index]);
+
+        let lost = id_map::insert(self.entries, index);
+
+        #[cfg(feature = "ptp")]
+        if lost {
+            self.lost_timestamps = self.lost_timestamps.saturating_add(1);
+        }
+        #[cfg(not(feature = "ptp"))]
+        let _ = lost;
+    }
+
     /// Prepare a packet for sending.
     ///
     /// Write the data that you wish to send to the buffer
@@ -124,9 +392,189 @@ impl<'ring> TxRing<'ring> {
             idx: entry,
             length,
             packet_id,
+            checksum_insertion: ChecksumInsertion::None,
+            crc_pad_control: CrcPadControl::InsertCrcAndPad,
+            completion: None,
+        })
+    }
+
+    /// Start building a frame in the buffer of the next TX entry.
+    ///
+    /// Unlike with [`TxRing::send_next`], the length of the frame does not
+    /// have to be known in advance: it is determined by the data written with
+    /// [`TxFrameBuilder::write`]. See [`TxFrameBuilder`].
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    pub fn build_frame<'borrow>(
+        &'borrow mut self,
+        packet_id: Option<PacketId>,
+    ) -> Result<TxFrameBuilder<'borrow, 'ring>, TxError> {
+        let rollback = self.send_state();
+        let entry = self.send_next_impl()?;
+
+        Ok(TxFrameBuilder::new(self, entry, packet_id, rollback))
+    }
+
+    /// Prepare a frame with `f`, and hold it in the ring until the DMA
+    /// is polled by the PTP target time interrupt.
+    ///
+    /// The frame is passed to the DMA right away. The DMA only fetches it
+    /// once it is polled, as it has transmitted all other frames, and is
+    /// suspended in front of the entry that holds the frame. If frames are
+    /// still waiting to be transmitted, this returns
+    /// [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    ///
+    /// See [`EthernetDMA::send_at`](super::EthernetDMA::send_at).
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    pub(crate) fn send_held<F>(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        f: F,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let rollback = self.send_state();
+        let index = self.send_next_impl()?;
+        let entry = &mut self.entries[index];
+
+        let buffer = entry.buffer_mut();
+        assert!(length <= buffer.len(), "Not enough space in TX buffer");
+        f(&mut buffer[..length]);
+
+        let checksum_insertion =
+            checksum_insertion_for(&entry.buffer()[..length], ChecksumInsertion::None);
+
+        // The target time interrupt polls the DMA, so it must not run between
+        // checking that the DMA is suspended and passing the frame to it.
+        cortex_m::interrupt::free(|_| {
+            if !self.is_empty() || self.running_state() != RunningState::Suspended {
+                self.restore_send_state(rollback);
+                return Err(TxError::WouldBlock);
+            }
+
+            self.send_entry(index, |entry| {
+                entry.send(
+                    length,
+                    packet_id,
+                    checksum_insertion,
+                    CrcPadControl::default(),
+                )
+            });
+            self.launch = Some(index);
+
+            Ok(())
+        })?;
+
+        self.update_high_water_mark();
+
+        Ok(())
+    }
+
+    /// Check whether a frame sent with [`TxRing::send_held`] still waits
+    /// for its launch time, or is being transmitted.
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    pub(crate) fn launch_pending(&self) -> bool {
+        self.launch
+            .is_some_and(|index| !self.entries[index].is_available())
+    }
+
+    /// Drop the frame that waits for its launch time, if the DMA has not
+    /// been polled for it yet.
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    pub(crate) fn cancel_launch(&mut self) {
+        let index = match self.launch.take() {
+            Some(index) => index,
+            None => return,
+        };
+
+        // The DMA stays suspended in front of the held frame until it is
+        // polled by the target time interrupt, which cannot preempt this. If
+        // it was polled already, the frame is transmitted as usual.
+        cortex_m::interrupt::free(|_| {
+            if self.running_state() == RunningState::Suspended
+                && !self.entries[index].is_available()
+            {
+                self.entries[index].take_back();
+            }
         })
     }
 
+    /// Make sure that the DMA processes this ring, unless that would
+    /// release a frame that waits for its launch time.
+    pub(crate) fn resume(&self) {
+        #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+        if self.launch_pending() {
+            return;
+        }
+
+        Self::demand_poll();
+    }
+
+    /// Send a frame that is stored in `'static` memory.
+    ///
+    /// If `frame` resides in memory that the DMA can access (see
+    /// [`is_dma_accessible`](super::is_dma_accessible)), the DMA transmits it
+    /// directly from there, without copying it into the TX ring. Otherwise,
+    /// `frame` is copied into the TX ring first, since the Ethernet DMA cannot
+    /// read all memories of all supported MCUs (most notably, flash memory on
+    /// all of them, and the CCM on the STM32F4).
+    ///
+    /// Checksums are inserted as described in [`TxPacket::set_checksum_insertion`].
+    /// The checksums are inserted into the frame while it is being transmitted,
+    /// so `frame` itself is never modified.
+    ///
+    /// If `frame` is longer than a descriptor can transmit (4095 bytes), or
+    /// if it has to be copied and is longer than the buffers of the ring,
+    /// [`Err(TxError::FrameTooLong)`](TxError::FrameTooLong) is returned.
+    pub fn send_static(
+        &mut self,
+        frame: &'static [u8],
+        packet_id: Option<PacketId>,
+        checksum_insertion: ChecksumInsertion,
+    ) -> Result<(), TxError> {
+        let zero_copy = super::is_dma_accessible(frame.as_ptr(), frame.len());
+        let max_len = if zero_copy {
+            descriptor::MAX_BUFFER_LEN
+        } else {
+            super::MTU
+        };
+
+        if frame.len() > max_len {
+            return Err(TxError::FrameTooLong);
+        }
+
+        let index = self.send_next_impl()?;
+
+        let checksum_insertion = checksum_insertion_for(frame, checksum_insertion);
+
+        self.send_entry(index, |entry| {
+            if zero_copy {
+                entry.send_external(
+                    frame,
+                    packet_id,
+                    checksum_insertion,
+                    CrcPadControl::default(),
+                );
+            } else {
+                entry.buffer_mut()[..frame.len()].copy_from_slice(frame);
+                entry.send(
+                    frame.len(),
+                    packet_id,
+                    checksum_insertion,
+                    CrcPadControl::default(),
+                );
+            }
+        });
+
+        self.update_high_water_mark();
+        Self::demand_poll();
+
+        Ok(())
+    }
+
     /// Prepare a packet for sending.
     ///
     /// Write the data that you wish to send to the buffer
@@ -158,12 +606,15 @@ impl<'ring> TxRing<'ring> {
             idx: entry,
             length,
             packet_id,
+            checksum_insertion: ChecksumInsertion::None,
+            crc_pad_control: CrcPadControl::InsertCrcAndPad,
+            completion: None,
         }
     }
 
     /// Demand that the DMA engine polls the current `TxDescriptor`
     /// (when we just transferred ownership to the hardware).
-    pub(crate) fn demand_poll(&self) {
+    pub(crate) fn demand_poll() {
         // SAFETY: we only perform an atomic write to `dmatpdr`
         let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
         eth_dma.dmatpdr.write(|w| {
@@ -205,20 +656,43 @@ impl<'ring> TxRing<'ring> {
     }
 }
 
-#[cfg(feature = "ptp")]
 impl TxRing<'_> {
     fn entry_for_id(&self, id: &PacketId) -> Option<usize> {
-        self.entries.iter().enumerate().find_map(
-            |(idx, e)| {
-                if e.has_packet_id(id) {
-                    Some(idx)
-                } else {
-                    None
-                }
-            },
-        )
+        id_map::find(self.entries, id)
+    }
+
+    /// Poll to check if the frame with the given ID has been
+    /// transmitted, and get its [`TxStatus`] if so.
+    pub fn poll_completion(
+        &self,
+        packet_id: &PacketId,
+    ) -> Poll<Result<TxStatus, PacketIdNotFound>> {
+        let entry = if let Some(entry) = self.entry_for_id(packet_id) {
+            entry
+        } else {
+            return Poll::Ready(Err(PacketIdNotFound));
+        };
+
+        match self.entries[entry].status() {
+            Some(status) => Poll::Ready(Ok(status)),
+            None => Poll::Pending,
+        }
+    }
+
+    /// Get the [`TxStatus`] of the most recently sent frame, if it has
+    /// been transmitted.
+    ///
+    /// Returns `None` if no frame was sent yet, or if the most recently
+    /// sent frame is still owned by the DMA.
+    pub fn last_status(&self) -> Option<TxStatus> {
+        let entries_len = self.entries.len();
+        let last_entry = (self.next_entry + entries_len - 1) % entries_len;
+        self.entries[last_entry].status()
     }
+}
 
+#[cfg(feature = "ptp")]
+impl TxRing<'_> {
     fn entry_available(&self, index: usize) -> bool {
         self.entries[index].is_available()
     }
@@ -242,6 +716,16 @@ impl TxRing<'_> {
 
     /// Poll to check if the timestamp for the given ID is already
     /// available.
+    ///
+    /// The ring maps the packet IDs of its entries to the entries that hold
+    /// them, so this takes constant time on average, regardless of the size
+    /// of the ring. The map holds one packet ID per entry of the ring.
+    ///
+    /// A packet ID is held by at most one entry: if a frame is sent with a
+    /// packet ID that an earlier frame in the ring was sent with, the earlier
+    /// frame loses its packet ID, and its timestamp (if it has not been
+    /// retrieved yet) is lost (see [`TxRing::lost_timestamps`]). Afterwards,
+    /// polling for that packet ID always refers to the most recent frame.
     pub fn poll_timestamp(
         &self,
         packet_id: &PacketId,
@@ -259,6 +743,57 @@ impl TxRing<'_> {
         }
     }
 
+    /// Get the number of TX timestamps that were lost because their
+    /// TX descriptor was reused before they were retrieved.
+    ///
+    /// TX timestamps are stored in the TX descriptors themselves, so at most
+    /// as many timestamps as there are entries in this ring can be outstanding.
+    /// A timestamp is retrieved once [`TxRing::poll_timestamp`] (or one of the
+    /// functions built on it) or [`TxRing::drain_tx_timestamps`] has returned it.
+    /// If a frame is sent using an entry whose timestamp was not retrieved yet,
+    /// that timestamp is lost, and this counter is incremented. Subsequently
+    /// polling for its packet ID returns [`PacketIdNotFound`], or the results
+    /// for the frame that is now associated with it, if its packet ID was reused.
+    /// The counter is also incremented if a frame is sent with a packet ID that
+    /// is still held by an earlier frame, see [`TxRing::poll_timestamp`].
+    ///
+    /// If this counter increases, timestamps are requested faster than they are
+    /// retrieved: the ring should be enlarged, or timestamps should be retrieved
+    /// more often. The counter saturates at [`u32::MAX`].
+    pub fn lost_timestamps(&self) -> u32 {
+        self.lost_timestamps
+    }
+
+    /// Reset the value returned by [`TxRing::lost_timestamps`] to 0.
+    pub fn reset_lost_timestamps(&mut self) {
+        self.lost_timestamps = 0;
+    }
+
+    /// Take the timestamps of all transmitted frames for which a timestamp
+    /// is available, oldest first.
+    ///
+    /// Every timestamp is returned at most once: afterwards, its packet ID is
+    /// no longer associated with any TX descriptor, so the timestamp can no
+    /// longer be retrieved using [`TxRing::poll_timestamp`]. Frames that are still
+    /// owned by the DMA are skipped.
+    ///
+    /// Timestamps are stored in the TX descriptors themselves, so at most one
+    /// timestamp per entry of this ring is retained. The timestamp of a frame is
+    /// lost once its entry is used to transmit a new frame, regardless of whether
+    /// it was drained.
+    pub fn drain_tx_timestamps(&mut self) -> impl Iterator<Item = (PacketId, Timestamp)> + '_ {
+        let entries = &mut *self.entries;
+        let entries_len = entries.len();
+        let next_entry = self.next_entry;
+
+        (0..entries_len).filter_map(move |offset| {
+            let index = (next_entry + offset) % entries_len;
+            let (packet_id, timestamp) = entries[index].take_timestamp()?;
+            id_map::remove(entries, index, &packet_id);
+            Some((packet_id, timestamp))
+        })
+    }
+
     /// Wait until the timestamp for the given ID is available.
     #[cfg(feature = "async-await")]
     pub async fn timestamp(
@@ -313,6 +848,10 @@ pub struct TxPacket<'borrow, 'ring> {
     idx: usize,
     length: usize,
     packet_id: Option<PacketId>,
+    checksum_insertion: ChecksumInsertion,
+    crc_pad_control: CrcPadControl,
+    /// The completion slot reserved for this packet.
+    completion: Option<usize>,
 }
 
 impl core::ops::Deref for TxPacket<'_, '_> {
@@ -334,11 +873,375 @@ impl TxPacket<'_, '_> {
     pub fn send(self) {
         drop(self);
     }
+
+    /// Configure which checksums the hardware should insert into this packet.
+    ///
+    /// By default, no checksums are inserted, and the packet is sent exactly as
+    /// it was written.
+    ///
+    /// Checksums are never inserted into packets whose EtherType field (that of
+    /// the inner frame for VLAN tagged frames) does not indicate an IPv4 or IPv6
+    /// frame at the time they are sent, regardless of this setting. Other frames,
+    /// such as ARP, are always sent unmodified.
+    pub fn set_checksum_insertion(&mut self, checksum_insertion: ChecksumInsertion) {
+        self.checksum_insertion = checksum_insertion;
+    }
+
+    /// Set `flag` once this packet has been transmitted.
+    ///
+    /// `flag` is cleared when the packet is passed to the DMA, and set by
+    /// [`EthernetDMA::interrupt_handler`](super::EthernetDMA::interrupt_handler)
+    /// once the DMA has passed the descriptor of the packet back, so a task can
+    /// wait for this packet alone instead of polling the ring. The packet
+    /// always raises a TX interrupt, regardless of
+    /// [`EthernetDMA::set_tx_interrupt_coalescing`](super::EthernetDMA::set_tx_interrupt_coalescing).
+    ///
+    /// At most [`TX_COMPLETION_SLOTS`] packets with a completion flag can be
+    /// in flight at the same time. A slot is reserved (and `flag` is cleared)
+    /// by this call, and freed once the flag is set, so this returns
+    /// [`CompletionSlotsFull`] if all slots are in use. Calling this again
+    /// replaces the flag, and keeps the slot.
+    ///
+    /// The flag is set exactly once. If the interrupt handler did not run
+    /// since the packet was transmitted, the flag is set at the latest when
+    /// its descriptor is reused for another packet. If the DMA is stopped and
+    /// restarted before the packet was transmitted (see
+    /// [`EthernetDMA::wake`](super::EthernetDMA::wake)), the packet is
+    /// discarded and the flag is never set.
+    pub fn set_completion_flag(
+        &mut self,
+        flag: &'static AtomicBool,
+    ) -> Result<(), CompletionSlotsFull> {
+        if let Some(slot) = self.completion.take() {
+            COMPLETIONS.unreserve(slot);
+        }
+
+        self.completion = Some(COMPLETIONS.reserve(flag)?);
+        Ok(())
+    }
+
+    /// Configure whether the MAC should append the CRC (FCS) to this packet,
+    /// and pad it to the minimum frame length.
+    ///
+    /// By default, the MAC pads the packet and appends the CRC
+    /// ([`CrcPadControl::InsertCrcAndPad`]). See [`CrcPadControl`] for the
+    /// amount of bytes that are transmitted with each setting.
+    pub fn set_crc_pad_control(&mut self, crc_pad_control: CrcPadControl) {
+        self.crc_pad_control = crc_pad_control;
+    }
 }
 
 impl Drop for TxPacket<'_, '_> {
     fn drop(&mut self) {
-        self.ring.entries[self.idx].send(self.length, self.packet_id.clone());
-        self.ring.demand_poll();
+        let checksum_insertion = checksum_insertion_for(self, self.checksum_insertion);
+        let (length, packet_id, crc_pad_control) =
+            (self.length, self.packet_id.clone(), self.crc_pad_control);
+
+        match self.completion {
+            Some(slot) => cortex_m::interrupt::free(|_| {
+                self.ring.send_entry(self.idx, |entry| {
+                    entry.set_interrupt_on_completion(true);
+                    entry.send(length, packet_id, checksum_insertion, crc_pad_control)
+                });
+                COMPLETIONS.attach(slot, &self.ring.entries[self.idx]);
+            }),
+            None => self.ring.send_entry(self.idx, |entry| {
+                entry.send(length, packet_id, checksum_insertion, crc_pad_control)
+            }),
+        }
+        self.ring.update_high_water_mark();
+        TxRing::demand_poll();
+    }
+}
+
+/// The checksums that the hardware inserts into a frame that is being
+/// transmitted.
+///
+/// Checksum insertion is only performed for IPv4 and IPv6 frames. It is
+/// never performed for other frames, regardless of the selected value.
+///
+/// The variants map to the values of the Checksum Insertion Control (CIC)
+/// field of the TX descriptor, in order. Which variant to use depends on
+/// the payload of the IP packet:
+///
+/// | Payload                                    | Variant                                                       |
+/// |--------------------------------------------|---------------------------------------------------------------|
+/// | TCP, UDP, ICMP                             | [`Full`](ChecksumInsertion::Full)                             |
+/// | TCP, UDP with a precomputed pseudo-header  | [`IpHeaderAndPayload`](ChecksumInsertion::IpHeaderAndPayload) |
+/// | UDP over IPv4 without a checksum           | [`IpHeader`](ChecksumInsertion::IpHeader)                     |
+/// | Any other protocol                         | [`IpHeader`](ChecksumInsertion::IpHeader)                     |
+///
+/// Selecting a variant that does not match the contents of the checksum
+/// fields of the frame silently produces wrong checksums. IPv6 headers have
+/// no checksum, so the IP header checksum is only inserted into IPv4 frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecksumInsertion {
+    /// Do not insert any checksums, and transmit the frame unmodified
+    /// (CIC = 0b00).
+    ///
+    /// Use this for frames whose checksums were already calculated, or that
+    /// the MAC must not modify (such as encrypted or tunneled frames).
+    #[default]
+    None,
+    /// Only insert the IPv4 header checksum (CIC = 0b01).
+    ///
+    /// The payload is never modified, so use this for IPv4 UDP datagrams
+    /// whose checksum is zero (i.e. disabled), and for all IP payloads that
+    /// the MAC can not calculate the checksum of. The header checksum field
+    /// may have any value.
+    IpHeader,
+    /// Insert the IPv4 header checksum, and the TCP, UDP or ICMP checksum,
+    /// but do not include the pseudo-header in the latter (CIC = 0b10).
+    ///
+    /// The checksum field of the payload must contain the checksum of the
+    /// pseudo-header (without its complement), which the MAC adds the
+    /// checksum of the payload to. Use this if the pseudo-header checksum is
+    /// already known, e.g. because it was computed by a network stack that
+    /// expects partial checksum offloading. ICMP over IPv4 has no
+    /// pseudo-header, so its checksum field must be zero.
+    IpHeaderAndPayload,
+    /// Insert the IPv4 header checksum, and the TCP, UDP or ICMP checksum
+    /// (including the pseudo-header) (CIC = 0b11).
+    ///
+    /// The checksum fields in the frame must be zero. Use this for TCP
+    /// segments, UDP datagrams and ICMP messages whose checksum is not
+    /// calculated in software.
+    Full,
+}
+
+/// Whether the MAC appends the CRC (frame check sequence) to a frame
+/// that is being transmitted, and pads it to the minimum frame length.
+///
+/// The variants map to the Disable CRC (DC) and Disable Pad (DP) bits
+/// of the TX descriptor. A legal Ethernet frame is at least 64 bytes long,
+/// including its 4 bytes of CRC. [`CrcPadControl::transmitted_length`]
+/// returns the amount of bytes that are transmitted for a frame (excluding the
+/// preamble and the start frame delimiter).
+///
+/// The fourth encoding (DC = 1, DP = 0) is not offered: with it, the MAC pads
+/// short frames and appends the CRC to them regardless of DC, but does not
+/// append the CRC to frames of the minimum length or longer, so the frame
+/// must or must not contain its CRC depending on its length.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrcPadControl {
+    /// Pad frames shorter than 60 bytes with zeros, and append the CRC
+    /// (DC = 0, DP = 0).
+    ///
+    /// `max(length, 60) + 4` bytes are transmitted, so every frame has
+    /// the minimum length.
+    #[default]
+    InsertCrcAndPad,
+    /// Append the CRC, but do not pad short frames (DC = 0, DP = 1).
+    ///
+    /// `length + 4` bytes are transmitted. Frames shorter than 60 bytes
+    /// are transmitted as runt frames, which are discarded by most receivers.
+    /// Use this if the frame already contains its padding, or to send
+    /// deliberately undersized test frames.
+    InsertCrc,
+    /// Neither append the CRC nor pad short frames (DC = 1, DP = 1).
+    ///
+    /// `length` bytes are transmitted. The frame must contain the CRC (for
+    /// instance, a caller-computed or deliberately wrong value), and its
+    /// length must include these last 4 bytes. Frames shorter than 64 bytes
+    /// (including the CRC) are transmitted as runt frames.
+    ///
+    /// Inserting checksums (see [`ChecksumInsertion`]) modifies the frame
+    /// after its CRC was computed, so it should not be combined with this.
+    Disabled,
+}
+
+impl CrcPadControl {
+    /// The minimum length of a frame, excluding its CRC.
+    pub const MIN_FRAME_LEN: usize = 60;
+
+    /// The length of the CRC that the MAC appends to a frame.
+    pub const CRC_LEN: usize = 4;
+
+    /// The amount of bytes that the MAC transmits for a frame of `length`
+    /// bytes, excluding the preamble and the start frame delimiter.
+    pub const fn transmitted_length(&self, length: usize) -> usize {
+        match self {
+            CrcPadControl::InsertCrcAndPad if length < Self::MIN_FRAME_LEN => {
+                Self::MIN_FRAME_LEN + Self::CRC_LEN
+            }
+            CrcPadControl::InsertCrcAndPad | CrcPadControl::InsertCrc => length + Self::CRC_LEN,
+            CrcPadControl::Disabled => length,
+        }
+    }
+}
+
+/// Determine which checksums are inserted into `frame`, given that
+/// `requested` was selected for it.
+fn checksum_insertion_for(frame: &[u8], requested: ChecksumInsertion) -> ChecksumInsertion {
+    if is_ip(frame) {
+        requested
+    } else {
+        ChecksumInsertion::None
+    }
+}
+
+/// Check whether `frame` should have its checksums inserted by
+/// the hardware, based on its EtherType.
+fn is_ip(frame: &[u8]) -> bool {
+    const ETHERTYPE_IPV4: u16 = 0x0800;
+    const ETHERTYPE_IPV6: u16 = 0x86DD;
+    const ETHERTYPE_VLAN: u16 = 0x8100;
+
+    let ethertype_at = |offset: usize| {
+        frame
+            .get(offset..offset + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    let ethertype = match ethertype_at(12) {
+        Some(ETHERTYPE_VLAN) => ethertype_at(16),
+        other => other,
+    };
+
+    matches!(ethertype, Some(ETHERTYPE_IPV4 | ETHERTYPE_IPV6))
+}
+
+/// The amount of entries of a ring with `entries_len` entries that are
+/// waiting to be transmitted, if the DMA is at `dma_entry` and the next
+/// frame will be sent from the entry at `next_entry`, which is available
+/// if `next_available` is set.
+fn pending_frames(
+    next_entry: usize,
+    dma_entry: usize,
+    entries_len: usize,
+    next_available: bool,
+) -> usize {
+    // If the DMA is at the next entry, it has either sent all
+    // entries, or all entries are waiting to be sent.
+    match (next_entry + entries_len - dma_entry) % entries_len {
+        0 if !next_available => entries_len,
+        pending => pending,
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    fn send(ring: &mut TxRing) -> Result<usize, TxError> {
+        let index = ring.send_next_impl()?;
+        ring.entries[index].send(64, None, ChecksumInsertion::None, CrcPadControl::default());
+        Ok(index)
+    }
+
+    #[test]
+    fn restart_rewinds_ring() {
+        let mut entries = [TxRingEntry::INIT; 4];
+        let mut ring = TxRing::new(&mut entries);
+        ring.reset();
+        ring.set_interrupt_coalescing(2);
+
+        assert_eq!(send(&mut ring), Ok(0));
+        assert_eq!(send(&mut ring), Ok(1));
+        assert_eq!(send(&mut ring), Ok(2));
+        assert_eq!(ring.since_interrupt, 1);
+
+        // Stopping and starting the ring (e.g. by `prepare_for_sleep`
+        // and `wake`) points the DMA at the first entry again.
+        ring.reset();
+
+        assert_eq!(ring.next_entry, 0);
+        assert_eq!(ring.since_interrupt, 0);
+        assert_eq!(ring.interrupt_coalescing(), 2);
+        assert_eq!(send(&mut ring), Ok(0));
+        assert_eq!(send(&mut ring), Ok(1));
+    }
+
+    #[test]
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    fn restart_forgets_held_frame() {
+        let mut entries = [TxRingEntry::INIT; 4];
+        let mut ring = TxRing::new(&mut entries);
+        ring.reset();
+
+        // Hold a frame like `send_held`, which checks the state of the DMA.
+        let held = send(&mut ring).unwrap();
+        ring.launch = Some(held);
+
+        assert!(ring.launch_pending());
+        assert!(!ring.is_empty());
+        assert_eq!(send(&mut ring), Err(TxError::WouldBlock));
+
+        ring.reset();
+
+        assert!(!ring.launch_pending());
+        assert!(ring.is_empty());
+        assert_eq!(send(&mut ring), Ok(0));
+    }
+
+    #[test]
+    fn static_frame_too_long() {
+        static FRAME: [u8; 2000] = [0; 2000];
+
+        let mut entries = [TxRingEntry::INIT; 4];
+        let mut ring = TxRing::new(&mut entries);
+        ring.reset();
+
+        // The frame is not accessible by the DMA on the host, so it would
+        // have to be copied into an entry.
+        assert!(!super::super::is_dma_accessible(
+            FRAME.as_ptr(),
+            FRAME.len()
+        ));
+        assert_eq!(
+            ring.send_static(&FRAME, None, ChecksumInsertion::None),
+            Err(TxError::FrameTooLong)
+        );
+
+        // No entry was taken.
+        assert_eq!(ring.next_entry, 0);
+        assert_eq!(ring.sent_frames(), 0);
+    }
+
+    #[test]
+    fn aborted_frame_rolls_back() {
+        let mut entries = [TxRingEntry::INIT; 4];
+        let mut ring = TxRing::new(&mut entries);
+        ring.reset();
+        ring.set_interrupt_coalescing(2);
+
+        assert_eq!(send(&mut ring), Ok(0));
+        let sent_frames = ring.sent_frames();
+
+        let mut builder = ring.build_frame(None).unwrap();
+        builder.write(&[0xAA; 14]).unwrap();
+        drop(builder);
+
+        assert_eq!(ring.next_entry, 1);
+        assert_eq!(ring.since_interrupt, 1);
+        assert_eq!(ring.sent_frames(), sent_frames);
+
+        // The entry of the aborted frame is used for the next frame, which
+        // requests the interrupt that is due after two frames.
+        assert_eq!(send(&mut ring), Ok(1));
+        assert_eq!(ring.since_interrupt, 0);
+        assert_eq!(send(&mut ring), Ok(2));
+    }
+
+    #[test]
+    fn pending_frames_wrap_around() {
+        // Entries 1 and 2 wait for the DMA, which is at entry 1.
+        assert_eq!(pending_frames(3, 1, 4, true), 2);
+        // Entries 3 and 0 wait for the DMA, which is at entry 3.
+        assert_eq!(pending_frames(1, 3, 4, true), 2);
+        // The DMA has sent all entries.
+        assert_eq!(pending_frames(2, 2, 4, true), 0);
+        // All entries wait for the DMA.
+        assert_eq!(pending_frames(2, 2, 4, false), 4);
+    }
+
+    #[test]
+    fn transmitted_length() {
+        assert_eq!(CrcPadControl::InsertCrcAndPad.transmitted_length(14), 64);
+        assert_eq!(CrcPadControl::InsertCrcAndPad.transmitted_length(100), 104);
+        assert_eq!(CrcPadControl::InsertCrc.transmitted_length(14), 18);
+        assert_eq!(CrcPadControl::Disabled.transmitted_length(14), 14);
     }
 }
@@ -0,0 +1,299 @@
+//! A high-level driver that ties the MAC, DMA, PHY and PTP together.
+
+use core::ops::DerefMut;
+
+use ieee802_3_miim::{phy::PhyWithSpeed, Miim, Phy};
+
+use crate::{
+    dma::{EthStats, EthernetDMA},
+    mac::{EthernetMAC, Speed},
+};
+
+#[cfg(feature = "ptp")]
+use crate::ptp::EthernetPTP;
+
+/// An event reported by [`EthernetDriver::poll`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The link came up, or its speed changed. The MAC has been
+    /// configured for the new speed.
+    LinkUp(Speed),
+    /// The link went down.
+    LinkDown,
+    /// At least one received frame is waiting in the RX ring.
+    ///
+    /// Receive the frames with [`EthernetDMA::recv_next`] through
+    /// [`EthernetDriver::dma_mut`].
+    RxReady,
+    /// All frames that were sent since this event was last reported have
+    /// been transmitted.
+    ///
+    /// The status of the most recently sent frame can be read with
+    /// [`EthernetDMA::last_tx_status`].
+    TxDone,
+    /// An error occurred.
+    Error(DriverError),
+}
+
+/// An error reported by [`EthernetDriver::poll`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriverError {
+    /// The link came up, but the PHY can not report its speed, or has
+    /// negotiated a speed that the MAC does not support (see
+    /// [`EthernetMAC::establish_link`]). The MAC has not been reconfigured.
+    UnsupportedLink,
+    /// The DMA encountered a fatal bus error, and has disabled all of its
+    /// bus accesses. The ethernet peripheral must be released and
+    /// initialized again.
+    ///
+    /// This error is reported by every call to [`EthernetDriver::poll`]
+    /// until then.
+    FatalBusError,
+}
+
+/// The events reported by a single call to [`EthernetDriver::poll`].
+///
+/// The events are yielded in the order link change,
+/// [`DriverError::FatalBusError`], [`DriverError::UnsupportedLink`],
+/// [`Event::TxDone`], [`Event::RxReady`]. A fatal bus error comes first among
+/// the errors, as it affects the peripheral as a whole. No event is yielded
+/// more than once.
+#[derive(Debug, Clone, Default)]
+pub struct Events {
+    link: Option<Event>,
+    fatal_bus_error: bool,
+    unsupported_link: bool,
+    tx_done: bool,
+    rx_ready: bool,
+}
+
+impl Iterator for Events {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if let Some(link) = self.link.take() {
+            Some(link)
+        } else if core::mem::take(&mut self.fatal_bus_error) {
+            Some(Event::Error(DriverError::FatalBusError))
+        } else if core::mem::take(&mut self.unsupported_link) {
+            Some(Event::Error(DriverError::UnsupportedLink))
+        } else if core::mem::take(&mut self.tx_done) {
+            Some(Event::TxDone)
+        } else if core::mem::take(&mut self.rx_ready) {
+            Some(Event::RxReady)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkState {
+    Down,
+    Up(Speed),
+    Unsupported,
+}
+
+/// A driver that owns the DMA, the PHY (and through it, the MAC), and the
+/// PTP peripheral, and drives them with a single [`EthernetDriver::poll`].
+///
+/// This is the simplest way of using the ethernet peripheral: create it from
+/// the [`Parts`](crate::Parts) returned by [`new_with_mii`](crate::new_with_mii)
+/// and a PHY that wraps the [`EthernetMACWithMii`](crate::mac::EthernetMACWithMii),
+/// and call [`EthernetDriver::poll`] periodically. The parts remain accessible,
+/// so everything that this driver does not cover can still be done through them.
+#[cfg_attr(
+    feature = "ptp",
+    doc = "",
+    doc = "The PTP peripheral is owned by the driver so that all parts stay together, but [`EthernetDriver::poll`] does not use it: the target time events of the PTP peripheral are cleared by [`EthernetPTP::interrupt_handler`], which must be called in the `ETH` interrupt, so polling for them would race with it. Use [`EthernetDriver::ptp_mut`] to configure it."
+)]
+pub struct EthernetDriver<'rx, 'tx, P> {
+    dma: EthernetDMA<'rx, 'tx>,
+    phy: P,
+    #[cfg(feature = "ptp")]
+    ptp: EthernetPTP,
+    link: LinkState,
+    tx_sent_frames: u32,
+    tx_pending: bool,
+}
+
+impl<'rx, 'tx, P> EthernetDriver<'rx, 'tx, P> {
+    /// Create a new driver from its parts.
+    ///
+    /// The link is assumed to be down until it is reported to be up by
+    /// the first call to [`EthernetDriver::poll`].
+    pub fn new(
+        dma: EthernetDMA<'rx, 'tx>,
+        phy: P,
+        #[cfg(feature = "ptp")] ptp: EthernetPTP,
+    ) -> Self {
+        let tx_sent_frames = dma.tx_ring.sent_frames();
+
+        Self {
+            dma,
+            phy,
+            #[cfg(feature = "ptp")]
+            ptp,
+            link: LinkState::Down,
+            tx_sent_frames,
+            tx_pending: false,
+        }
+    }
+
+    /// Poll the PHY and the DMA, and get the events that occurred since the
+    /// last call.
+    ///
+    /// The link state is read from the PHY on every call (see
+    /// [`phy_state`](crate::mac::phy_state)), so this function should be called
+    /// periodically, e.g. whenever the `ETH` interrupt or a timer fires. Once
+    /// the link comes up, the MAC is configured for the speed negotiated by the
+    /// PHY (see [`EthernetMAC::establish_link`]). The speed is only read again
+    /// once the link went down and came up again, as the PHY only negotiates
+    /// a new speed when the link is re-established.
+    ///
+    /// Received frames are not taken out of the RX ring, as they borrow
+    /// the ring: receive them through [`EthernetDriver::dma_mut`] after
+    /// [`Event::RxReady`] was reported. Sent frames do not need to be reclaimed,
+    /// as the DMA passes their descriptors back once they were transmitted.
+    pub fn poll<M>(&mut self) -> Events
+    where
+        M: Miim + DerefMut<Target = EthernetMAC>,
+        P: PhyWithSpeed<M>,
+    {
+        let mut events = Events::default();
+
+        let link = if !self.phy.phy_link_up() {
+            LinkState::Down
+        } else if self.link != LinkState::Down {
+            // The link stayed up, so it still has the negotiated speed.
+            self.link
+        } else if let Some(speed) = EthernetMAC::establish_link(&mut self.phy, &mut self.dma) {
+            LinkState::Up(speed)
+        } else {
+            LinkState::Unsupported
+        };
+
+        if link != self.link {
+            match link {
+                LinkState::Down => events.link = Some(Event::LinkDown),
+                LinkState::Up(speed) => events.link = Some(Event::LinkUp(speed)),
+                LinkState::Unsupported => events.unsupported_link = true,
+            }
+            self.link = link;
+        }
+
+        events.fatal_bus_error = self.dma.eth_dma.dmasr.read().fbes().bit_is_set();
+
+        let sent_frames = self.dma.tx_ring.sent_frames();
+        self.tx_pending |= sent_frames != self.tx_sent_frames;
+        self.tx_sent_frames = sent_frames;
+
+        if self.tx_pending && self.dma.tx_ring.is_empty() {
+            events.tx_done = true;
+            self.tx_pending = false;
+        }
+
+        events.rx_ready = self.dma.rx_available();
+
+        events
+    }
+
+    /// Take a snapshot of the statistics of the DMA and the MAC.
+    ///
+    /// See [`EthernetDMA::stats`].
+    pub fn stats<M>(&mut self) -> EthStats
+    where
+        M: Miim + DerefMut<Target = EthernetMAC>,
+        P: Phy<M>,
+    {
+        self.dma.stats(self.phy.get_miim())
+    }
+
+    /// The speed of the link, if it is up.
+    ///
+    /// This is the state reported by the last call to [`EthernetDriver::poll`].
+    pub fn link_speed(&self) -> Option<Speed> {
+        match self.link {
+            LinkState::Up(speed) => Some(speed),
+            _ => None,
+        }
+    }
+
+    /// Access the DMA.
+    pub fn dma(&self) -> &EthernetDMA<'rx, 'tx> {
+        &self.dma
+    }
+
+    /// Mutably access the DMA, e.g. to send and receive frames.
+    pub fn dma_mut(&mut self) -> &mut EthernetDMA<'rx, 'tx> {
+        &mut self.dma
+    }
+
+    /// Access the PHY.
+    pub fn phy(&self) -> &P {
+        &self.phy
+    }
+
+    /// Mutably access the PHY.
+    ///
+    /// The MAC can be accessed through the [`Miim`] of the PHY, with
+    /// [`Phy::get_miim`].
+    pub fn phy_mut(&mut self) -> &mut P {
+        &mut self.phy
+    }
+}
+
+#[cfg(feature = "ptp")]
+impl<'rx, 'tx, P> EthernetDriver<'rx, 'tx, P> {
+    /// Access the PTP peripheral.
+    pub fn ptp(&self) -> &EthernetPTP {
+        &self.ptp
+    }
+
+    /// Mutably access the PTP peripheral.
+    pub fn ptp_mut(&mut self) -> &mut EthernetPTP {
+        &mut self.ptp
+    }
+
+    /// Split this [`EthernetDriver`] into its parts.
+    pub fn split(self) -> (EthernetDMA<'rx, 'tx>, P, EthernetPTP) {
+        (self.dma, self.phy, self.ptp)
+    }
+}
+
+#[cfg(not(feature = "ptp"))]
+impl<'rx, 'tx, P> EthernetDriver<'rx, 'tx, P> {
+    /// Split this [`EthernetDriver`] into its parts.
+    pub fn split(self) -> (EthernetDMA<'rx, 'tx>, P) {
+        (self.dma, self.phy)
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_all_errors() {
+        let mut events = Events {
+            link: None,
+            fatal_bus_error: true,
+            unsupported_link: true,
+            tx_done: false,
+            rx_ready: true,
+        };
+
+        assert_eq!(
+            events.next(),
+            Some(Event::Error(DriverError::FatalBusError))
+        );
+        assert_eq!(
+            events.next(),
+            Some(Event::Error(DriverError::UnsupportedLink))
+        );
+        assert_eq!(events.next(), Some(Event::RxReady));
+        assert_eq!(events.next(), None);
+    }
+}
@@ -30,15 +30,25 @@ pub mod dma;
 #[cfg(feature = "device-selected")]
 pub mod mac;
 
+#[cfg(feature = "device-selected")]
+pub mod driver;
+#[doc(inline)]
+#[cfg(feature = "device-selected")]
+pub use driver::EthernetDriver;
+
 #[cfg(feature = "device-selected")]
 pub mod setup;
 #[doc(inline)]
 #[cfg(feature = "device-selected")]
-pub use setup::{EthPins, Parts, PartsIn};
+pub use setup::{EthPins, EthernetPins, Interface, MiiPins, Parts, PartsIn};
 
 #[cfg(feature = "device-selected")]
 pub(crate) mod peripherals;
 
+mod wait;
+
+pub mod util;
+
 #[cfg(feature = "ptp")]
 pub mod ptp;
 
@@ -47,9 +57,8 @@ pub use smoltcp;
 
 #[cfg(feature = "device-selected")]
 use {
-    dma::{EthernetDMA, RxRingEntry, TxRingEntry},
+    dma::{DmaError, EthernetDMA, RxRingEntry, TxRingEntry},
     mac::{EthernetMAC, EthernetMACWithMii, MdcPin, MdioPin, Speed, WrongClock},
-    setup::*,
 };
 
 #[cfg(all(feature = "device-selected", feature = "ptp"))]
@@ -66,12 +75,49 @@ pub struct InterruptReason {
     pub tx: bool,
     /// A DMA error occured.
     pub dma_error: bool,
+    /// The DMA has filled the first buffer of a received packet.
+    ///
+    /// See [`EthernetDMA::set_early_interrupts`](dma::EthernetDMA::set_early_interrupts).
+    pub early_rx: bool,
+    /// The DMA has transferred a packet to be transmitted into the TX FIFO.
+    ///
+    /// See [`EthernetDMA::set_early_interrupts`](dma::EthernetDMA::set_early_interrupts).
+    pub early_tx: bool,
+    /// The TX DMA was suspended because of a TX underflow.
+    ///
+    /// See [`EthernetDMA::set_tx_underflow_recovery`](dma::EthernetDMA::set_tx_underflow_recovery).
+    pub tx_underflow: bool,
     #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
     /// The target time configured for PTP has
     /// passed.
     pub time_passed: bool,
 }
 
+/// An error that can occur while creating the ethernet driver.
+#[cfg(feature = "device-selected")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum InitError {
+    /// HCLK is not set correctly.
+    WrongClock(WrongClock),
+    /// The DMA could not be reset.
+    Dma(DmaError),
+}
+
+#[cfg(feature = "device-selected")]
+impl From<WrongClock> for InitError {
+    fn from(value: WrongClock) -> Self {
+        Self::WrongClock(value)
+    }
+}
+
+#[cfg(feature = "device-selected")]
+impl From<DmaError> for InitError {
+    fn from(value: DmaError) -> Self {
+        Self::Dma(value)
+    }
+}
+
 /// Handle the `ETH` interrupt.
 ///
 /// This function wakes wakers and resets
@@ -87,6 +133,9 @@ pub fn eth_interrupt_handler() -> InterruptReason {
         rx: dma.is_rx,
         tx: dma.is_tx,
         dma_error: dma.is_error,
+        early_rx: dma.is_early_rx,
+        early_tx: dma.is_early_tx,
+        tx_underflow: dma.is_tx_underflow,
         #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
         time_passed: is_time_trigger,
     }
@@ -99,6 +148,9 @@ pub fn eth_interrupt_handler() -> InterruptReason {
 /// and configures the ETH MAC and DMA peripherals.
 /// Automatically sets slew rate to VeryHigh.
 ///
+/// The MAC is configured for the [`Interface`] of `pins`: pass [`EthPins`] for
+/// RMII, or [`MiiPins`] for MII.
+///
 /// The speed of the MAC is set to [`Speed::FullDuplexBase100Tx`].
 /// This can be changed using [`EthernetMAC::set_speed`].
 ///
@@ -107,36 +159,32 @@ pub fn eth_interrupt_handler() -> InterruptReason {
 ///
 /// # Note
 /// - Make sure that the buffers reside in a memory region that is
-/// accessible by the peripheral. Core-Coupled Memory (CCM) is
-/// usually not accessible.
+///   accessible by the peripheral. Core-Coupled Memory (CCM) is
+///   usually not accessible.
 /// - HCLK must be at least 25 MHz.
+/// - The reference clock must be provided by the PHY, otherwise resetting
+///   the DMA fails with [`DmaError::ResetTimeout`].
 #[cfg(feature = "device-selected")]
-pub fn new<'rx, 'tx, REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>(
+pub fn new<'rx, 'tx, PINS>(
     parts: PartsIn,
     rx_buffer: &'rx mut [RxRingEntry],
     tx_buffer: &'tx mut [TxRingEntry],
     clocks: Clocks,
-    pins: EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>,
-) -> Result<Parts<'rx, 'tx, EthernetMAC>, WrongClock>
+    pins: PINS,
+) -> Result<Parts<'rx, 'tx, EthernetMAC>, InitError>
 where
-    REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
-    CRS: RmiiCrsDv + AlternateVeryHighSpeed,
-    TXEN: RmiiTxEN + AlternateVeryHighSpeed,
-    TXD0: RmiiTxD0 + AlternateVeryHighSpeed,
-    TXD1: RmiiTxD1 + AlternateVeryHighSpeed,
-    RXD0: RmiiRxD0 + AlternateVeryHighSpeed,
-    RXD1: RmiiRxD1 + AlternateVeryHighSpeed,
+    PINS: EthernetPins,
 {
     // Configure all of the pins correctly
     pins.setup_pins();
 
     // Set up the clocks and reset the MAC periperhal
-    setup::setup();
+    setup::setup(PINS::INTERFACE);
 
     let eth_mac = parts.mac.into();
 
     // Congfigure and start up the ethernet DMA.
-    let dma = EthernetDMA::new(parts.dma.into(), rx_buffer, tx_buffer);
+    let dma = EthernetDMA::new(parts.dma.into(), rx_buffer, tx_buffer)?;
 
     // Configure the ethernet PTP
     #[cfg(feature = "ptp")]
@@ -162,6 +210,9 @@ where
 /// and configures the ETH MAC and DMA peripherals.
 /// Automatically sets slew rate to VeryHigh.
 ///
+/// The MAC is configured for the [`Interface`] of `pins`: pass [`EthPins`] for
+/// RMII, or [`MiiPins`] for MII.
+///
 /// This method does not initialise the external PHY.
 ///
 /// The speed of the MAC is set to [`Speed::FullDuplexBase100Tx`].
@@ -172,27 +223,23 @@ where
 ///
 /// # Note
 /// - Make sure that the buffers reside in a memory region that is
-/// accessible by the peripheral. Core-Coupled Memory (CCM) is
-/// usually not accessible.
+///   accessible by the peripheral. Core-Coupled Memory (CCM) is
+///   usually not accessible.
 /// - HCLK must be at least 25 MHz.
+/// - The reference clock must be provided by the PHY, otherwise resetting
+///   the DMA fails with [`DmaError::ResetTimeout`].
 #[cfg(feature = "device-selected")]
-pub fn new_with_mii<'rx, 'tx, REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1, MDIO, MDC>(
+pub fn new_with_mii<'rx, 'tx, PINS, MDIO, MDC>(
     parts: PartsIn,
     rx_buffer: &'rx mut [RxRingEntry],
     tx_buffer: &'tx mut [TxRingEntry],
     clocks: Clocks,
-    pins: EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>,
+    pins: PINS,
     mdio: MDIO,
     mdc: MDC,
-) -> Result<Parts<'rx, 'tx, EthernetMACWithMii<MDIO, MDC>>, WrongClock>
+) -> Result<Parts<'rx, 'tx, EthernetMACWithMii<MDIO, MDC>>, InitError>
 where
-    REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
-    CRS: RmiiCrsDv + AlternateVeryHighSpeed,
-    TXEN: RmiiTxEN + AlternateVeryHighSpeed,
-    TXD0: RmiiTxD0 + AlternateVeryHighSpeed,
-    TXD1: RmiiTxD1 + AlternateVeryHighSpeed,
-    RXD0: RmiiRxD0 + AlternateVeryHighSpeed,
-    RXD1: RmiiRxD1 + AlternateVeryHighSpeed,
+    PINS: EthernetPins,
     MDIO: MdioPin,
     MDC: MdcPin,
 {
@@ -200,12 +247,12 @@ where
     pins.setup_pins();
 
     // Set up the clocks and reset the MAC periperhal
-    setup::setup();
+    setup::setup(PINS::INTERFACE);
 
     let eth_mac = parts.mac.into();
 
     // Congfigure and start up the ethernet DMA.
-    let dma = EthernetDMA::new(parts.dma.into(), rx_buffer, tx_buffer);
+    let dma = EthernetDMA::new(parts.dma.into(), rx_buffer, tx_buffer)?;
 
     // Configure the ethernet PTP
     #[cfg(feature = "ptp")]
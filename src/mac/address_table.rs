@@ -0,0 +1,278 @@
+use super::EthernetMAC;
+
+/// Address enable
+const MACAHR_AE: u32 = 1 << 31;
+/// Source address: compare the address to the source address of frames
+const MACAHR_SA: u32 = 1 << 30;
+/// The offset of the mask byte control field
+const MACAHR_MBC_SHIFT: u32 = 24;
+/// The width of the mask byte control field
+const MACAHR_MBC_MASK: u32 = 0x3F << MACAHR_MBC_SHIFT;
+
+/// The perfect filter address slots that are managed by a
+/// [`MacAddressTable`].
+fn slots() -> core::ops::Range<usize> {
+    1..super::HwFeatures::CURRENT.address_filters
+}
+
+/// A mask of the bytes of a MAC address that the address filter
+/// ignores when comparing it to the destination address of a frame.
+///
+/// Bit `n` masks byte `n` of the address, so `ByteMask::new(0b100000)`
+/// matches all addresses that share the first 5 bytes.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteMask(u8);
+
+impl ByteMask {
+    /// Compare all bytes of the address.
+    pub const NONE: Self = Self(0);
+
+    /// Create a new mask from `bits`.
+    ///
+    /// Only the lower 6 bits are used.
+    pub const fn new(bits: u8) -> Self {
+        Self(bits & 0x3F)
+    }
+
+    /// Additionally ignore byte `byte` (0 to 5) of the address.
+    ///
+    /// # Panics
+    /// This function panics if `byte` is greater than 5.
+    pub const fn with_masked(self, byte: usize) -> Self {
+        assert!(byte < 6, "A MAC address only has 6 bytes");
+        Self(self.0 | (1 << byte))
+    }
+
+    /// Check whether byte `byte` of the address is ignored.
+    pub const fn is_masked(&self, byte: usize) -> bool {
+        byte < 6 && self.0 & (1 << byte) != 0
+    }
+
+    /// The raw bits of this mask.
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for ByteMask {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// How the source address filter treats frames whose source address
+/// matches one of the configured addresses.
+///
+/// See [`EthernetMAC::set_source_filter`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaFilterMode {
+    /// Only accept frames from the configured addresses.
+    Accept,
+    /// Drop frames from the configured addresses.
+    Reject,
+}
+
+/// An error returned by [`MacAddressTable::add`] if all slots
+/// are in use, and by [`EthernetMAC::set_source_filter`] if there are
+/// not enough slots that are not used by the [`MacAddressTable`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressTableFull;
+
+/// The additional station MAC addresses of the perfect address filter.
+///
+/// The MAC has four perfect filter address slots. Slot 0 holds the MAC
+/// address of this station (see [`EthernetMAC::set_address`]), is always
+/// enabled, and does not support masking. This table manages the other
+/// three slots (1 to 3), so at most three additional addresses can be
+/// added. Every slot has its own enable flag and [`ByteMask`].
+///
+/// Whether a slot is in use is read from its enable flag, so addresses
+/// that were programmed through a previous [`MacAddressTable`] are kept.
+///
+/// The addresses are compared to the destination address of received
+/// frames. The same slots are used by the source address filter (see
+/// [`EthernetMAC::set_source_filter`]): every slot compares its address to
+/// either the destination or the source address of a frame, never both.
+/// Slots used by the source address filter are not reported by this table,
+/// but are not available to [`MacAddressTable::add`] either.
+///
+/// The addresses only have an effect while promiscuous and receive-all mode
+/// are disabled, both of which are enabled by [`new`](crate::new) and
+/// [`new_with_mii`](crate::new_with_mii) (see
+/// [`EthernetMAC::set_receive_all`]).
+pub struct MacAddressTable<'mac> {
+    mac: &'mac mut EthernetMAC,
+}
+
+impl<'mac> MacAddressTable<'mac> {
+    pub(super) fn new(mac: &'mac mut EthernetMAC) -> Self {
+        Self { mac }
+    }
+
+    /// Add `address` to the table, ignoring the bytes in `mask`.
+    ///
+    /// Returns the slot that the address was programmed into. If the address
+    /// is already in the table, its mask is replaced and its slot is returned.
+    pub fn add(&mut self, address: [u8; 6], mask: ByteMask) -> Result<usize, AddressTableFull> {
+        self.add_with(address, mask, false)
+    }
+
+    /// Add `address` to the table, for comparing it to the source address
+    /// of frames if `source` is set.
+    pub(super) fn add_with(
+        &mut self,
+        address: [u8; 6],
+        mask: ByteMask,
+        source: bool,
+    ) -> Result<usize, AddressTableFull> {
+        let slot = self
+            .find_with(address, source)
+            .or_else(|| slots().find(|slot| self.entry(*slot).is_none()))
+            .ok_or(AddressTableFull)?;
+
+        let mut high = u16::from_le_bytes([address[4], address[5]]) as u32
+            | (mask.bits() as u32) << MACAHR_MBC_SHIFT
+            | MACAHR_AE;
+        if source {
+            high |= MACAHR_SA;
+        }
+        let low = u32::from_le_bytes([address[0], address[1], address[2], address[3]]);
+
+        self.write(slot, high, low);
+
+        Ok(slot)
+    }
+
+    /// Remove `address` from the table.
+    ///
+    /// Returns `false` if the address was not in the table.
+    pub fn remove(&mut self, address: [u8; 6]) -> bool {
+        if let Some(slot) = self.find(address) {
+            self.write(slot, 0, 0);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove all addresses from the table.
+    pub fn clear(&mut self) {
+        self.clear_with(false);
+    }
+
+    /// Remove all addresses that are compared to the source address of
+    /// frames if `source` is set, or to the destination address otherwise.
+    pub(super) fn clear_with(&mut self, source: bool) {
+        for slot in slots() {
+            if matches!(self.entry(slot), Some((_, _, s)) if s == source) {
+                self.write(slot, 0, 0);
+            }
+        }
+    }
+
+    /// The amount of slots that are either unused, or used by the
+    /// source address filter.
+    pub(super) fn source_capacity(&self) -> usize {
+        slots()
+            .filter(|slot| !matches!(self.entry(*slot), Some((_, _, false))))
+            .count()
+    }
+
+    /// Get the address and mask in slot `slot` (1 to 3).
+    ///
+    /// Returns `None` if the slot is not in use, if it is used by the source
+    /// address filter, or if `slot` is not managed by this table.
+    pub fn get(&self, slot: usize) -> Option<([u8; 6], ByteMask)> {
+        match self.entry(slot)? {
+            (address, mask, false) => Some((address, mask)),
+            (_, _, true) => None,
+        }
+    }
+
+    /// Get the address, mask and source address flag in slot `slot`.
+    fn entry(&self, slot: usize) -> Option<([u8; 6], ByteMask, bool)> {
+        let (high, low) = self.read(slot)?;
+
+        if high & MACAHR_AE != MACAHR_AE {
+            return None;
+        }
+
+        let mask = ByteMask::new(((high & MACAHR_MBC_MASK) >> MACAHR_MBC_SHIFT) as u8);
+        let source = high & MACAHR_SA == MACAHR_SA;
+        let high = (high as u16).to_le_bytes();
+        let low = low.to_le_bytes();
+
+        Some((
+            [low[0], low[1], low[2], low[3], high[0], high[1]],
+            mask,
+            source,
+        ))
+    }
+
+    /// Get the slot that holds `address`, if any.
+    pub fn find(&self, address: [u8; 6]) -> Option<usize> {
+        self.find_with(address, false)
+    }
+
+    fn find_with(&self, address: [u8; 6], source: bool) -> Option<usize> {
+        slots().find(
+            |slot| matches!(self.entry(*slot), Some((a, _, s)) if a == address && s == source),
+        )
+    }
+
+    /// The amount of slots that hold a destination address.
+    pub fn len(&self) -> usize {
+        slots().filter(|slot| self.get(*slot).is_some()).count()
+    }
+
+    /// Check whether no slots hold a destination address.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check whether all slots are in use, including the slots used by the
+    /// source address filter.
+    pub fn is_full(&self) -> bool {
+        slots().all(|slot| self.entry(slot).is_some())
+    }
+
+    fn read(&self, slot: usize) -> Option<(u32, u32)> {
+        let mac = &self.mac.eth_mac;
+        match slot {
+            1 => Some((mac.maca1hr.read().bits(), mac.maca1lr.read().bits())),
+            2 => Some((mac.maca2hr.read().bits(), mac.maca2lr.read().bits())),
+            3 => Some((mac.maca3hr.read().bits(), mac.maca3lr.read().bits())),
+            _ => None,
+        }
+    }
+
+    /// Write the registers of `slot`.
+    ///
+    /// As with [`EthernetMAC::set_address`], the high register is written
+    /// first, as the MAC only transfers the address once the low register
+    /// is written.
+    fn write(&mut self, slot: usize, high: u32, low: u32) {
+        let mac = &self.mac.eth_mac;
+        // SAFETY: all bits of the address registers may be written.
+        unsafe {
+            match slot {
+                1 => {
+                    mac.maca1hr.write(|w| w.bits(high));
+                    mac.maca1lr.write(|w| w.bits(low));
+                }
+                2 => {
+                    mac.maca2hr.write(|w| w.bits(high));
+                    mac.maca2lr.write(|w| w.bits(low));
+                }
+                3 => {
+                    mac.maca3hr.write(|w| w.bits(high));
+                    mac.maca3lr.write(|w| w.bits(low));
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use ieee802_3_miim::{
+    registers::{AutoNegCap, Esr},
+    AutoNegotiationAdvertisement, Miim, Phy,
+};
+
+/// The auto-negotiation link partner ability register (ANLPAR).
+pub const LINK_PARTNER_ABILITY_REGISTER: u8 = AutoNegCap::PARTNER_CAP_ADDRESS;
+
+/// The 1000BASE-T status register.
+pub const GIGABIT_STATUS_REGISTER: u8 = 10;
+
+/// Master-slave configuration fault
+const GBSR_MS_FAULT: u16 = 1 << 15;
+/// Master-slave configuration resolution: the PHY is the master
+const GBSR_MASTER: u16 = 1 << 14;
+/// Local receiver status OK
+const GBSR_LOCAL_RX_OK: u16 = 1 << 13;
+/// Remote receiver status OK
+const GBSR_REMOTE_RX_OK: u16 = 1 << 12;
+/// The link partner is capable of 1000BASE-T full duplex
+const GBSR_LP_1000BASET_FD: u16 = 1 << 11;
+/// The link partner is capable of 1000BASE-T half duplex
+const GBSR_LP_1000BASET_HD: u16 = 1 << 10;
+/// The idle error count
+const GBSR_IDLE_ERRORS_MASK: u16 = 0xFF;
+
+/// The contents of the 1000BASE-T status register of a PHY.
+///
+/// See [`gigabit_status`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GigabitStatus {
+    /// The master-slave configuration could not be resolved.
+    pub master_slave_fault: bool,
+    /// The PHY was resolved as the master (otherwise, it is the slave).
+    pub master: bool,
+    /// The local receiver is operating correctly.
+    pub local_receiver_ok: bool,
+    /// The remote receiver is operating correctly.
+    pub remote_receiver_ok: bool,
+    /// The link partner advertised 1000BASE-T full duplex operation.
+    pub partner_1000base_t_fd: bool,
+    /// The link partner advertised 1000BASE-T half duplex operation.
+    pub partner_1000base_t_hd: bool,
+    /// The amount of idle errors since the register was last read.
+    /// The PHY stops counting at 255.
+    pub idle_errors: u8,
+}
+
+impl From<u16> for GigabitStatus {
+    fn from(gbsr: u16) -> Self {
+        let bit = |mask| gbsr & mask == mask;
+
+        Self {
+            master_slave_fault: bit(GBSR_MS_FAULT),
+            master: bit(GBSR_MASTER),
+            local_receiver_ok: bit(GBSR_LOCAL_RX_OK),
+            remote_receiver_ok: bit(GBSR_REMOTE_RX_OK),
+            partner_1000base_t_fd: bit(GBSR_LP_1000BASET_FD),
+            partner_1000base_t_hd: bit(GBSR_LP_1000BASET_HD),
+            idle_errors: (gbsr & GBSR_IDLE_ERRORS_MASK) as u8,
+        }
+    }
+}
+
+/// The abilities in the auto-negotiation advertisement register
+/// that only support half-duplex operation.
+const HALF_DUPLEX_CAPS: AutoNegCap = AutoNegCap::_10BASET
+    .union(AutoNegCap::_100BASETX)
+    .union(AutoNegCap::_100BASET4);
+
+/// Stop `phy` from advertising any half-duplex abilities, and restart
+/// auto-negotiation.
+///
+/// All other bits of the auto-negotiation advertisement register (4) are
+/// left unchanged, so this works with all PHYs that implement the registers
+/// defined by IEEE 802.3 clause 22.
+///
+/// This changes the outcome of auto-negotiation: if the link partner does not
+/// support full-duplex operation at any of the speeds advertised by `phy`,
+/// auto-negotiation fails and the link stays down instead of falling back to
+/// half-duplex operation.
+///
+/// However, if the link partner does not perform auto-negotiation at all, most
+/// PHYs resort to parallel detection, which always results in a half-duplex
+/// link. Check the duplex mode that the PHY reports once the link is up to
+/// detect this.
+///
+/// This is a no-op if `phy` does not report that it can perform
+/// auto-negotiation.
+pub fn restrict_to_full_duplex<M, P>(phy: &mut P)
+where
+    M: Miim,
+    P: Phy<M>,
+{
+    if !phy.status().autonegotiation {
+        return;
+    }
+
+    let ana = phy.read(AutoNegCap::LOCAL_CAP_ADDRESS);
+    phy.write(
+        AutoNegCap::LOCAL_CAP_ADDRESS,
+        ana & !HALF_DUPLEX_CAPS.bits(),
+    );
+
+    phy.modify_bcr(|bcr| {
+        bcr.set_autonegotiation(true).restart_autonegotiation();
+    });
+}
+
+/// Get the abilities that the link partner of `phy` advertised
+/// during auto-negotiation.
+///
+/// This reads the link partner ability register
+/// ([`LINK_PARTNER_ABILITY_REGISTER`]), which is defined by IEEE 802.3
+/// clause 22, and returns `None` if auto-negotiation has not completed
+/// (yet), or if `phy` does not report that it can perform auto-negotiation.
+///
+/// Comparing this with the abilities advertised by `phy` itself (see
+/// [`Phy::get_autonegotiation_caps`]) helps to diagnose why a link was
+/// established at a lower speed or in half-duplex mode.
+pub fn link_partner_ability<M, P>(phy: &mut P) -> Option<AutoNegotiationAdvertisement>
+where
+    M: Miim,
+    P: Phy<M>,
+{
+    if !phy.status().autonegotiation || !phy.autoneg_completed() {
+        return None;
+    }
+
+    let anlpar = AutoNegCap::from_bits_truncate(phy.read(LINK_PARTNER_ABILITY_REGISTER));
+    Some(anlpar.into())
+}
+
+/// Read the 1000BASE-T status register ([`GIGABIT_STATUS_REGISTER`]) of `phy`.
+///
+/// The MACs of the supported MCUs only support 10 and 100 Mbit/s, but they
+/// may be connected to a gigabit PHY. Such a PHY negotiates a link at
+/// 100 Mbit/s at most, as long as it does not advertise its 1000BASE-T
+/// abilities. This register shows whether the link partner would have
+/// supported a gigabit link, which helps to diagnose why the link was
+/// established at a lower speed.
+///
+/// Returns `None` if `phy` does not report extended status (register 15)
+/// or does not support 1000BASE-T according to it, as the register
+/// is not defined for such PHYs.
+pub fn gigabit_status<M, P>(phy: &mut P) -> Option<GigabitStatus>
+where
+    M: Miim,
+    P: Phy<M>,
+{
+    let esr = phy.esr()?;
+
+    if !esr.intersects(Esr::_1000BASETFD | Esr::_1000BASETHD) {
+        return None;
+    }
+
+    Some(phy.read(GIGABIT_STATUS_REGISTER).into())
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    /// The basic status register.
+    const BSR: u8 = 1;
+    /// Extended capabilities
+    const BSR_EXTENDED_CAPS: u16 = 1 << 0;
+    /// Auto-negotiation ability
+    const BSR_AUTONEG_ABLE: u16 = 1 << 3;
+    /// Auto-negotiation complete
+    const BSR_AUTONEG_COMPLETE: u16 = 1 << 5;
+
+    /// A PHY whose registers are plain memory.
+    struct TestPhy([u16; 32]);
+
+    impl Miim for TestPhy {
+        fn read(&mut self, _phy: u8, reg: u8) -> u16 {
+            self.0[reg as usize]
+        }
+
+        fn write(&mut self, _phy: u8, reg: u8, data: u16) {
+            self.0[reg as usize] = data;
+        }
+    }
+
+    impl Phy<TestPhy> for TestPhy {
+        fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+            AutoNegotiationAdvertisement::default()
+        }
+
+        fn get_miim(&mut self) -> &mut TestPhy {
+            self
+        }
+
+        fn get_phy_addr(&self) -> u8 {
+            0
+        }
+    }
+
+    #[test]
+    fn link_partner_ability_needs_autoneg_ability() {
+        let anlpar = LINK_PARTNER_ABILITY_REGISTER as usize;
+
+        let mut phy = TestPhy([0; 32]);
+        phy.0[BSR as usize] = BSR_EXTENDED_CAPS | BSR_AUTONEG_COMPLETE;
+        phy.0[anlpar] = 0x01E1;
+        assert_eq!(link_partner_ability(&mut phy), None);
+
+        phy.0[BSR as usize] = BSR_AUTONEG_ABLE | BSR_AUTONEG_COMPLETE;
+        let ability = link_partner_ability(&mut phy).unwrap();
+        assert!(ability.fd_100base_tx);
+        assert!(ability.hd_10base_t);
+    }
+
+    #[test]
+    fn restrict_to_full_duplex_needs_autoneg_ability() {
+        let ana = AutoNegCap::LOCAL_CAP_ADDRESS as usize;
+        let caps = 0x01E1;
+
+        // A PHY that reports extended capabilities, but cannot perform
+        // auto-negotiation, is left untouched.
+        let mut phy = TestPhy([0; 32]);
+        phy.0[BSR as usize] = BSR_EXTENDED_CAPS;
+        phy.0[ana] = caps;
+        restrict_to_full_duplex(&mut phy);
+        assert_eq!(phy.0[ana], caps);
+
+        let mut phy = TestPhy([0; 32]);
+        phy.0[BSR as usize] = BSR_AUTONEG_ABLE;
+        phy.0[ana] = caps;
+        restrict_to_full_duplex(&mut phy);
+        assert_eq!(phy.0[ana], caps & !HALF_DUPLEX_CAPS.bits());
+    }
+
+    #[test]
+    fn gigabit_status_bits() {
+        let status = GigabitStatus::from(0x7C12);
+
+        assert_eq!(
+            status,
+            GigabitStatus {
+                master_slave_fault: false,
+                master: true,
+                local_receiver_ok: true,
+                remote_receiver_ok: true,
+                partner_1000base_t_fd: true,
+                partner_1000base_t_hd: true,
+                idle_errors: 0x12,
+            }
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use super::EthernetMAC;
+
+/// The optional features that the Ethernet peripheral of the selected
+/// MCU family provides.
+///
+/// See [`EthernetMAC::hardware_features`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HwFeatures {
+    /// The MAC captures IEEE 1588 timestamps of frames, and has a
+    /// PTP clock.
+    pub ptp: bool,
+    /// The MAC supports IEEE 1588-2008 (PTPv2) timestamping, with
+    /// timestamps and PTP message status in extended descriptors.
+    ///
+    /// If this is `false`, only IEEE 1588-2002 timestamping is supported.
+    pub ptp_v2: bool,
+    /// The MAC can insert IPv4 header and TCP, UDP and ICMP checksums
+    /// into transmitted frames (see [`ChecksumInsertion`](crate::dma::ChecksumInsertion)).
+    pub tx_checksum_offload: bool,
+    /// The MAC can verify the IPv4 header and TCP, UDP and ICMP checksums
+    /// of received frames.
+    pub rx_checksum_offload: bool,
+    /// The amount of perfect MAC address filters, including the one that
+    /// holds the address of this station (MAC address 0).
+    pub address_filters: usize,
+    /// The MAC has a 64-bit hash filter for destination addresses.
+    pub hash_filter: bool,
+    /// The amount of TX queues (and TX DMA channels).
+    pub tx_queues: usize,
+    /// The amount of RX queues (and RX DMA channels).
+    pub rx_queues: usize,
+}
+
+impl HwFeatures {
+    /// The features of the Ethernet peripheral of the STM32F107.
+    pub const STM32F107: Self = Self {
+        ptp: true,
+        ptp_v2: false,
+        tx_checksum_offload: true,
+        rx_checksum_offload: true,
+        address_filters: 4,
+        hash_filter: true,
+        tx_queues: 1,
+        rx_queues: 1,
+    };
+
+    /// The features of the Ethernet peripheral of the STM32F4 and the
+    /// STM32F7.
+    pub const STM32F4_F7: Self = Self {
+        ptp_v2: true,
+        ..Self::STM32F107
+    };
+
+    /// The features of the Ethernet peripheral of the MCU family that this
+    /// crate was compiled for.
+    #[cfg(feature = "stm32f1xx-hal")]
+    pub const CURRENT: Self = Self::STM32F107;
+
+    /// The features of the Ethernet peripheral of the MCU family that this
+    /// crate was compiled for.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub const CURRENT: Self = Self::STM32F4_F7;
+}
+
+impl EthernetMAC {
+    /// Get the optional features that this Ethernet peripheral provides.
+    ///
+    /// Newer versions of the Synopsys MAC (such as the one of the STM32H7)
+    /// describe their optional features in hardware feature registers.
+    /// The MACs of the STM32F107, STM32F4 and STM32F7 do not have these
+    /// registers, and every device of a family has the same features. The
+    /// returned features are therefore those that the reference manual of the
+    /// selected family documents ([`HwFeatures::CURRENT`]), and not read from
+    /// the peripheral.
+    pub fn hardware_features(&self) -> HwFeatures {
+        HwFeatures::CURRENT
+    }
+}
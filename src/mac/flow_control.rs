@@ -0,0 +1,222 @@
+use super::{EthernetMAC, Speed};
+
+// NOTE: the flow control register is accessed directly, as the PACs of the
+// supported families do not agree on the API of its fields. The layout of
+// the register is the same on the STM32F107, STM32F4 and STM32F7.
+/// Pause time
+const MACFCR_PT_SHIFT: u32 = 16;
+const MACFCR_PT_MASK: u32 = 0xFFFF << MACFCR_PT_SHIFT;
+/// Zero-quanta pause disable
+const MACFCR_ZQPD: u32 = 1 << 7;
+/// Pause low threshold
+const MACFCR_PLT_SHIFT: u32 = 4;
+const MACFCR_PLT_MASK: u32 = 0b11 << MACFCR_PLT_SHIFT;
+/// Receive flow control enable
+const MACFCR_RFCE: u32 = 1 << 2;
+/// Transmit flow control enable
+const MACFCR_TFCE: u32 = 1 << 1;
+
+/// The fields of `ETH_MACFCR` that are described by a [`FlowControlConfig`].
+///
+/// This excludes the flow control busy/back pressure activate bit, which
+/// transmits a pause frame when it is written with 1.
+const MACFCR_CONFIG_MASK: u32 =
+    MACFCR_PT_MASK | MACFCR_ZQPD | MACFCR_PLT_MASK | MACFCR_RFCE | MACFCR_TFCE;
+
+/// The amount of bit times in a pause quantum.
+const BIT_TIMES_PER_QUANTUM: u32 = 512;
+
+/// The threshold of the pause timer at which a pause frame is
+/// retransmitted.
+///
+/// The variants map to the values of the Pause Low Threshold (PLT) field of
+/// the `ETH_MACFCR` register, in order. The threshold is relative to the
+/// [`pause_time`](FlowControlConfig::pause_time): for example, with
+/// [`PauseLowThreshold::Minus28`], the next pause frame is transmitted
+/// 28 quanta before the pause time of the previous one expires.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PauseLowThreshold {
+    /// Pause time minus 4 quanta (PLT = 0b00).
+    #[default]
+    Minus4,
+    /// Pause time minus 28 quanta (PLT = 0b01).
+    Minus28,
+    /// Pause time minus 144 quanta (PLT = 0b10).
+    Minus144,
+    /// Pause time minus 256 quanta (PLT = 0b11).
+    Minus256,
+}
+
+impl PauseLowThreshold {
+    /// The amount of quanta before the pause time expires at which a
+    /// pause frame is retransmitted.
+    pub const fn quanta(&self) -> u16 {
+        match self {
+            PauseLowThreshold::Minus4 => 4,
+            PauseLowThreshold::Minus28 => 28,
+            PauseLowThreshold::Minus144 => 144,
+            PauseLowThreshold::Minus256 => 256,
+        }
+    }
+}
+
+/// The flow control configuration of the MAC.
+///
+/// Pause frames are only used in full-duplex mode. In half-duplex mode,
+/// the MAC applies back pressure instead, and this configuration only
+/// enables or disables transmit flow control.
+///
+/// The pause time is given in quanta of 512 bit times. One quantum lasts
+/// 51.2 µs at 10 Mbit/s and 5.12 µs at 100 Mbit/s, see
+/// [`FlowControlConfig::pause_duration_ns`].
+///
+/// The [`Default`] is the configuration set by [`new`](crate::new) and
+/// [`new_with_mii`](crate::new_with_mii).
+///
+/// See [`EthernetMAC::set_flow_control`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowControlConfig {
+    /// The pause time, in quanta of 512 bit times, that is transmitted in
+    /// pause frames (the PT field).
+    pub pause_time: u16,
+    /// The threshold of the pause timer at which a pause frame is
+    /// transmitted again while flow control is still active, so that the
+    /// peer does not resume transmission before the pause time expires
+    /// (the PLT field).
+    pub pause_low_threshold: PauseLowThreshold,
+    /// Transmit a pause frame with a pause time of zero once flow control
+    /// is deactivated, so that the peer resumes transmission immediately
+    /// instead of waiting for the previous pause time to expire (the
+    /// inverse of the ZQPD bit).
+    pub zero_quanta_pause: bool,
+    /// Transmit pause frames in full-duplex mode, and apply back pressure
+    /// in half-duplex mode (the TFCE bit).
+    pub tx_flow_control: bool,
+    /// Pause transmission for the time requested by received pause frames
+    /// (the RFCE bit).
+    pub rx_flow_control: bool,
+}
+
+impl Default for FlowControlConfig {
+    fn default() -> Self {
+        Self {
+            pause_time: 0x100,
+            pause_low_threshold: PauseLowThreshold::Minus4,
+            zero_quanta_pause: true,
+            tx_flow_control: false,
+            rx_flow_control: false,
+        }
+    }
+}
+
+impl FlowControlConfig {
+    /// The duration, in nanoseconds, of the pause time of this
+    /// configuration at `speed`.
+    ///
+    /// A pause quantum is 512 bit times, which is 51 200 ns at 10 Mbit/s
+    /// and 5 120 ns at 100 Mbit/s.
+    pub const fn pause_duration_ns(&self, speed: Speed) -> u32 {
+        let ns_per_bit = match speed {
+            Speed::HalfDuplexBase10T | Speed::FullDuplexBase10T => 100,
+            Speed::HalfDuplexBase100Tx | Speed::FullDuplexBase100Tx => 10,
+        };
+
+        self.pause_time as u32 * BIT_TIMES_PER_QUANTUM * ns_per_bit
+    }
+
+    fn to_bits(self) -> u32 {
+        let plt = match self.pause_low_threshold {
+            PauseLowThreshold::Minus4 => 0b00,
+            PauseLowThreshold::Minus28 => 0b01,
+            PauseLowThreshold::Minus144 => 0b10,
+            PauseLowThreshold::Minus256 => 0b11,
+        };
+
+        let mut bits = ((self.pause_time as u32) << MACFCR_PT_SHIFT) | (plt << MACFCR_PLT_SHIFT);
+        if !self.zero_quanta_pause {
+            bits |= MACFCR_ZQPD;
+        }
+        if self.tx_flow_control {
+            bits |= MACFCR_TFCE;
+        }
+        if self.rx_flow_control {
+            bits |= MACFCR_RFCE;
+        }
+        bits
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        let pause_low_threshold = match (bits & MACFCR_PLT_MASK) >> MACFCR_PLT_SHIFT {
+            0b00 => PauseLowThreshold::Minus4,
+            0b01 => PauseLowThreshold::Minus28,
+            0b10 => PauseLowThreshold::Minus144,
+            _ => PauseLowThreshold::Minus256,
+        };
+
+        Self {
+            pause_time: ((bits & MACFCR_PT_MASK) >> MACFCR_PT_SHIFT) as u16,
+            pause_low_threshold,
+            zero_quanta_pause: bits & MACFCR_ZQPD == 0,
+            tx_flow_control: bits & MACFCR_TFCE != 0,
+            rx_flow_control: bits & MACFCR_RFCE != 0,
+        }
+    }
+}
+
+impl EthernetMAC {
+    /// Configure flow control.
+    ///
+    /// This does not transmit a pause frame. The new pause time is used
+    /// for the next pause frame that the MAC transmits.
+    pub fn set_flow_control(&mut self, config: FlowControlConfig) {
+        self.eth_mac
+            .macfcr
+            .modify(|r, w| unsafe { w.bits((r.bits() & !MACFCR_CONFIG_MASK) | config.to_bits()) });
+    }
+
+    /// Get the current flow control configuration.
+    ///
+    /// See [`EthernetMAC::set_flow_control`].
+    pub fn flow_control(&self) -> FlowControlConfig {
+        FlowControlConfig::from_bits(self.eth_mac.macfcr.read().bits())
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bits_round_trip() {
+        let config = FlowControlConfig {
+            pause_time: 0x1234,
+            pause_low_threshold: PauseLowThreshold::Minus144,
+            zero_quanta_pause: false,
+            tx_flow_control: true,
+            rx_flow_control: false,
+        };
+
+        assert_eq!(config.to_bits(), 0x1234_00A2);
+        assert_eq!(FlowControlConfig::from_bits(config.to_bits()), config);
+        assert_eq!(FlowControlConfig::default().to_bits(), 0x0100_0000);
+    }
+
+    #[test]
+    fn pause_duration() {
+        let config = FlowControlConfig {
+            pause_time: 0xFFFF,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.pause_duration_ns(Speed::FullDuplexBase10T),
+            3_355_392_000
+        );
+        assert_eq!(
+            config.pause_duration_ns(Speed::FullDuplexBase100Tx),
+            335_539_200
+        );
+    }
+}
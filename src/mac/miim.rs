@@ -2,7 +2,9 @@ pub use ieee802_3_miim::Miim;
 
 pub use ieee802_3_miim::*;
 
-use crate::{peripherals::ETHERNET_MAC, stm32::ethernet_mac::MACMIIAR};
+use crate::{
+    dma::DMA_TIMEOUT_ITERATIONS, peripherals::ETHERNET_MAC, stm32::ethernet_mac::MACMIIAR,
+};
 
 use super::EthernetMAC;
 
@@ -20,9 +22,16 @@ pub unsafe trait MdioPin {}
 /// may implement this trait
 pub unsafe trait MdcPin {}
 
+/// Wait until the MII is no longer busy.
+///
+/// The [`Miim`] trait cannot report errors, so if the MII stays busy (e.g.
+/// because the MAC is not clocked), the transaction is attempted anyway, and
+/// reads return whatever the data register holds.
 #[inline(always)]
 fn miim_wait_ready(iar: &MACMIIAR) {
-    while iar.read().mb().bit_is_set() {}
+    let _ = crate::wait::wait_for("the MII", DMA_TIMEOUT_ITERATIONS, || {
+        iar.read().mb().bit_is_clear()
+    });
 }
 
 #[inline(always)]
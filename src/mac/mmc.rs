@@ -0,0 +1,62 @@
+use super::EthernetMAC;
+
+/// Counters freeze: the counters keep their values while this bit is set
+const MMCCR_MCF: u32 = 1 << 3;
+
+/// A snapshot of the MAC management counters (MMC).
+///
+/// The MMC only counts these frames: there are no counters for received
+/// multicast or broadcast frames, nor for the amount of bytes. All counters
+/// wrap around on overflow.
+///
+/// See [`EthernetMAC::mmc_counters`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MmcCounters {
+    /// The amount of received good unicast frames.
+    pub rx_good_unicast_frames: u32,
+    /// The amount of received frames with a CRC error.
+    pub rx_crc_errors: u32,
+    /// The amount of received frames with an alignment (dribble bit) error.
+    pub rx_alignment_errors: u32,
+    /// The amount of good frames that were transmitted.
+    pub tx_good_frames: u32,
+    /// The amount of good frames that were transmitted after exactly
+    /// one collision.
+    pub tx_single_collision_frames: u32,
+    /// The amount of good frames that were transmitted after more than
+    /// one collision.
+    pub tx_multiple_collision_frames: u32,
+}
+
+impl EthernetMAC {
+    /// Read the MAC management counters (MMC).
+    ///
+    /// The counters are frozen while they are read, so all of them are
+    /// taken at the same point in time.
+    ///
+    /// The counters are not reset by reading them: this driver never enables
+    /// reset-on-read in the MMC control register.
+    pub fn mmc_counters(&self) -> MmcCounters {
+        let mmc = &self.eth_mmc;
+
+        // SAFETY: the freeze bit may be set and cleared at any time.
+        mmc.mmccr
+            .modify(|r, w| unsafe { w.bits(r.bits() | MMCCR_MCF) });
+
+        let counters = MmcCounters {
+            rx_good_unicast_frames: mmc.mmcrgufcr.read().bits(),
+            rx_crc_errors: mmc.mmcrfcecr.read().bits(),
+            rx_alignment_errors: mmc.mmcrfaecr.read().bits(),
+            tx_good_frames: mmc.mmctgfcr.read().bits(),
+            tx_single_collision_frames: mmc.mmctgfsccr.read().bits(),
+            tx_multiple_collision_frames: mmc.mmctgfmsccr.read().bits(),
+        };
+
+        // SAFETY: see above.
+        mmc.mmccr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !MMCCR_MCF) });
+
+        counters
+    }
+}
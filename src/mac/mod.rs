@@ -2,11 +2,37 @@
 
 use core::ops::{Deref, DerefMut};
 
+use ieee802_3_miim::phy::{AdvancedPhySpeed, PhyWithSpeed};
+
 use crate::{dma::EthernetDMA, hal::rcc::Clocks, peripherals::ETHERNET_MAC, stm32::ETHERNET_MMC};
 
 mod miim;
 pub use miim::*;
 
+mod address_table;
+pub use address_table::{AddressTableFull, ByteMask, MacAddressTable, SaFilterMode};
+
+mod features;
+pub use features::HwFeatures;
+
+mod flow_control;
+pub use flow_control::{FlowControlConfig, PauseLowThreshold};
+
+mod autoneg;
+pub use autoneg::{
+    gigabit_status, link_partner_ability, restrict_to_full_duplex, GigabitStatus,
+    GIGABIT_STATUS_REGISTER, LINK_PARTNER_ABILITY_REGISTER,
+};
+
+mod mmc;
+pub use mmc::MmcCounters;
+
+mod phy_state;
+pub use phy_state::{phy_state, PhyState};
+
+mod phy_reset;
+pub use phy_reset::{PhyReset, PhyResetTiming};
+
 /// Speeds at which this MAC can be configured
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,6 +47,44 @@ pub enum Speed {
     FullDuplexBase100Tx,
 }
 
+/// The minimum HCLK frequency required by the ethernet peripheral.
+///
+/// The reference manuals of all supported families (STM32F107, STM32F4
+/// and STM32F7) specify a minimum of 25 MHz, regardless of the speed and
+/// of the interface (MII or RMII). If HCLK is too slow, the DMA cannot keep
+/// up with the MAC, which causes TX FIFO underruns and RX FIFO overflows.
+const MIN_HCLK_HZ: u32 = 25_000_000;
+
+/// Which MAC control frames (such as pause frames) the MAC passes on to
+/// the DMA.
+///
+/// The variants map to the values of the Pass Control Frames (PCF) field
+/// of the `ETH_MACFFR` register, in order. The field has the same layout
+/// on the STM32F107, STM32F4 and STM32F7.
+///
+/// See [`EthernetMAC::set_control_frame_filter`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlFrameMode {
+    /// Do not pass on any control frames (PCF = 0b00).
+    #[default]
+    BlockAll,
+    /// Pass on all control frames, except for pause frames (PCF = 0b01).
+    ForwardAllExceptPause,
+    /// Pass on all control frames, even if they do not pass the address
+    /// filter (PCF = 0b10).
+    ForwardAll,
+    /// Pass on the control frames that pass the address filter
+    /// (PCF = 0b11).
+    ForwardFiltered,
+}
+
+// NOTE: the PCF field is accessed directly, as the PACs of the supported
+// families do not agree on its API.
+/// Pass control frames
+const MACFFR_PCF_SHIFT: u32 = 6;
+const MACFFR_PCF_MASK: u32 = 0b11 << MACFFR_PCF_SHIFT;
+
 mod consts {
     /* For HCLK 60-100 MHz */
     pub const ETH_MACMIIAR_CR_HCLK_DIV_42: u8 = 0;
@@ -35,9 +99,33 @@ mod consts {
 }
 use self::consts::*;
 
+/// Select the MDC clock range for an HCLK frequency of `hclk_hz`, which
+/// keeps the MDC clock at or below 2.5 MHz.
+fn mdc_clock_range(hclk_hz: u32) -> u8 {
+    match hclk_hz {
+        0..=34_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_16,
+        35_000_000..=59_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_26,
+        60_000_000..=99_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_42,
+        100_000_000..=149_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_62,
+        _ => ETH_MACMIIAR_CR_HCLK_DIV_102,
+    }
+}
+
+/// The divider of HCLK that produces the MDC clock for `clock_range`.
+fn mdc_divider(clock_range: u8) -> u32 {
+    match clock_range {
+        ETH_MACMIIAR_CR_HCLK_DIV_42 => 42,
+        ETH_MACMIIAR_CR_HCLK_DIV_62 => 62,
+        ETH_MACMIIAR_CR_HCLK_DIV_16 => 16,
+        ETH_MACMIIAR_CR_HCLK_DIV_26 => 26,
+        _ => 102,
+    }
+}
+
 /// HCLK must be at least 25MHz to use the ethernet peripheral.
 /// This (empty) struct is returned to indicate that it is not set
 /// correctly
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug)]
 pub struct WrongClock;
 
@@ -47,6 +135,8 @@ pub struct WrongClock;
 // atomically.
 pub struct EthernetMAC {
     eth_mac: ETHERNET_MAC,
+    eth_mmc: ETHERNET_MMC,
+    hclk_hz: u32,
 }
 
 impl EthernetMAC {
@@ -71,14 +161,11 @@ impl EthernetMAC {
     ) -> Result<Self, WrongClock> {
         let clock_frequency = clocks.hclk().to_Hz();
 
-        let clock_range = match clock_frequency {
-            0..=24_999_999 => return Err(WrongClock),
-            25_000_000..=34_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_16,
-            35_000_000..=59_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_26,
-            60_000_000..=99_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_42,
-            100_000_000..=149_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_62,
-            _ => ETH_MACMIIAR_CR_HCLK_DIV_102,
-        };
+        if clock_frequency < MIN_HCLK_HZ {
+            return Err(WrongClock);
+        }
+
+        let clock_range = mdc_clock_range(clock_frequency);
 
         // Set clock range in MAC MII address register
         eth_mac
@@ -106,6 +193,12 @@ impl EthernetMAC {
                 // Retry disable in half-duplex mode
                 .rd()
                 .set_bit()
+                // Carrier sense during transmission in half-duplex mode
+                .csd()
+                .clear_bit()
+                // Receive own frames in half-duplex mode
+                .rod()
+                .clear_bit()
                 // Receiver enable
                 .re()
                 .set_bit()
@@ -146,13 +239,29 @@ impl EthernetMAC {
             .mmctimr
             .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 21)) });
 
-        let mut me = Self { eth_mac };
+        let mut me = Self {
+            eth_mac,
+            eth_mmc,
+            hclk_hz: clock_frequency,
+        };
 
         me.set_speed(initial_speed);
 
         Ok(me)
     }
 
+    /// Disable the transmitter and receiver, and release the MAC and
+    /// MMC peripherals.
+    ///
+    /// See [`Parts::release`](crate::Parts::release).
+    pub(crate) fn release(self) -> (ETHERNET_MAC, ETHERNET_MMC) {
+        self.eth_mac
+            .maccr
+            .modify(|_, w| w.te().clear_bit().re().clear_bit());
+
+        (self.eth_mac, self.eth_mmc)
+    }
+
     /// Borrow access to the MAC's SMI.
     ///
     /// Allows for controlling and monitoring any PHYs that may be accessible via the MDIO/MDC
@@ -199,6 +308,22 @@ impl EthernetMAC {
         });
     }
 
+    /// Get the frequency of the MDC clock in Hz.
+    ///
+    /// The MDC clock is derived from HCLK, with a divider that is selected
+    /// from the HCLK frequency when the MAC is set up, so that the MDC clock
+    /// does not exceed 2.5 MHz, the maximum that IEEE 802.3 requires PHYs
+    /// to support. The frequency is computed from the divider that is currently
+    /// programmed into the MAC, and the HCLK frequency that was passed to
+    /// [`new`](crate::new) or [`new_with_mii`](crate::new_with_mii).
+    ///
+    /// If MDIO reads return `0xFFFF`, check that this frequency is supported
+    /// by the PHY, and that HCLK was configured as expected.
+    pub fn mdc_frequency(&self) -> u32 {
+        let clock_range = self.eth_mac.macmiiar.read().cr().bits();
+        self.hclk_hz / mdc_divider(clock_range)
+    }
+
     /// Get the Ethernet Speed at which the MAC communicates
     pub fn get_speed(&self) -> Speed {
         let cr = self.eth_mac.maccr.read();
@@ -210,6 +335,463 @@ impl EthernetMAC {
         }
     }
 
+    /// Bring up the link that `phy` has established.
+    ///
+    /// This performs the complete bring-up sequence:
+    /// 1. Check that the PHY reports that the link is up.
+    /// 2. Read the negotiated speed and duplex mode from the PHY.
+    /// 3. Reconfigure the MAC if it is not already operating at them.
+    /// 4. Make sure that `dma` processes its rings: a suspended RX DMA is
+    ///    polled, an RX DMA that stopped by itself is restarted (see
+    ///    [`RxRing::is_stopped`](crate::dma::RxRing::is_stopped)), and the TX
+    ///    DMA is polled for frames that were queued while the link was down.
+    ///
+    /// The established [`Speed`] is returned.
+    ///
+    /// Returns `None` if the link is down, if the PHY cannot report its
+    /// speed, or if the PHY has negotiated a speed that this MAC does not
+    /// support (i.e. 1000 Mbit/s). In those cases, the MAC and the DMA are
+    /// left untouched.
+    ///
+    /// This function can be called repeatedly (e.g. whenever the link state
+    /// changes): the MAC is only written to if the speed has changed, and the
+    /// DMA is only restarted if it is not running.
+    pub fn establish_link<M, P>(phy: &mut P, dma: &mut EthernetDMA) -> Option<Speed>
+    where
+        M: Miim + DerefMut<Target = EthernetMAC>,
+        P: PhyWithSpeed<M>,
+    {
+        if !phy.phy_link_up() {
+            return None;
+        }
+
+        let speed = match phy.get_link_speed()? {
+            AdvancedPhySpeed::HalfDuplexBase10T => Speed::HalfDuplexBase10T,
+            AdvancedPhySpeed::FullDuplexBase10T => Speed::FullDuplexBase10T,
+            AdvancedPhySpeed::HalfDuplexBase100Tx => Speed::HalfDuplexBase100Tx,
+            AdvancedPhySpeed::FullDuplexBase100Tx => Speed::FullDuplexBase100Tx,
+            _ => return None,
+        };
+
+        let mac = phy.get_miim();
+        if mac.get_speed() != speed {
+            mac.set_speed(speed);
+        }
+
+        dma.resume();
+
+        Some(speed)
+    }
+
+    /// Set the MAC address of this station (MAC address 0).
+    ///
+    /// This address is used by the address filter (if promiscuous mode is
+    /// disabled) and as the source address of pause frames.
+    ///
+    /// This function can be called while the DMA is running. The MAC only
+    /// transfers the address into its receive clock domain once the low
+    /// address register is written, so the high register is written first.
+    /// The address filter thus always sees either the old or the new address,
+    /// never a mix of both.
+    pub fn set_address(&mut self, address: [u8; 6]) {
+        let high = u16::from_le_bytes([address[4], address[5]]);
+        let low = u32::from_le_bytes([address[0], address[1], address[2], address[3]]);
+
+        self.eth_mac.maca0hr.write(|w| w.maca0h().bits(high));
+        self.eth_mac.maca0lr.write(|w| w.maca0l().bits(low));
+    }
+
+    /// Get the MAC address of this station (MAC address 0).
+    pub fn address(&self) -> [u8; 6] {
+        let high = self.eth_mac.maca0hr.read().maca0h().bits().to_le_bytes();
+        let low = self.eth_mac.maca0lr.read().maca0l().bits().to_le_bytes();
+
+        [low[0], low[1], low[2], low[3], high[0], high[1]]
+    }
+
+    /// Get the MAC address programmed into perfect filter address slot
+    /// `index` (0 to 3).
+    ///
+    /// Slot 0 holds the MAC address of this station (see
+    /// [`EthernetMAC::address`]), and is always enabled. For slots 1 to 3,
+    /// `None` is returned if the slot is not enabled. `None` is also returned
+    /// if `index` is greater than 3.
+    ///
+    /// The address is reconstructed from the high register of the slot, which
+    /// holds the last two bytes, and its low register, which holds the first
+    /// four bytes.
+    pub fn mac_address(&self, index: usize) -> Option<[u8; 6]> {
+        /// Address enable
+        const MACAHR_AE: u32 = 1 << 31;
+
+        let mac = &self.eth_mac;
+        let (high, low) = match index {
+            0 => (
+                mac.maca0hr.read().bits() | MACAHR_AE,
+                mac.maca0lr.read().bits(),
+            ),
+            1 => (mac.maca1hr.read().bits(), mac.maca1lr.read().bits()),
+            2 => (mac.maca2hr.read().bits(), mac.maca2lr.read().bits()),
+            3 => (mac.maca3hr.read().bits(), mac.maca3lr.read().bits()),
+            _ => return None,
+        };
+
+        if high & MACAHR_AE != MACAHR_AE {
+            return None;
+        }
+
+        let high = (high as u16).to_le_bytes();
+        let low = low.to_le_bytes();
+
+        Some([low[0], low[1], low[2], low[3], high[0], high[1]])
+    }
+
+    /// Manage the additional station MAC addresses in perfect filter
+    /// address slots 1 to 3.
+    ///
+    /// See [`MacAddressTable`].
+    pub fn address_table(&mut self) -> MacAddressTable<'_> {
+        MacAddressTable::new(self)
+    }
+
+    /// Configure the source address filter to accept or reject (according
+    /// to `mode`) frames from the senders in `addresses`.
+    ///
+    /// Every address is programmed into a perfect filter address slot with
+    /// the source address compare bit set, replacing the addresses of a
+    /// previous call. The mode applies to the whole source address filter.
+    /// An empty `addresses` disables the source address filter.
+    ///
+    /// The source address filter shares the three slots (1 to 3) that are
+    /// not used for the address of this station with the [`MacAddressTable`].
+    /// Every slot compares its address to either the source or the destination
+    /// address of frames, so an address that is used by both filters occupies
+    /// two slots. A frame must pass both the destination and the source address
+    /// filter to be received: with [`SaFilterMode::Accept`], a frame to one of
+    /// the destination addresses is still dropped if it is not sent by one of
+    /// `addresses`.
+    ///
+    /// If there are more `addresses` than there are slots that are not used
+    /// by the [`MacAddressTable`], [`AddressTableFull`] is returned and the
+    /// filter is not modified.
+    ///
+    /// Like all address filters, the source address filter only has an effect
+    /// while promiscuous and receive-all mode are disabled (see
+    /// [`EthernetMAC::set_receive_all`]).
+    pub fn set_source_filter(
+        &mut self,
+        mode: SaFilterMode,
+        addresses: &[[u8; 6]],
+    ) -> Result<(), AddressTableFull> {
+        // NOTE: some PACs use the wrong positions for these bits, so they
+        // are written directly.
+        /// Source address filter
+        const MACFFR_SAF: u32 = 1 << 9;
+        /// Source address inverse filtering
+        const MACFFR_SAIF: u32 = 1 << 8;
+
+        let mut table = self.address_table();
+
+        if addresses.len() > table.source_capacity() {
+            return Err(AddressTableFull);
+        }
+
+        table.clear_with(true);
+
+        for address in addresses {
+            table.add_with(*address, ByteMask::NONE, true)?;
+        }
+
+        let bits = match mode {
+            _ if addresses.is_empty() => 0,
+            SaFilterMode::Accept => MACFFR_SAF,
+            SaFilterMode::Reject => MACFFR_SAF | MACFFR_SAIF,
+        };
+
+        self.eth_mac
+            .macffr
+            .modify(|r, w| unsafe { w.bits((r.bits() & !(MACFFR_SAF | MACFFR_SAIF)) | bits) });
+
+        Ok(())
+    }
+
+    /// Configure which MAC control frames the MAC passes on to the DMA.
+    ///
+    /// MAC control frames (EtherType `0x8808`) are normally consumed by the
+    /// MAC. A bridge that has to forward them can use this to receive them,
+    /// and identify them with [`RxPacket::is_control_frame`](crate::dma::RxPacket::is_control_frame).
+    ///
+    /// Whether the MAC acts on received pause frames only depends on whether
+    /// receive flow control is enabled (see
+    /// [`FlowControlConfig::rx_flow_control`], which is not enabled by
+    /// [`new`](crate::new) and [`new_with_mii`](crate::new_with_mii)),
+    /// not on this setting. Undersized frames are forwarded by the DMA, see
+    /// [`RxRing::set_accept_runt_frames`](crate::dma::RxRing::set_accept_runt_frames).
+    ///
+    /// The default is [`ControlFrameMode::BlockAll`].
+    pub fn set_control_frame_filter(&mut self, mode: ControlFrameMode) {
+        let pcf = match mode {
+            ControlFrameMode::BlockAll => 0b00,
+            ControlFrameMode::ForwardAllExceptPause => 0b01,
+            ControlFrameMode::ForwardAll => 0b10,
+            ControlFrameMode::ForwardFiltered => 0b11,
+        };
+
+        self.eth_mac.macffr.modify(|r, w| unsafe {
+            w.bits((r.bits() & !MACFFR_PCF_MASK) | (pcf << MACFFR_PCF_SHIFT))
+        });
+    }
+
+    /// Get which MAC control frames the MAC passes on to the DMA.
+    ///
+    /// See [`EthernetMAC::set_control_frame_filter`].
+    pub fn control_frame_filter(&self) -> ControlFrameMode {
+        match (self.eth_mac.macffr.read().bits() & MACFFR_PCF_MASK) >> MACFFR_PCF_SHIFT {
+            0b00 => ControlFrameMode::BlockAll,
+            0b01 => ControlFrameMode::ForwardAllExceptPause,
+            0b10 => ControlFrameMode::ForwardAll,
+            _ => ControlFrameMode::ForwardFiltered,
+        }
+    }
+
+    /// Enable or disable hardware filtering of broadcast frames.
+    ///
+    /// If `filter` is `true`, the address filter drops all incoming
+    /// broadcast frames (the Broadcast Frames Disable bit).
+    ///
+    /// # Note
+    /// - ARP requests are sent to the broadcast address, so a host that
+    ///   filters broadcast frames will not answer them. Make sure that peers
+    ///   learn this station's address through some other means (e.g. static
+    ///   ARP entries).
+    /// - The address filter is bypassed while the MAC is in promiscuous or
+    ///   receive-all mode, both of which are enabled by [`new`](crate::new) and
+    ///   [`new_with_mii`](crate::new_with_mii). In those modes, broadcast
+    ///   frames are delivered regardless of this setting.
+    pub fn set_broadcast_filter(&mut self, filter: bool) {
+        self.eth_mac.macffr.modify(|_, w| w.bfd().bit(filter));
+    }
+
+    /// Check whether broadcast frames are filtered by the MAC.
+    ///
+    /// See [`EthernetMAC::set_broadcast_filter`].
+    pub fn broadcast_filter(&self) -> bool {
+        self.eth_mac.macffr.read().bfd().bit_is_set()
+    }
+
+    /// Enable or disable the transmitter of the MAC.
+    ///
+    /// The transmitter and the receiver are enabled by [`new`](crate::new)
+    /// and [`new_with_mii`](crate::new_with_mii). Disabling only the transmitter
+    /// makes the MAC listen-only, e.g. for a diagnostic tap: the TX DMA keeps
+    /// moving queued frames into the TX FIFO until it is full, but no frame
+    /// (including pause frames) leaves the MAC until the transmitter is
+    /// enabled again.
+    ///
+    /// This is independent of the TX DMA. To disable the transmitter without
+    /// truncating a frame that is being transmitted, first wait until the TX
+    /// DMA has transmitted all queued frames (see
+    /// [`EthernetDMA::tx_is_running`](crate::dma::EthernetDMA::tx_is_running))
+    /// or stop the DMA (see [`EthernetDMA::prepare_for_sleep`](crate::dma::EthernetDMA::prepare_for_sleep)),
+    /// and disable the transmitter afterwards. When enabling it again, enable
+    /// the transmitter before passing new frames to the DMA.
+    pub fn set_transmitter_enabled(&mut self, enabled: bool) {
+        self.eth_mac.maccr.modify(|_, w| w.te().bit(enabled));
+    }
+
+    /// Check whether the transmitter of the MAC is enabled.
+    ///
+    /// See [`EthernetMAC::set_transmitter_enabled`].
+    pub fn transmitter_enabled(&self) -> bool {
+        self.eth_mac.maccr.read().te().bit_is_set()
+    }
+
+    /// Enable or disable the receiver of the MAC.
+    ///
+    /// The transmitter and the receiver are enabled by [`new`](crate::new)
+    /// and [`new_with_mii`](crate::new_with_mii). While the receiver is
+    /// disabled, no frames reach the RX FIFO and the RX DMA, and the MAC does
+    /// not react to received pause frames.
+    ///
+    /// This is independent of the RX DMA. The MAC finishes receiving the
+    /// current frame before the receiver is disabled, and the RX DMA then
+    /// moves the frames that remain in the RX FIFO into the RX ring. To avoid
+    /// dropping them, disable the receiver before stopping the RX DMA (see
+    /// [`EthernetDMA::prepare_for_sleep`](crate::dma::EthernetDMA::prepare_for_sleep)),
+    /// and only enable it again once the RX DMA is running.
+    pub fn set_receiver_enabled(&mut self, enabled: bool) {
+        self.eth_mac.maccr.modify(|_, w| w.re().bit(enabled));
+    }
+
+    /// Check whether the receiver of the MAC is enabled.
+    ///
+    /// See [`EthernetMAC::set_receiver_enabled`].
+    pub fn receiver_enabled(&self) -> bool {
+        self.eth_mac.maccr.read().re().bit_is_set()
+    }
+
+    /// Enable or disable the internal loopback of the MAC.
+    ///
+    /// While the loopback is enabled, all transmitted frames are received
+    /// again by the MAC instead of being sent to the PHY.
+    pub fn set_loopback(&mut self, loopback: bool) {
+        self.eth_mac.maccr.modify(|_, w| w.lm().bit(loopback));
+    }
+
+    /// Check whether the internal loopback of the MAC is enabled.
+    ///
+    /// See [`EthernetMAC::set_loopback`].
+    pub fn loopback(&self) -> bool {
+        self.eth_mac.maccr.read().lm().bit_is_set()
+    }
+
+    /// Enable or disable stripping of the FCS (the 4-byte CRC) from
+    /// received frames.
+    ///
+    /// While stripping is enabled, the MAC removes the FCS before passing a
+    /// frame to the DMA, so the length of an [`RxPacket`](crate::dma::RxPacket)
+    /// does not include it. For IEEE 802.3 frames whose length/type field holds
+    /// a length (less than 0x600), the padding is removed as well, so their length
+    /// is the value of that field plus the 14 bytes of the header.
+    ///
+    /// While stripping is disabled, every received frame, including its padding,
+    /// is passed to the DMA exactly as it was received: its last 4 bytes are the
+    /// FCS, and the length of an [`RxPacket`](crate::dma::RxPacket) includes them.
+    /// The FCS can then be verified by the application. Combined with forward
+    /// error frames, which is enabled by [`new`](crate::new) and
+    /// [`new_with_mii`](crate::new_with_mii), and
+    /// [`RxRing::set_deliver_soft_errors`](crate::dma::RxRing::set_deliver_soft_errors),
+    /// this gives full visibility into the received frames.
+    ///
+    /// Stripping is enabled by [`new`](crate::new) and [`new_with_mii`](crate::new_with_mii).
+    ///
+    /// # Note
+    /// The MAC of the STM32F107 can only strip the FCS of IEEE 802.3 frames whose
+    /// length/type field holds a length. Frames whose length/type field holds an
+    /// EtherType (such as IPv4, IPv6 and ARP frames) always include the FCS there,
+    /// regardless of this setting.
+    pub fn set_crc_stripping(&mut self, strip: bool) {
+        self.eth_mac.maccr.modify(|_, w| {
+            #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
+            let w = w.cstf().bit(strip);
+
+            w.apcs().bit(strip)
+        });
+    }
+
+    /// Check whether the FCS is stripped from received frames.
+    ///
+    /// See [`EthernetMAC::set_crc_stripping`].
+    pub fn crc_stripping(&self) -> bool {
+        self.eth_mac.maccr.read().apcs().bit_is_set()
+    }
+
+    /// Enable or disable the minimum interframe gap of 40 bit times, instead
+    /// of the default of 96 bit times.
+    ///
+    /// This allows transmitting frames back-to-back with the smallest gap that
+    /// the MAC supports, e.g. for physical layer or throughput characterization
+    /// (see `dma::bench::stream_frame`, which also requires `ptp`). The gap
+    /// can not be reduced to zero. Such a gap violates IEEE 802.3, so this must
+    /// not be used in production.
+    ///
+    /// In half-duplex mode, the MAC uses a gap of at least 64 bit times
+    /// regardless of this setting.
+    #[cfg(feature = "test-modes")]
+    pub fn set_minimum_interframe_gap(&mut self, minimum: bool) {
+        self.eth_mac.maccr.modify(|_, w| {
+            if minimum {
+                w.ifg().ifg40()
+            } else {
+                w.ifg().ifg96()
+            }
+        });
+    }
+
+    /// Check whether the minimum interframe gap of 40 bit times is used.
+    ///
+    /// See [`EthernetMAC::set_minimum_interframe_gap`].
+    #[cfg(feature = "test-modes")]
+    pub fn minimum_interframe_gap(&self) -> bool {
+        self.eth_mac.maccr.read().ifg().bits() == 0b111
+    }
+
+    /// Enable or disable carrier sense during transmission (the inverse of
+    /// the Carrier Sense Disable bit).
+    ///
+    /// While carrier sense is enabled, the MAC aborts a transmission with a
+    /// carrier sense error if the carrier is lost while transmitting, or never
+    /// detected. While it is disabled, the MAC ignores the carrier sense signal
+    /// of the PHY during transmission, which some half-duplex setups (e.g.
+    /// single-pair Ethernet PHYs attached through RMII) require.
+    ///
+    /// This only applies in half-duplex mode. Disabling carrier sense on a shared
+    /// medium makes the MAC transmit over other stations, which causes collisions.
+    ///
+    /// Carrier sense is enabled by [`new`](crate::new) and
+    /// [`new_with_mii`](crate::new_with_mii).
+    pub fn set_carrier_sense(&mut self, enabled: bool) {
+        self.eth_mac.maccr.modify(|_, w| w.csd().bit(!enabled));
+    }
+
+    /// Check whether carrier sense during transmission is enabled.
+    ///
+    /// See [`EthernetMAC::set_carrier_sense`].
+    pub fn carrier_sense(&self) -> bool {
+        self.eth_mac.maccr.read().csd().bit_is_clear()
+    }
+
+    /// Enable or disable receiving own frames (the inverse of the Receive Own
+    /// Disable bit).
+    ///
+    /// While enabled, the MAC receives the frames that it transmits, if the PHY
+    /// loops them back while the transmit enable signal is asserted, as half-duplex
+    /// PHYs do. While disabled, the MAC ignores all frames that are received while
+    /// it is transmitting.
+    ///
+    /// This only applies in half-duplex mode. Receiving own frames is enabled by
+    /// [`new`](crate::new) and [`new_with_mii`](crate::new_with_mii).
+    pub fn set_receive_own(&mut self, enabled: bool) {
+        self.eth_mac.maccr.modify(|_, w| w.rod().bit(!enabled));
+    }
+
+    /// Check whether own frames are received.
+    ///
+    /// See [`EthernetMAC::set_receive_own`].
+    pub fn receive_own(&self) -> bool {
+        self.eth_mac.maccr.read().rod().bit_is_clear()
+    }
+
+    /// Enable or disable receive-all mode (the Receive All bit).
+    ///
+    /// In receive-all mode, the MAC passes every received frame to the DMA,
+    /// regardless of the result of the source and destination address
+    /// filters. This differs from the other filter bypasses, which only
+    /// affect a single stage of the filter:
+    /// - Promiscuous mode only makes the destination address filter pass
+    ///   all frames. Frames can still be dropped by the source address filter.
+    /// - Pass-all-multicast only makes the destination address filter pass
+    ///   all multicast frames.
+    ///
+    /// Pause control frames are still handled according to the
+    /// Pass Control Frames setting of the MAC.
+    ///
+    /// Because every frame on the link is received, this may significantly
+    /// increase CPU load on busy networks.
+    ///
+    /// Receive-all mode (and promiscuous mode) are enabled by [`new`](crate::new)
+    /// and [`new_with_mii`](crate::new_with_mii).
+    pub fn set_receive_all(&mut self, receive_all: bool) {
+        self.eth_mac.macffr.modify(|_, w| w.ra().bit(receive_all));
+    }
+
+    /// Check whether receive-all mode is enabled.
+    ///
+    /// See [`EthernetMAC::set_receive_all`].
+    pub fn receive_all(&self) -> bool {
+        self.eth_mac.macffr.read().ra().bit_is_set()
+    }
+
     #[cfg(feature = "ptp")]
     pub(crate) fn mask_timestamp_trigger_interrupt() {
         // SAFETY: MACIMR only receives atomic writes.
@@ -0,0 +1,122 @@
+use embedded_hal::{blocking::delay::DelayUs, digital::v2::OutputPin};
+
+/// The timing of a hardware reset of a PHY.
+///
+/// Most common PHYs require a reset pulse of 1 µs to 500 µs, and can be
+/// accessed through MDIO less than a few milliseconds after the reset is
+/// released. The exact values can be found in the datasheet of the PHY, usually
+/// as the minimum reset pulse width and the time between the end of the reset
+/// and the first MDIO access (or the time until the configuration straps are
+/// latched).
+///
+/// The defaults (a 1 ms reset pulse and 10 ms of settling time) are long
+/// enough for most PHYs, at the cost of a slightly slower bring-up.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyResetTiming {
+    /// The time that the reset is asserted for, in microseconds.
+    pub assert_us: u32,
+    /// The time to wait after the reset is released, before the PHY
+    /// is accessed, in microseconds.
+    pub settle_us: u32,
+}
+
+impl PhyResetTiming {
+    /// The default timing: a 1 ms reset pulse, and 10 ms of settling time.
+    pub const DEFAULT: Self = Self {
+        assert_us: 1_000,
+        settle_us: 10_000,
+    };
+}
+
+impl Default for PhyResetTiming {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A hardware reset pin of a PHY.
+///
+/// [`PhyReset::reset`] performs the reset sequence: it asserts the reset,
+/// waits for [`PhyResetTiming::assert_us`], releases the reset, and waits for
+/// [`PhyResetTiming::settle_us`]. Once it returns, the PHY can be accessed
+/// through MDIO. Perform the reset before the PHY is configured, e.g. before
+/// [`EthernetMAC::establish_link`](super::EthernetMAC::establish_link).
+///
+/// The reset input of most PHYs is active-low (`nRST`), which is what
+/// [`PhyReset::new`] expects. Use [`PhyReset::new_active_high`] otherwise.
+pub struct PhyReset<P> {
+    pin: P,
+    active_low: bool,
+    timing: PhyResetTiming,
+}
+
+impl<P> PhyReset<P>
+where
+    P: OutputPin,
+{
+    /// Create a new [`PhyReset`] for the active-low reset pin `pin`, using
+    /// the default timing.
+    pub fn new(pin: P) -> Self {
+        Self {
+            pin,
+            active_low: true,
+            timing: PhyResetTiming::DEFAULT,
+        }
+    }
+
+    /// Create a new [`PhyReset`] for the active-high reset pin `pin`, using
+    /// the default timing.
+    pub fn new_active_high(pin: P) -> Self {
+        Self {
+            active_low: false,
+            ..Self::new(pin)
+        }
+    }
+
+    /// Use `timing` for the reset sequence.
+    pub fn with_timing(self, timing: PhyResetTiming) -> Self {
+        Self { timing, ..self }
+    }
+
+    /// The timing of the reset sequence.
+    pub fn timing(&self) -> PhyResetTiming {
+        self.timing
+    }
+
+    /// Reset the PHY, using `delay` to wait.
+    ///
+    /// Returns the error of the pin if it can not be set.
+    pub fn reset<D>(&mut self, delay: &mut D) -> Result<(), P::Error>
+    where
+        D: DelayUs<u32>,
+    {
+        self.set_reset(true)?;
+        delay.delay_us(self.timing.assert_us);
+        self.set_reset(false)?;
+        delay.delay_us(self.timing.settle_us);
+
+        Ok(())
+    }
+
+    /// Assert the reset of the PHY until [`PhyReset::reset`] is called,
+    /// e.g. to keep it in its low-power reset state.
+    pub fn hold_in_reset(&mut self) -> Result<(), P::Error> {
+        self.set_reset(true)
+    }
+
+    /// Release the pin.
+    ///
+    /// The pin is left in its current state.
+    pub fn release(self) -> P {
+        self.pin
+    }
+
+    fn set_reset(&mut self, reset: bool) -> Result<(), P::Error> {
+        if reset == self.active_low {
+            self.pin.set_low()
+        } else {
+            self.pin.set_high()
+        }
+    }
+}
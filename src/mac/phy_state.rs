@@ -0,0 +1,73 @@
+use ieee802_3_miim::{Miim, Phy};
+
+/// The combined identifier of the SMSC/Microchip PHYs that report
+/// energy detection in their Mode Control/Status register, with the
+/// revision number masked out.
+const LAN87XXA_IDENTS: [u32; 2] = [
+    // LAN8720A
+    0x0007_C0F0,
+    // LAN8742A
+    0x0007_C130,
+];
+
+/// Mode Control/Status register of the LAN87xxA PHYs.
+const LAN87XXA_MODE_CONTROL_STATUS: u8 = 17;
+/// Energy on
+const LAN87XXA_ENERGYON: u16 = 1 << 1;
+
+/// The state of the link of a PHY, including the energy detection
+/// state of PHYs that support it.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhyState {
+    /// The link is up.
+    LinkUp,
+    /// The link is down, but the PHY detects energy on the line: a cable
+    /// is connected and the link is being established.
+    EnergyDetected,
+    /// The link is down, and the PHY does not detect any energy on the
+    /// line: no cable (or no active link partner) is connected.
+    NoEnergy,
+    /// The link is down, and the PHY does not support energy detection.
+    LinkDown,
+}
+
+/// Get the detailed link state of `phy`.
+///
+/// Energy detection is supported on the LAN8720A and LAN8742A. For other
+/// PHYs, this function only returns [`PhyState::LinkUp`] or [`PhyState::LinkDown`].
+///
+/// # Link changes
+/// The MACs of all supported MCUs (STM32F107, STM32F4 and STM32F7) only
+/// support MII and RMII, and do not report changes of the link state: they
+/// have neither an in-band link status (as with RGMII or SMII), nor an input
+/// for the management interrupt of the PHY. The link state must therefore
+/// always be read from the PHY through MDIO, e.g. by calling this function
+/// periodically.
+///
+/// To avoid polling, the interrupt output of the PHY (if any) can be connected
+/// to a GPIO, and this function can be called from the EXTI interrupt of that
+/// GPIO. How the interrupt of the PHY is enabled and acknowledged is specific
+/// to the PHY.
+pub fn phy_state<M, P>(phy: &mut P) -> PhyState
+where
+    M: Miim,
+    P: Phy<M>,
+{
+    if phy.phy_link_up() {
+        return PhyState::LinkUp;
+    }
+
+    let supports_energy_detect = phy
+        .phy_ident()
+        .map(|ident| LAN87XXA_IDENTS.contains(&(ident.raw_u32() & 0xFFFF_FFF0)))
+        .unwrap_or(false);
+
+    if !supports_energy_detect {
+        PhyState::LinkDown
+    } else if phy.read(LAN87XXA_MODE_CONTROL_STATUS) & LAN87XXA_ENERGYON == LAN87XXA_ENERGYON {
+        PhyState::EnergyDetected
+    } else {
+        PhyState::NoEnergy
+    }
+}
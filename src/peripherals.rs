@@ -214,6 +214,14 @@ mod pac_override_impl {
         }
     }
 
+    impl From<ETHERNET_MAC> for PAC_ETHERNET_MAC {
+        fn from(_value: ETHERNET_MAC) -> Self {
+            // SAFETY: `_value` is consumed, so this is the only instance of the
+            // peripheral. All other stolen peripherals are dropped immediately.
+            unsafe { stm32::Peripherals::steal() }.ETHERNET_MAC
+        }
+    }
+
     #[repr(C)]
     pub struct DmaRegisterBlock {
         #[doc = "0x00 - Ethernet DMA bus mode register"]
@@ -286,6 +294,14 @@ mod pac_override_impl {
         }
     }
 
+    impl From<ETHERNET_DMA> for PAC_ETHERNET_DMA {
+        fn from(_value: ETHERNET_DMA) -> Self {
+            // SAFETY: `_value` is consumed, so this is the only instance of the
+            // peripheral. All other stolen peripherals are dropped immediately.
+            unsafe { stm32::Peripherals::steal() }.ETHERNET_DMA
+        }
+    }
+
     #[repr(C)]
     pub struct PtpRegisterBlock {
         #[doc = "0x00 - Ethernet PTP time stamp control register"]
@@ -347,6 +363,14 @@ mod pac_override_impl {
         }
     }
 
+    impl From<ETHERNET_PTP> for PAC_ETHERNET_PTP {
+        fn from(_value: ETHERNET_PTP) -> Self {
+            // SAFETY: `_value` is consumed, so this is the only instance of the
+            // peripheral. All other stolen peripherals are dropped immediately.
+            unsafe { stm32::Peripherals::steal() }.ETHERNET_PTP
+        }
+    }
+
     #[inline(always)]
     /// Preventative measure for issues described in:
     /// Section 2.11.5 of [ES0182, Rev 13] (for F40x and F41x)
@@ -0,0 +1,98 @@
+//! Calibration of the timestamp capture points of the MAC.
+
+use crate::{
+    dma::{bench::BenchError, EthernetDMA},
+    mac::EthernetMAC,
+};
+
+use super::Timestamp;
+
+/// The egress and ingress timestamps of a single frame that was sent
+/// through the MAC loopback.
+///
+/// See [`loopback_latency`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopbackLatency {
+    /// The time at which the MAC captured the egress timestamp of the frame.
+    pub egress: Timestamp,
+    /// The time at which the MAC captured the ingress timestamp of the frame.
+    pub ingress: Timestamp,
+}
+
+impl LoopbackLatency {
+    /// The time between the egress and the ingress timestamp.
+    ///
+    /// This is the sum of the latencies from the egress capture point to the
+    /// loopback, and from the loopback to the ingress capture point.
+    pub fn total(&self) -> Timestamp {
+        self.ingress - self.egress
+    }
+
+    /// The part of [`LoopbackLatency::total`] that is attributed to the
+    /// egress path.
+    ///
+    /// A single loopback can not tell the egress and the ingress latency
+    /// apart, so both are assumed to be equal. If the total latency is an odd
+    /// amount of subsecond increments, the extra increment is attributed to
+    /// [`LoopbackLatency::ingress_latency`].
+    pub fn egress_latency(&self) -> Timestamp {
+        Timestamp::new_raw(self.total().raw() / 2)
+    }
+
+    /// The part of [`LoopbackLatency::total`] that is attributed to the
+    /// ingress path.
+    ///
+    /// See [`LoopbackLatency::egress_latency`].
+    pub fn ingress_latency(&self) -> Timestamp {
+        self.total() - self.egress_latency()
+    }
+}
+
+/// Measure the latency between the egress and ingress timestamp capture
+/// points of the MAC by sending a single frame through the MAC loopback.
+///
+/// The MAC loopback is enabled while this function runs, and restored to its
+/// previous state afterwards. Frames in the RX ring that were received before
+/// this function was called are dropped. The frame is sent like the frames of
+/// [`loopback_rtt`](crate::dma::bench::loopback_rtt), so the same
+/// requirements apply: a PHY must be connected to provide the clocks, and on
+/// the STM32F107 the MAC may not capture an ingress timestamp for it, in which
+/// case [`BenchError::MissingTimestamp`] is returned.
+///
+/// This is meant to be run once, e.g. during production, and not while the
+/// link is used. [`PtpClockConfig`](super::PtpClockConfig) has no settings for
+/// the latencies, and the MAC does not correct the timestamps it captures, so
+/// the measured latencies must be applied to the timestamps by the user (e.g.
+/// by subtracting [`LoopbackLatency::ingress_latency`] from every ingress
+/// timestamp).
+pub fn loopback_latency(
+    dma: &mut EthernetDMA,
+    mac: &mut EthernetMAC,
+) -> Result<LoopbackLatency, BenchError> {
+    let loopback = mac.loopback();
+    mac.set_loopback(true);
+
+    let result = crate::dma::bench::loopback_frame(dma, mac.address(), 0);
+
+    mac.set_loopback(loopback);
+
+    result.map(|(_, egress, ingress)| LoopbackLatency { egress, ingress })
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn latency_split() {
+        let latency = LoopbackLatency {
+            egress: Timestamp::new_raw(1000),
+            ingress: Timestamp::new_raw(1007),
+        };
+
+        assert_eq!(latency.total().raw(), 7);
+        assert_eq!(latency.egress_latency().raw(), 3);
+        assert_eq!(latency.ingress_latency().raw(), 4);
+    }
+}
@@ -0,0 +1,185 @@
+use super::{PtpUpdateTimeout, Subseconds, NANOS_PER_SECOND, SUBSECONDS_PER_SECOND};
+
+/// The largest subsecond increment that fits in the 8-bit
+/// subsecond increment register.
+const MAX_INCREMENT: u32 = u8::MAX as u32;
+
+/// The clock configuration of the PTP peripheral.
+///
+/// See [`EthernetPTP`](super::EthernetPTP) for a description of how the
+/// global time is derived from these values.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PtpClockConfig {
+    /// The frequency of the clock driving the PTP peripheral (HCLK), in Hz.
+    pub clock_hz: u32,
+    /// The frequency at which the global time is updated, in Hz.
+    ///
+    /// Every update advances the global time by the subsecond increment,
+    /// so this determines the resolution of the global time and of the
+    /// timestamps. It must not be greater than `clock_hz`, and the resulting
+    /// subsecond increment must fit into 8 bits, which requires a value of
+    /// at least ~8.43 MHz.
+    pub update_hz: u32,
+}
+
+/// An error returned if a [`PtpClockConfig`] can not be realized.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PtpClockConfigError {
+    /// The update frequency is greater than the clock frequency.
+    UpdateRateTooHigh,
+    /// The subsecond increment required for the update frequency
+    /// does not fit into the 8-bit subsecond increment register.
+    UpdateRateTooLow,
+    /// The PTP clock did not apply the new configuration (see
+    /// [`PtpUpdateTimeout`]).
+    ///
+    /// This is never returned by [`PtpClockConfig::registers`].
+    UpdateTimeout,
+}
+
+impl From<PtpUpdateTimeout> for PtpClockConfigError {
+    fn from(_: PtpUpdateTimeout) -> Self {
+        Self::UpdateTimeout
+    }
+}
+
+/// An error returned by
+/// [`EthernetPTP::configure_resolution`](super::EthernetPTP::configure_resolution)
+/// if the requested resolution can not be achieved.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PtpResolutionError {
+    /// The resolution is finer than `finest_ns`, the finest resolution that
+    /// can be achieved with the clock driving the PTP peripheral.
+    TooFine {
+        /// The finest achievable resolution, in nanoseconds.
+        finest_ns: u32,
+    },
+    /// The resolution is coarser than `coarsest_ns`, the coarsest resolution
+    /// whose subsecond increment fits into the 8-bit subsecond increment
+    /// register.
+    TooCoarse {
+        /// The coarsest achievable resolution, in nanoseconds.
+        coarsest_ns: u32,
+    },
+    /// The PTP clock did not apply the new addend (see [`PtpUpdateTimeout`]).
+    UpdateTimeout,
+}
+
+impl From<PtpUpdateTimeout> for PtpResolutionError {
+    fn from(_: PtpUpdateTimeout) -> Self {
+        Self::UpdateTimeout
+    }
+}
+
+impl PtpClockConfig {
+    /// Create a new configuration for a PTP peripheral driven by a clock
+    /// running at `clock_hz`, that updates the global time at half of that
+    /// frequency.
+    ///
+    /// This is the configuration used by [`new`](crate::new) and
+    /// [`new_with_mii`](crate::new_with_mii).
+    pub const fn new(clock_hz: u32) -> Self {
+        Self {
+            clock_hz,
+            update_hz: clock_hz / 2,
+        }
+    }
+
+    /// Create a new configuration for a PTP peripheral driven by a clock
+    /// running at `clock_hz`, that advances the global time by (approximately)
+    /// `resolution_ns` nanoseconds on every update.
+    ///
+    /// The actual resolution is the nearest multiple of one subsecond
+    /// (~0.466 ns).
+    pub const fn with_resolution(clock_hz: u32, resolution_ns: u32) -> Self {
+        let update_hz = match NANOS_PER_SECOND.checked_div(resolution_ns) {
+            Some(update_hz) => update_hz,
+            None => u32::MAX,
+        };
+
+        Self {
+            clock_hz,
+            update_hz,
+        }
+    }
+
+    /// Calculate the subsecond increment and the `addend` required for
+    /// running the global time at the correct rate, with a resolution of
+    /// `resolution_ns`.
+    pub(super) const fn resolution_registers(
+        clock_hz: u32,
+        resolution_ns: u32,
+    ) -> Result<(Subseconds, u32), PtpResolutionError> {
+        match Self::with_resolution(clock_hz, resolution_ns).registers() {
+            Ok(registers) => Ok(registers),
+            Err(PtpClockConfigError::UpdateRateTooHigh) => Err(PtpResolutionError::TooFine {
+                finest_ns: Self::finest_resolution_ns(clock_hz),
+            }),
+            Err(PtpClockConfigError::UpdateRateTooLow) => Err(PtpResolutionError::TooCoarse {
+                coarsest_ns: Self::coarsest_resolution_ns(),
+            }),
+            Err(PtpClockConfigError::UpdateTimeout) => Err(PtpResolutionError::UpdateTimeout),
+        }
+    }
+
+    /// The finest resolution, in whole nanoseconds, that can be achieved
+    /// with a clock running at `clock_hz`.
+    const fn finest_resolution_ns(clock_hz: u32) -> u32 {
+        if clock_hz == 0 {
+            return u32::MAX;
+        }
+
+        // The global time can be updated at most once per clock cycle.
+        let increment = SUBSECONDS_PER_SECOND.div_ceil(clock_hz);
+        let mut nanos = Subseconds::new_unchecked(increment).nanos();
+
+        // Rounding to whole nanoseconds may result in an update rate
+        // that is slightly too high.
+        while Self::with_resolution(clock_hz, nanos).registers().is_err()
+            && nanos < Self::coarsest_resolution_ns()
+        {
+            nanos += 1;
+        }
+
+        nanos
+    }
+
+    /// The coarsest resolution whose subsecond increment fits into the
+    /// subsecond increment register, rounded down to whole nanoseconds.
+    const fn coarsest_resolution_ns() -> u32 {
+        ((MAX_INCREMENT as u64 * NANOS_PER_SECOND as u64) / SUBSECONDS_PER_SECOND as u64) as u32
+    }
+
+    /// Calculate the subsecond increment and the `addend` required
+    /// for running the global time at the correct rate.
+    pub const fn registers(&self) -> Result<(Subseconds, u32), PtpClockConfigError> {
+        if self.update_hz == 0 {
+            return Err(PtpClockConfigError::UpdateRateTooLow);
+        }
+
+        // Calculate the closest `subsecond_increment` we can use if we want to update at a
+        // frequency of `update_hz`
+        let stssi = Subseconds::nearest_increment(self.update_hz);
+
+        if stssi.raw() > MAX_INCREMENT {
+            return Err(PtpClockConfigError::UpdateRateTooLow);
+        } else if stssi.raw() == 0 {
+            return Err(PtpClockConfigError::UpdateRateTooHigh);
+        }
+
+        let subsec_increment_hz = stssi.hertz();
+
+        if subsec_increment_hz > self.clock_hz {
+            return Err(PtpClockConfigError::UpdateRateTooHigh);
+        }
+
+        // Calculate the `addend` required for running `global_time` at
+        // the correct rate, given that we increment `global_time` by `stssi` every
+        // time `accumulator` overflows.
+        let tsa = ((subsec_increment_hz as u64 * u32::MAX as u64) / self.clock_hz as u64) as u32;
+        Ok((stssi, tsa))
+    }
+}
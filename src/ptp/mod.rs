@@ -16,6 +16,30 @@ pub use subseconds::{Subseconds, NANOS_PER_SECOND, SUBSECONDS_PER_SECOND, SUBSEC
 mod pps_pin;
 pub use pps_pin::PPSPin;
 
+mod config;
+pub use config::{PtpClockConfig, PtpClockConfigError, PtpResolutionError};
+
+#[cfg(not(feature = "stm32f1xx-hal"))]
+mod periodic;
+
+/// The PTP clock did not apply an update of the addend or of the global time
+/// within [`DMA_TIMEOUT_ITERATIONS`](crate::dma::DMA_TIMEOUT_ITERATIONS).
+///
+/// The update bits clear within a few cycles of the PTP clock, so this
+/// indicates that the PTP clock is not running. The update is abandoned, and
+/// may still be applied once the PTP clock starts running.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtpUpdateTimeout;
+
+/// Wait until the PTP clock has applied an update.
+fn wait_for_update(what: &'static str, done: impl FnMut() -> bool) -> Result<(), PtpUpdateTimeout> {
+    crate::wait::wait_for(what, crate::dma::DMA_TIMEOUT_ITERATIONS, done)
+        .map_err(|_| PtpUpdateTimeout)
+}
+
+pub mod calibrate;
+
 /// Access to the IEEE 1508v2 PTP peripheral present on the ethernet peripheral.
 ///
 /// On STM32FXXX's, the PTP peripheral has/uses the following important parts:
@@ -31,7 +55,8 @@ pub use pps_pin::PPSPin;
 ///
 /// When a new [`EthernetPTP`] is created, it is assumed that the frequency of HCLK is exactly correct.
 /// Using HCLK, values for `subsecond_increment` and `addend` are calculated so that `global_time` represents
-/// real-time.
+/// real-time. By default, `global_time` is updated at half of the frequency of HCLK. A different update rate
+/// can be selected using [`EthernetPTP::init`].
 ///
 /// Subsequently, `addend` can be adjusted to compensate for possible errors in HCLK, using [`EthernetPTP::addend`] and [`EthernetPTP::set_addend`]
 ///
@@ -51,23 +76,6 @@ pub struct EthernetPTP {
 }
 
 impl EthernetPTP {
-    // Calculate the `addend` required for running `global_time` at
-    // the correct rate
-    const fn calculate_regs(hclk: u32) -> (Subseconds, u32) {
-        let half_hclk = hclk / 2;
-
-        // Calculate the closest `subsecond_increment` we can use if we want to update at a
-        // frequency of `half_hclk`
-        let stssi = Subseconds::nearest_increment(half_hclk);
-        let half_rate_subsec_increment_hz = stssi.hertz();
-
-        // Calculate the `addend` required for running `global_time` at
-        // the correct rate, given that we increment `global_time` by `stssi` every
-        // time `accumulator` overflows.
-        let tsa = ((half_rate_subsec_increment_hz as u64 * u32::MAX as u64) / hclk as u64) as u32;
-        (stssi, tsa)
-    }
-
     pub(crate) fn new(
         eth_ptp: ETHERNET_PTP,
         clocks: Clocks,
@@ -81,28 +89,103 @@ impl EthernetPTP {
 
         let hclk = clocks.hclk().to_Hz();
 
-        let (stssi, tsa) = Self::calculate_regs(hclk);
+        let mut me = Self { eth_ptp };
+
+        // NOTE(unused): this configuration is valid for all HCLK frequencies
+        // supported by the MAC, and `EthernetMAC::new` rejects all other ones.
+        // The updates can not time out either: the DMA reset only completes if
+        // all clocks of the Ethernet peripheral are running.
+        let _ = me.init(PtpClockConfig::new(hclk));
+
+        me
+    }
+
+    /// Release the PTP peripheral.
+    ///
+    /// See [`Parts::release`](crate::Parts::release).
+    pub(crate) fn release(self) -> ETHERNET_PTP {
+        self.eth_ptp
+    }
+
+    /// Initialize the PTP clock with `config`, and reset the global time to zero.
+    ///
+    /// This performs the complete initialization sequence of the PTP peripheral:
+    /// 1. Enable timestamping.
+    /// 2. Program the subsecond increment.
+    /// 3. Program the addend.
+    /// 4. Enable the fine update method.
+    /// 5. Initialize the global time.
+    ///
+    /// It is run with [`PtpClockConfig::new`] by [`new`](crate::new) and
+    /// [`new_with_mii`](crate::new_with_mii), so this is only required if a different
+    /// resolution is desired.
+    ///
+    /// If the subsecond increment and addend can not be calculated for `config`,
+    /// an error is returned, and the PTP peripheral is not modified. If the
+    /// PTP clock does not apply the addend or the global time,
+    /// [`PtpClockConfigError::UpdateTimeout`] is returned.
+    pub fn init(&mut self, config: PtpClockConfig) -> Result<(), PtpClockConfigError> {
+        let (stssi, tsa) = config.registers()?;
 
-        // Setup PTP timestamping in fine mode.
-        eth_ptp.ptptscr.write(|w| {
+        let ptp = &self.eth_ptp;
+
+        // Enable timestamping.
+        ptp.ptptscr.write(|w| {
             // Enable snapshots for all frames.
             #[cfg(not(feature = "stm32f1xx-hal"))]
             let w = w.tssarfe().set_bit();
 
-            w.tse().set_bit().tsfcu().set_bit()
+            w.tse().set_bit()
         });
 
         // Set up subsecond increment
-        eth_ptp
-            .ptpssir
+        ptp.ptpssir
             .write(|w| unsafe { w.stssi().bits(stssi.raw() as u8) });
 
-        let mut me = Self { eth_ptp };
+        self.set_addend(tsa)?;
 
-        me.set_addend(tsa);
-        me.set_time(Timestamp::new_unchecked(false, 0, 0));
+        // Switch to fine update mode.
+        self.eth_ptp.ptptscr.modify(|_, w| w.tsfcu().set_bit());
 
-        me
+        self.set_time(Timestamp::new_unchecked(false, 0, 0))?;
+
+        Ok(())
+    }
+
+    /// Configure the PTP clock to advance the global time by (approximately)
+    /// `resolution_ns` nanoseconds on every update, given that the PTP
+    /// peripheral is driven by a clock (HCLK) running at `clock_hz`.
+    ///
+    /// This calculates and programs the subsecond increment and the initial
+    /// addend (see [`PtpClockConfig::with_resolution`]). Unlike
+    /// [`EthernetPTP::init`], the global time is not reset. The achieved
+    /// resolution is the nearest multiple of one subsecond, and can be read
+    /// back with [`EthernetPTP::subsecond_increment`].
+    ///
+    /// For instance, a `resolution_ns` of 20 results in a subsecond increment
+    /// of 43 (~20.02 ns), which requires an HCLK of at least ~50 MHz.
+    ///
+    /// If the resolution can not be achieved, an error that contains the
+    /// finest or coarsest achievable resolution is returned, and the PTP
+    /// peripheral is not modified. The coarsest resolution is ~118 ns, as the
+    /// subsecond increment register only has 8 bits. The finest resolution is
+    /// limited by `clock_hz`, as the global time is updated at most once per
+    /// clock cycle. If the PTP clock does not apply the addend,
+    /// [`PtpResolutionError::UpdateTimeout`] is returned.
+    pub fn configure_resolution(
+        &mut self,
+        clock_hz: u32,
+        resolution_ns: u32,
+    ) -> Result<(), PtpResolutionError> {
+        let (stssi, tsa) = PtpClockConfig::resolution_registers(clock_hz, resolution_ns)?;
+
+        self.eth_ptp
+            .ptpssir
+            .write(|w| unsafe { w.stssi().bits(stssi.raw() as u8) });
+
+        self.set_addend(tsa)?;
+
+        Ok(())
     }
 
     /// Get the configured subsecond increment.
@@ -116,28 +199,40 @@ impl EthernetPTP {
     }
 
     /// Set the PTP clock addend.
+    ///
+    /// Returns an error if the PTP clock does not apply the new addend.
     #[inline(always)]
-    pub fn set_addend(&mut self, rate: u32) {
+    pub fn set_addend(&mut self, rate: u32) -> Result<(), PtpUpdateTimeout> {
         let ptp = &self.eth_ptp;
         ptp.ptptsar.write(|w| unsafe { w.bits(rate) });
 
         #[cfg(feature = "stm32f1xx-hal")]
         {
-            while ptp.ptptscr.read().tsaru().bit_is_set() {}
+            wait_for_update("the addend update", || {
+                ptp.ptptscr.read().tsaru().bit_is_clear()
+            })?;
             ptp.ptptscr.modify(|_, w| w.tsaru().set_bit());
-            while ptp.ptptscr.read().tsaru().bit_is_set() {}
+            wait_for_update("the addend update", || {
+                ptp.ptptscr.read().tsaru().bit_is_clear()
+            })
         }
 
         #[cfg(not(feature = "stm32f1xx-hal"))]
         {
-            while ptp.ptptscr.read().ttsaru().bit_is_set() {}
+            wait_for_update("the addend update", || {
+                ptp.ptptscr.read().ttsaru().bit_is_clear()
+            })?;
             ptp.ptptscr.modify(|_, w| w.ttsaru().set_bit());
-            while ptp.ptptscr.read().ttsaru().bit_is_set() {}
+            wait_for_update("the addend update", || {
+                ptp.ptptscr.read().ttsaru().bit_is_clear()
+            })
         }
     }
 
     /// Set the current time.
-    pub fn set_time(&mut self, time: Timestamp) {
+    ///
+    /// Returns an error if the PTP clock does not apply the new time.
+    pub fn set_time(&mut self, time: Timestamp) -> Result<(), PtpUpdateTimeout> {
         let ptp = &self.eth_ptp;
 
         let seconds = time.seconds();
@@ -147,16 +242,22 @@ impl EthernetPTP {
         ptp.ptptslur.write(|w| unsafe { w.bits(subseconds) });
 
         // Initialise timestamp
-        while ptp.ptptscr.read().tssti().bit_is_set() {}
+        wait_for_update("the time initialization", || {
+            ptp.ptptscr.read().tssti().bit_is_clear()
+        })?;
         ptp.ptptscr.modify(|_, w| w.tssti().set_bit());
-        while ptp.ptptscr.read().tssti().bit_is_set() {}
+        wait_for_update("the time initialization", || {
+            ptp.ptptscr.read().tssti().bit_is_clear()
+        })
     }
 
     /// Add the provided time to the current time, atomically.
     ///
     /// If `time` is negative, it will instead be subtracted from the
     /// system time.
-    pub fn update_time(&mut self, time: Timestamp) {
+    ///
+    /// Returns an error if the PTP clock does not apply the update.
+    pub fn update_time(&mut self, time: Timestamp) -> Result<(), PtpUpdateTimeout> {
         let ptp = &self.eth_ptp;
 
         let seconds = time.seconds();
@@ -172,9 +273,11 @@ impl EthernetPTP {
             scr.tsstu().bit_is_set() || scr.tssti().bit_is_set()
         };
 
-        while read_status() {}
+        wait_for_update("the time update", || !read_status())?;
         ptp.ptptscr.modify(|_, w| w.tsstu().set_bit());
-        while ptp.ptptscr.read().tsstu().bit_is_set() {}
+        wait_for_update("the time update", || {
+            ptp.ptptscr.read().tsstu().bit_is_clear()
+        })
     }
 
     /// Get the current time
@@ -226,25 +329,87 @@ impl EthernetPTP {
         &WAKER
     }
 
-    /// Configure the target time.
-    fn set_target_time(&mut self, timestamp: Timestamp) {
+    /// Configure the target time, and enable the target time interrupt.
+    ///
+    /// This is also called from [`EthernetPTP::interrupt_handler`], so it can
+    /// not take `&mut self`.
+    fn arm_target_time_interrupt(timestamp: Timestamp) {
+        // SAFETY: the target time registers and the TSITE bit are only
+        // written by this function.
+        let eth_ptp = unsafe { &*ETHERNET_PTP::ptr() };
+
         let (high, low) = (timestamp.seconds(), timestamp.subseconds_signed());
-        self.eth_ptp
-            .ptptthr
-            .write(|w| unsafe { w.ttsh().bits(high) });
-        self.eth_ptp
-            .ptpttlr
-            .write(|w| unsafe { w.ttsl().bits(low) });
+        eth_ptp.ptptthr.write(|w| unsafe { w.ttsh().bits(high) });
+        eth_ptp.ptpttlr.write(|w| unsafe { w.ttsl().bits(low) });
+
+        eth_ptp.ptptscr.modify(|_, w| w.tsite().set_bit());
+        EthernetMAC::unmask_timestamp_trigger_interrupt();
     }
 
     /// Configure the target time interrupt.
     ///
     /// You must call [`EthernetPTP::interrupt_handler`] in the `ETH`
     /// interrupt to detect (and clear) the correct status bits.
+    ///
+    /// This stops the periodic interrupts configured with
+    /// [`EthernetPTP::configure_periodic`], if any.
     pub fn configure_target_time_interrupt(&mut self, timestamp: Timestamp) {
-        self.set_target_time(timestamp);
-        self.eth_ptp.ptptscr.modify(|_, w| w.tsite().set_bit());
-        EthernetMAC::unmask_timestamp_trigger_interrupt();
+        periodic::stop();
+        Self::arm_target_time_interrupt(timestamp);
+    }
+
+    /// Generate a target time interrupt at `start`, and every `period`
+    /// after it, aligned to the PTP time.
+    ///
+    /// The MACs of the supported MCUs have a single target time, without
+    /// an auto-reload: [`EthernetPTP::interrupt_handler`] programs the next
+    /// target time whenever the current one is reached, so it must be called
+    /// in the `ETH` interrupt. It returns `true` for every period. Use
+    /// [`EthernetPTP::periodic_ticks`] to find out how many periods have
+    /// elapsed.
+    ///
+    /// The target times are always `start + n * period`, so the interrupts
+    /// do not drift, even if some of them are handled late. The interrupts
+    /// are raised once the PTP time reaches the target time, which it is
+    /// compared to on every update of the PTP time (see
+    /// [`EthernetPTP::subsecond_increment`]). They are delayed by the
+    /// interrupt latency, which is also their jitter: it depends on the
+    /// priority of the `ETH` interrupt and on the other interrupts.
+    ///
+    /// The minimum period is the time it takes to enter the `ETH` interrupt
+    /// and to reprogram the target time, i.e. a few microseconds. If a target
+    /// time has already passed when it would be programmed (because the
+    /// period is too short, or the interrupt was handled too late), its
+    /// periods are skipped, but still counted by [`EthernetPTP::periodic_ticks`].
+    ///
+    /// The periodic interrupts share the target time with
+    /// [`EthernetPTP::configure_target_time_interrupt`],
+    /// [`EthernetPTP::wait_until`] and [`EthernetDMA::send_at`], which stop them.
+    ///
+    /// # Panics
+    /// Panics if `period` is not positive.
+    pub fn configure_periodic(&mut self, start: Timestamp, period: Timestamp) {
+        assert!(period.raw() > 0, "The period must be positive");
+
+        periodic::start(start, period);
+        Self::arm_target_time_interrupt(start);
+    }
+
+    /// Stop the periodic interrupts configured with
+    /// [`EthernetPTP::configure_periodic`].
+    ///
+    /// The interrupt for the current target time is still raised.
+    pub fn stop_periodic(&mut self) {
+        periodic::stop();
+    }
+
+    /// Get the amount of periods that have elapsed since the periodic
+    /// interrupts were configured with [`EthernetPTP::configure_periodic`].
+    ///
+    /// This includes periods whose interrupt was skipped. The counter
+    /// wraps around on overflow.
+    pub fn periodic_ticks() -> u32 {
+        periodic::ticks()
     }
 
     /// Wait until the specified time.
@@ -275,6 +440,11 @@ impl EthernetPTP {
     /// Returns a boolean indicating whether or not the interrupt
     /// was caused by a Timestamp trigger and clears the interrupt
     /// flag.
+    ///
+    /// If the interrupt was caused by a Timestamp trigger, the DMA is polled
+    /// for the frame that waits for its launch time (see
+    /// [`EthernetDMA::send_at`]), and the next target time of the periodic
+    /// interrupts (see [`EthernetPTP::configure_periodic`]) is programmed.
     pub fn interrupt_handler() -> bool {
         // SAFETY: we only perform one atomic read.
         let eth_mac = unsafe { &*crate::peripherals::ETHERNET_MAC::ptr() };
@@ -282,6 +452,11 @@ impl EthernetPTP {
         let is_tsint = eth_mac.macsr.read().tsts().bit_is_set();
         if is_tsint {
             EthernetMAC::mask_timestamp_trigger_interrupt();
+
+            // The frame that waits for this target time (if any) is already
+            // owned by the DMA, which only fetches it once it is polled.
+            #[cfg(not(feature = "stm32f1xx-hal"))]
+            crate::dma::TxRing::demand_poll();
         }
 
         #[cfg(feature = "async-await")]
@@ -294,6 +469,12 @@ impl EthernetPTP {
         #[cfg(not(feature = "async-await"))]
         EthernetPTP::read_and_clear_interrupt_flag();
 
+        if is_tsint {
+            if let Some(next) = periodic::tick(EthernetPTP::get_time()) {
+                EthernetPTP::arm_target_time_interrupt(next);
+            }
+        }
+
         is_tsint
     }
 
@@ -324,7 +505,7 @@ mod test {
     #[test]
     fn hclk_to_regs() {
         for hclk_hz in (25..180).map(|v| v * 1_000_000) {
-            let (stssi, tsa) = EthernetPTP::calculate_regs(hclk_hz);
+            let (stssi, tsa) = PtpClockConfig::new(hclk_hz).registers().unwrap();
 
             let stssi = stssi.raw() as f64;
             let tsa = tsa as f64;
@@ -338,4 +519,65 @@ mod test {
             assert!(ppm <= 0.06, "{} at {}", ppm, hclk_hz);
         }
     }
+
+    // Test that configurations that do not fit in the registers are rejected.
+    #[test]
+    fn invalid_clock_config() {
+        let config = |clock_hz, update_hz| PtpClockConfig {
+            clock_hz,
+            update_hz,
+        };
+
+        // The increment of 2^31 / 8 MHz = 268 does not fit into 8 bits
+        assert_eq!(
+            config(100_000_000, 8_000_000).registers(),
+            Err(PtpClockConfigError::UpdateRateTooLow)
+        );
+        assert_eq!(
+            config(100_000_000, 0).registers(),
+            Err(PtpClockConfigError::UpdateRateTooLow)
+        );
+        assert_eq!(
+            config(25_000_000, 50_000_000).registers(),
+            Err(PtpClockConfigError::UpdateRateTooHigh)
+        );
+
+        let (stssi, tsa) = config(50_000_000, 10_000_000).registers().unwrap();
+        assert_eq!(stssi.raw(), 215);
+        assert_eq!(
+            tsa,
+            ((stssi.hertz() as u64 * u32::MAX as u64) / 50_000_000) as u32
+        );
+    }
+
+    // Test that resolutions are converted to the correct registers, and that
+    // the reported limits can be achieved.
+    #[test]
+    fn resolution_to_regs() {
+        let (stssi, _) = PtpClockConfig::resolution_registers(50_000_000, 20).unwrap();
+        assert_eq!(stssi.raw(), 43);
+
+        assert_eq!(
+            PtpClockConfig::resolution_registers(50_000_000, 10),
+            Err(PtpResolutionError::TooFine { finest_ns: 20 })
+        );
+        assert_eq!(
+            PtpClockConfig::resolution_registers(50_000_000, 0),
+            Err(PtpResolutionError::TooFine { finest_ns: 20 })
+        );
+        assert_eq!(
+            PtpClockConfig::resolution_registers(50_000_000, 200),
+            Err(PtpResolutionError::TooCoarse { coarsest_ns: 118 })
+        );
+
+        for hclk_hz in (25..180).map(|v| v * 1_000_000) {
+            let finest_ns = match PtpClockConfig::resolution_registers(hclk_hz, 1) {
+                Err(PtpResolutionError::TooFine { finest_ns }) => finest_ns,
+                other => panic!("{:?} at {}", other, hclk_hz),
+            };
+
+            assert!(PtpClockConfig::resolution_registers(hclk_hz, finest_ns).is_ok());
+            assert!(PtpClockConfig::resolution_registers(hclk_hz, 118).is_ok());
+        }
+    }
 }
@@ -0,0 +1,103 @@
+//! Periodic target time interrupts.
+//!
+//! See [`EthernetPTP::configure_periodic`](super::EthernetPTP::configure_periodic).
+
+use core::{
+    cell::Cell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use cortex_m::interrupt::Mutex;
+
+use super::Timestamp;
+
+#[derive(Clone, Copy)]
+struct Schedule {
+    next: Timestamp,
+    period: Timestamp,
+}
+
+/// The next target time and the period, if periodic interrupts are enabled.
+static SCHEDULE: Mutex<Cell<Option<Schedule>>> = Mutex::new(Cell::new(None));
+
+/// The amount of periods that have elapsed since periodic interrupts
+/// were enabled.
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Start generating an interrupt at `first`, and every `period` after it.
+pub(super) fn start(first: Timestamp, period: Timestamp) {
+    cortex_m::interrupt::free(|cs| {
+        TICKS.store(0, Ordering::Relaxed);
+        SCHEDULE.borrow(cs).set(Some(Schedule {
+            next: first,
+            period,
+        }));
+    });
+}
+
+/// Stop generating periodic interrupts.
+pub(super) fn stop() {
+    cortex_m::interrupt::free(|cs| SCHEDULE.borrow(cs).set(None));
+}
+
+/// The amount of periods that have elapsed since periodic interrupts
+/// were enabled.
+pub(super) fn ticks() -> u32 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Record that the current target time was reached at `now`, and get the
+/// next target time, if periodic interrupts are enabled.
+pub(super) fn tick(now: Timestamp) -> Option<Timestamp> {
+    cortex_m::interrupt::free(|cs| {
+        let schedule = SCHEDULE.borrow(cs);
+        let mut current = schedule.get()?;
+
+        let (next, elapsed) = advance(current.next, current.period, now);
+        current.next = next;
+        schedule.set(Some(current));
+
+        TICKS.fetch_add(elapsed, Ordering::Relaxed);
+
+        Some(next)
+    })
+}
+
+/// Advance the target time `next`, which has been reached, by as many
+/// `period`s as required for it to lie after `now`.
+///
+/// Returns the new target time, and the amount of periods that it was
+/// advanced by.
+fn advance(next: Timestamp, period: Timestamp, now: Timestamp) -> (Timestamp, u32) {
+    let elapsed = if now.raw() >= next.raw() {
+        (now.raw() - next.raw()) / period.raw() + 1
+    } else {
+        1
+    };
+
+    let next = Timestamp::new_raw(next.raw() + elapsed * period.raw());
+
+    (next, elapsed as u32)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn advance_skips_missed_periods() {
+        let period = Timestamp::new_raw(100);
+
+        // The interrupt was handled in time.
+        let (next, elapsed) = advance(Timestamp::new_raw(1000), period, Timestamp::new_raw(1010));
+        assert_eq!((next.raw(), elapsed), (1100, 1));
+
+        // The interrupt was handled after the next target time, which is skipped.
+        let (next, elapsed) = advance(Timestamp::new_raw(1000), period, Timestamp::new_raw(1150));
+        assert_eq!((next.raw(), elapsed), (1200, 2));
+
+        // The next target time is never the current time.
+        let (next, elapsed) = advance(Timestamp::new_raw(1000), period, Timestamp::new_raw(1100));
+        assert_eq!((next.raw(), elapsed), (1200, 2));
+    }
+}
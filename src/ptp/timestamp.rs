@@ -9,10 +9,12 @@ pub struct Timestamp(i64);
 #[cfg(feature = "defmt")]
 impl defmt::Format for Timestamp {
     fn format(&self, fmt: defmt::Formatter) {
-        if self.is_positive() {
-            defmt::write!(fmt, "{}.{:09}", self.seconds(), self.nanos());
+        let (negative, seconds, nanos) = self.normalized();
+
+        if !negative {
+            defmt::write!(fmt, "{}.{:09}", seconds, nanos);
         } else {
-            defmt::write!(fmt, "-{}.{:09}", self.seconds(), self.nanos());
+            defmt::write!(fmt, "-{}.{:09}", seconds, nanos);
         }
     }
 }
@@ -85,12 +87,46 @@ impl Timestamp {
     }
 
     /// Get the nanosecond component of this timestamp
+    ///
+    /// Subsecond values that are less than half a nanosecond away from
+    /// a full second round up to [`NANOS_PER_SECOND`], so that
+    /// `seconds() * NANOS_PER_SECOND + nanos()` is always equal to the
+    /// magnitude of [`Timestamp::total_nanos`]. Use [`Timestamp::normalized`]
+    /// to get a nanosecond component that is always less than
+    /// [`NANOS_PER_SECOND`].
     pub const fn nanos(&self) -> u32 {
         self.subseconds().nanos()
     }
 
+    /// Get the sign, seconds and nanoseconds of this timestamp, with the
+    /// nanoseconds normalized to be less than [`NANOS_PER_SECOND`].
+    ///
+    /// Unlike [`Timestamp::seconds`] and [`Timestamp::nanos`], a subsecond
+    /// value that rounds up to a full second is carried into the seconds.
+    /// The sign applies to both the seconds and the nanoseconds, so the
+    /// nanoseconds are never negative relative to the seconds.
+    ///
+    /// Example:
+    /// ```rust
+    /// # use stm32_eth::ptp::{Subseconds, Timestamp};
+    /// let timestamp = Timestamp::new(true, 2, Subseconds::MAX);
+    /// assert_eq!(timestamp.nanos(), 1_000_000_000);
+    /// assert_eq!(timestamp.normalized(), (true, 3, 0));
+    /// ```
+    pub const fn normalized(&self) -> (bool, u64, u32) {
+        let total_nanos = self.total_nanos().unsigned_abs();
+        let seconds = total_nanos / NANOS_PER_SECOND as u64;
+        let nanos = (total_nanos % NANOS_PER_SECOND as u64) as u32;
+
+        (self.is_negative(), seconds, nanos)
+    }
+
     /// Get the total amount of nanoseconds in this [`Timestamp`].
     ///
+    /// The subseconds are converted to nanoseconds assuming binary
+    /// rollover (which is what [`EthernetPTP`](super::EthernetPTP)
+    /// configures), rounding to the nearest nanosecond.
+    ///
     /// Example:
     /// ```rust
     /// # use stm32_eth::ptp::{Subseconds, Timestamp};
@@ -250,4 +286,86 @@ mod test {
         assert_eq!(one_neg - two_neg, one);
         assert_eq!(two_neg - one_neg, one_minus_two);
     }
+
+    #[test]
+    fn timestamp_total_nanos() {
+        let half = Subseconds::new_from_nanos(500_000_000).unwrap();
+
+        let values = [
+            (Timestamp::new(false, 0, subs(0)), 0),
+            (Timestamp::new(false, 0, subs(1)), 0),
+            (Timestamp::new(false, 0, subs(3)), 1),
+            (Timestamp::new(false, 1, half), 1_500_000_000),
+            (Timestamp::new(true, 3, half), -3_500_000_000),
+            (
+                Timestamp::new(false, 1234, subs(0x4000_0000)),
+                1_234_500_000_000,
+            ),
+            // Rounds up to the next second
+            (Timestamp::new(false, 2, Subseconds::MAX), 3_000_000_000),
+            (Timestamp::new(true, 2, Subseconds::MAX), -3_000_000_000),
+        ];
+
+        for (timestamp, nanos) in values {
+            assert_eq!(timestamp.total_nanos(), nanos, "{:?}", timestamp);
+        }
+    }
+
+    /// Check that subtracting `rhs` from `lhs` is consistent with the
+    /// nanosecond values of both, and that the result is normalized.
+    fn assert_sub_consistent(lhs: Timestamp, rhs: Timestamp, expected_nanos: i64) {
+        let diff = lhs - rhs;
+
+        assert_eq!(diff.total_nanos(), expected_nanos, "{:?} - {:?}", lhs, rhs);
+        assert!(
+            (lhs.total_nanos() - rhs.total_nanos() - expected_nanos).abs() <= 1,
+            "{:?} - {:?}",
+            lhs,
+            rhs
+        );
+
+        let (negative, seconds, nanos) = diff.normalized();
+        assert!(nanos < 1_000_000_000);
+        assert_eq!(negative, diff.is_negative());
+        let magnitude = seconds as i64 * 1_000_000_000 + nanos as i64;
+        assert_eq!(magnitude, expected_nanos.abs());
+
+        // Subtracting in the opposite order only changes the sign.
+        assert_eq!((rhs - lhs).total_nanos(), -expected_nanos);
+    }
+
+    #[test]
+    fn timestamp_sub_second_boundary_binary_rollover() {
+        let before = Timestamp::new(false, 1, Subseconds::MAX);
+        let after = Timestamp::new(false, 2, subs(0));
+        let after_one = Timestamp::new(false, 2, subs(1));
+
+        // One subsecond increment is ~0.466 ns.
+        assert_sub_consistent(after, before, 0);
+        assert_sub_consistent(after_one, before, 1);
+        assert_sub_consistent(after_one, Timestamp::new(false, 1, subs(1)), 1_000_000_000);
+
+        // Straddling zero.
+        let before_neg = Timestamp::new(true, 0, Subseconds::MAX);
+        let after_pos = Timestamp::new(false, 0, Subseconds::MAX);
+        assert_sub_consistent(after_pos, before_neg, 2_000_000_000);
+        assert_eq!(before_neg.normalized(), (true, 1, 0));
+    }
+
+    #[test]
+    fn timestamp_sub_second_boundary_digital_rollover() {
+        let nanos = |nanos: u32| Subseconds::new_from_nanos(nanos).unwrap();
+
+        let before = Timestamp::new(false, 1, nanos(999_999_999));
+        let after = Timestamp::new(false, 2, nanos(1));
+
+        assert_sub_consistent(after, before, 2);
+        assert_sub_consistent(after, Timestamp::new(false, 1, nanos(1)), 1_000_000_000);
+
+        // Straddling zero.
+        let before_neg = Timestamp::new(true, 0, nanos(999_999_999));
+        let after_pos = Timestamp::new(false, 0, nanos(1));
+        assert_sub_consistent(after_pos, before_neg, 1_000_000_000);
+        assert_eq!(before_neg.normalized(), (true, 0, 999_999_999));
+    }
 }
@@ -3,14 +3,25 @@
 //! This module contains the unsafe traits that determine
 //! which pins can have a specific function, and provides
 //! functionality for setting up clocks and the MAC peripheral
+//!
+//! The pins for the RMII interface are described by [`EthPins`], and
+//! the pins for the MII interface by [`MiiPins`]. Both only accept pins
+//! that can have the respective function on the selected part, and implement
+//! [`EthernetPins`], which selects the matching [`Interface`]. All supported
+//! families (STM32F107, STM32F4 and STM32F7) support both interfaces.
+//!
+//! Only the pin options that are available in all packages of a family are
+//! supported. On the STM32F107, the remapped pins on port D can only be used
+//! if the Ethernet pins are remapped in `AFIO_MAPR`, which must be done manually.
 
 #[cfg(feature = "stm32f4xx-hal")]
 use stm32f4xx_hal::{
     bb,
     gpio::{
         gpioa::{PA1, PA7},
-        gpiob::{PB11, PB12, PB13},
-        gpioc::{PC4, PC5},
+        gpiob::{PB0, PB1, PB11, PB12, PB13, PB8},
+        gpioc::{PC2, PC3, PC4, PC5},
+        gpioe::PE2,
         gpiog::{PG11, PG13, PG14},
         Input,
         Speed::VeryHigh,
@@ -25,8 +36,9 @@ use cortex_m::interrupt;
 use stm32f7xx_hal::{
     gpio::{
         gpioa::{PA1, PA7},
-        gpiob::{PB11, PB12, PB13},
-        gpioc::{PC4, PC5},
+        gpiob::{PB0, PB1, PB11, PB12, PB13, PB8},
+        gpioc::{PC2, PC3, PC4, PC5},
+        gpioe::PE2,
         gpiog::{PG11, PG13, PG14},
         Input,
         Speed::VeryHigh,
@@ -35,15 +47,51 @@ use stm32f7xx_hal::{
 };
 
 use crate::{
-    dma::EthernetDMA,
+    dma::{EthernetDMA, RxRingEntry, TxRingEntry},
+    mac::{EthernetMAC, EthernetMACWithMii, MdcPin, MdioPin},
     stm32::{ETHERNET_DMA, ETHERNET_MAC, ETHERNET_MMC},
 };
 
 #[cfg(feature = "ptp")]
 use crate::{ptp::EthernetPTP, stm32::ETHERNET_PTP};
 
-// Enable syscfg and ethernet clocks. Reset the Ethernet MAC.
-pub(crate) fn setup() {
+/// The interface between the MAC and the PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interface {
+    /// Reduced Media Independent Interface.
+    ///
+    /// Requires a 50 MHz reference clock on `REF_CLK`, which is provided
+    /// by the PHY or by an external oscillator. It may also be provided by
+    /// the MCO output of the MCU, which is usually fed back to `REF_CLK`.
+    Rmii,
+    /// Media Independent Interface.
+    ///
+    /// Requires a 25 MHz (100 Mbit/s) or 2.5 MHz (10 Mbit/s) clock on both
+    /// `TX_CLK` and `RX_CLK`, which are provided by the PHY. The PHY itself
+    /// usually requires a 25 MHz crystal, or a 25 MHz clock provided by the MCO
+    /// output of the MCU.
+    Mii,
+}
+
+/// A set of pins that connects the MAC to a PHY.
+///
+/// The interface mode of the MAC is selected according to [`EthernetPins::INTERFACE`]
+/// by [`new`](crate::new) and [`new_with_mii`](crate::new_with_mii). If the
+/// interface does not match the wiring of the board, the PHY can still be accessed
+/// over the SMI, but no frames are transmitted or received.
+pub trait EthernetPins {
+    /// The interface that these pins are used for.
+    const INTERFACE: Interface;
+
+    /// Configure the pins for use by the ethernet peripheral.
+    fn setup_pins(self);
+}
+
+// Enable syscfg and ethernet clocks. Select the interface. Reset the Ethernet MAC.
+pub(crate) fn setup(interface: Interface) {
+    let rmii = interface == Interface::Rmii;
+
     #[cfg(feature = "stm32f4xx-hal")]
     unsafe {
         const SYSCFG_BIT: u8 = 14;
@@ -65,7 +113,11 @@ pub(crate) fn setup() {
         }
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        bb::set(&syscfg.pmc, MII_RMII_BIT);
+        if rmii {
+            bb::set(&syscfg.pmc, MII_RMII_BIT);
+        } else {
+            bb::clear(&syscfg.pmc, MII_RMII_BIT);
+        }
 
         // enable ethernet clocks
         bb::set(&rcc.ahb1enr, ETH_MAC_BIT);
@@ -92,7 +144,7 @@ pub(crate) fn setup() {
 
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        syscfg.pmc.modify(|_, w| w.mii_rmii_sel().set_bit());
+        syscfg.pmc.modify(|_, w| w.mii_rmii_sel().bit(rmii));
 
         // enable ethernet clocks
         rcc.ahb1enr.modify(|_, w| {
@@ -124,7 +176,7 @@ pub(crate) fn setup() {
 
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        afio.mapr.modify(|_, w| w.mii_rmii_sel().set_bit());
+        afio.mapr.modify(|_, w| w.mii_rmii_sel().bit(rmii));
 
         // enable ethernet clocks
         rcc.ahbenr.modify(|_, w| {
@@ -160,15 +212,16 @@ pub(crate) fn setup() {
 }
 
 macro_rules ! pin_trait {
-    ($([$name:ident, $doc:literal, $rm_name:literal]),*) => {
+    ($prefix:literal: $([$name:ident, $doc:literal, $rm_name:literal]),*) => {
         $(
-        #[doc = concat!($doc, "\n# Safety\nOnly pins specified as `ETH_RMII_", $rm_name, "` in a part's Reference Manual\nmay implement this trait.")]
+        #[doc = concat!($doc, "\n# Safety\nOnly pins specified as `ETH_", $prefix, "_", $rm_name, "` in a part's Reference Manual\nmay implement this trait.")]
         pub unsafe trait $name {}
         )*
     }
 }
 
 pin_trait!(
+    "RMII":
     [RmiiRefClk, "RMII Reference Clock", "REF_CLK"],
     [RmiiCrsDv, "RMII Rx Data Valid", "CRS_DV"],
     [RmiiTxEN, "RMII TX Enable", "TX_EN"],
@@ -178,6 +231,22 @@ pin_trait!(
     [RmiiRxD1, "RMII RX Data Pin 1", "RXD1"]
 );
 
+pin_trait!(
+    "MII":
+    [MiiTxClk, "MII TX Clock", "TX_CLK"],
+    [MiiTxEN, "MII TX Enable", "TX_EN"],
+    [MiiTxD0, "MII TX Data Pin 0", "TXD0"],
+    [MiiTxD1, "MII TX Data Pin 1", "TXD1"],
+    [MiiTxD2, "MII TX Data Pin 2", "TXD2"],
+    [MiiTxD3, "MII TX Data Pin 3", "TXD3"],
+    [MiiRxClk, "MII RX Clock", "RX_CLK"],
+    [MiiRxDv, "MII RX Data Valid", "RX_DV"],
+    [MiiRxD0, "MII RX Data Pin 0", "RXD0"],
+    [MiiRxD1, "MII RX Data Pin 1", "RXD1"],
+    [MiiRxD2, "MII RX Data Pin 2", "RXD2"],
+    [MiiRxD3, "MII RX Data Pin 3", "RXD3"]
+);
+
 /// Trait needed to setup the pins for the Ethernet peripheral.
 pub trait AlternateVeryHighSpeed {
     /// Puts the pin in the Alternate Function 11 with Very High Speed.
@@ -245,6 +314,71 @@ impl<'rx, 'tx, T> Parts<'rx, 'tx, T> {
     }
 }
 
+impl<'rx, 'tx> Parts<'rx, 'tx, EthernetMAC> {
+    /// Shut down the ethernet peripheral, and release the peripherals and
+    /// ring entries that were passed to [`new`](crate::new).
+    ///
+    /// The DMA is stopped first, and this function waits until it no longer
+    /// accesses the ring entries. All buffers acquired from a
+    /// [`BufferProvider`](crate::dma::BufferProvider) are released to it.
+    /// Afterwards, the transmitter and receiver of the MAC are disabled.
+    ///
+    /// The returned values can be passed to [`new`](crate::new) or
+    /// [`new_with_mii`](crate::new_with_mii) again, which resets and
+    /// configures the peripheral from scratch.
+    // NOTE(allow): the peripherals only have to be converted on F4 parts.
+    #[allow(clippy::useless_conversion)]
+    pub fn release(self) -> (PartsIn, &'rx mut [RxRingEntry], &'tx mut [TxRingEntry]) {
+        let (dma, rx_buffer, tx_buffer) = self.dma.release();
+        let (mac, mmc) = self.mac.release();
+
+        let parts = PartsIn {
+            mac: mac.into(),
+            mmc,
+            dma: dma.into(),
+            #[cfg(feature = "ptp")]
+            ptp: self.ptp.release().into(),
+        };
+
+        (parts, rx_buffer, tx_buffer)
+    }
+}
+
+impl<'rx, 'tx, MDIO, MDC> Parts<'rx, 'tx, EthernetMACWithMii<MDIO, MDC>>
+where
+    MDIO: MdioPin,
+    MDC: MdcPin,
+{
+    /// Shut down the ethernet peripheral, and release the peripherals, ring
+    /// entries, and MDIO and MDC pins that were passed to
+    /// [`new_with_mii`](crate::new_with_mii).
+    ///
+    /// See [`Parts::release`](Parts#method.release).
+    #[allow(clippy::type_complexity)]
+    pub fn release(
+        self,
+    ) -> (
+        PartsIn,
+        &'rx mut [RxRingEntry],
+        &'tx mut [TxRingEntry],
+        MDIO,
+        MDC,
+    ) {
+        let (mac, mdio, mdc) = self.mac.release_pins();
+
+        let parts = Parts {
+            mac,
+            dma: self.dma,
+            #[cfg(feature = "ptp")]
+            ptp: self.ptp,
+        };
+
+        let (parts, rx_buffer, tx_buffer) = parts.release();
+
+        (parts, rx_buffer, tx_buffer, mdio, mdc)
+    }
+}
+
 /// A struct that represents a combination of pins to be used
 /// as RMII pins for the ethernet peripheral(s)
 // NOTE(missing_docs): all fields of this struct are self-explanatory
@@ -288,6 +422,124 @@ where
     }
 }
 
+impl<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1> EthernetPins
+    for EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>
+where
+    REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
+    CRS: RmiiCrsDv + AlternateVeryHighSpeed,
+    TXEN: RmiiTxEN + AlternateVeryHighSpeed,
+    TXD0: RmiiTxD0 + AlternateVeryHighSpeed,
+    TXD1: RmiiTxD1 + AlternateVeryHighSpeed,
+    RXD0: RmiiRxD0 + AlternateVeryHighSpeed,
+    RXD1: RmiiRxD1 + AlternateVeryHighSpeed,
+{
+    const INTERFACE: Interface = Interface::Rmii;
+
+    fn setup_pins(self) {
+        EthPins::setup_pins(self)
+    }
+}
+
+/// A struct that represents a combination of pins to be used
+/// as MII pins for the ethernet peripheral(s)
+///
+/// The MII carrier sense (`CRS`) and collision detect (`COL`) signals
+/// are only required in half-duplex mode, and the receive error (`RX_ER`)
+/// signal is optional, so they are not part of this struct. If they are
+/// required, they must be configured manually.
+// NOTE(missing_docs): all fields of this struct are self-explanatory
+#[allow(missing_docs)]
+pub struct MiiPins<TXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXCLK, RXDV, RXD0, RXD1, RXD2, RXD3> {
+    pub tx_clk: TXCLK,
+    pub tx_en: TXEN,
+    pub tx_d0: TXD0,
+    pub tx_d1: TXD1,
+    pub tx_d2: TXD2,
+    pub tx_d3: TXD3,
+    pub rx_clk: RXCLK,
+    pub rx_dv: RXDV,
+    pub rx_d0: RXD0,
+    pub rx_d1: RXD1,
+    pub rx_d2: RXD2,
+    pub rx_d3: RXD3,
+}
+
+impl<TXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXCLK, RXDV, RXD0, RXD1, RXD2, RXD3>
+    MiiPins<TXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXCLK, RXDV, RXD0, RXD1, RXD2, RXD3>
+where
+    TXCLK: MiiTxClk + AlternateVeryHighSpeed,
+    TXEN: MiiTxEN + AlternateVeryHighSpeed,
+    TXD0: MiiTxD0 + AlternateVeryHighSpeed,
+    TXD1: MiiTxD1 + AlternateVeryHighSpeed,
+    TXD2: MiiTxD2 + AlternateVeryHighSpeed,
+    TXD3: MiiTxD3 + AlternateVeryHighSpeed,
+    RXCLK: MiiRxClk + AlternateVeryHighSpeed,
+    RXDV: MiiRxDv + AlternateVeryHighSpeed,
+    RXD0: MiiRxD0 + AlternateVeryHighSpeed,
+    RXD1: MiiRxD1 + AlternateVeryHighSpeed,
+    RXD2: MiiRxD2 + AlternateVeryHighSpeed,
+    RXD3: MiiRxD3 + AlternateVeryHighSpeed,
+{
+    /// Pin setup.
+    ///
+    /// Set MII pins to
+    /// * Alternate function 11
+    /// * High-speed
+    ///
+    /// This function consumes the pins so that you cannot use them
+    /// anywhere else by accident.
+    pub fn setup_pins(self) {
+        self.tx_clk.into_af11_very_high_speed();
+        self.tx_en.into_af11_very_high_speed();
+        self.tx_d0.into_af11_very_high_speed();
+        self.tx_d1.into_af11_very_high_speed();
+        self.tx_d2.into_af11_very_high_speed();
+        self.tx_d3.into_af11_very_high_speed();
+        self.rx_clk.into_af11_very_high_speed();
+        self.rx_dv.into_af11_very_high_speed();
+        self.rx_d0.into_af11_very_high_speed();
+        self.rx_d1.into_af11_very_high_speed();
+        self.rx_d2.into_af11_very_high_speed();
+        self.rx_d3.into_af11_very_high_speed();
+    }
+}
+
+impl<TXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXCLK, RXDV, RXD0, RXD1, RXD2, RXD3> EthernetPins
+    for MiiPins<TXCLK, TXEN, TXD0, TXD1, TXD2, TXD3, RXCLK, RXDV, RXD0, RXD1, RXD2, RXD3>
+where
+    TXCLK: MiiTxClk + AlternateVeryHighSpeed,
+    TXEN: MiiTxEN + AlternateVeryHighSpeed,
+    TXD0: MiiTxD0 + AlternateVeryHighSpeed,
+    TXD1: MiiTxD1 + AlternateVeryHighSpeed,
+    TXD2: MiiTxD2 + AlternateVeryHighSpeed,
+    TXD3: MiiTxD3 + AlternateVeryHighSpeed,
+    RXCLK: MiiRxClk + AlternateVeryHighSpeed,
+    RXDV: MiiRxDv + AlternateVeryHighSpeed,
+    RXD0: MiiRxD0 + AlternateVeryHighSpeed,
+    RXD1: MiiRxD1 + AlternateVeryHighSpeed,
+    RXD2: MiiRxD2 + AlternateVeryHighSpeed,
+    RXD3: MiiRxD3 + AlternateVeryHighSpeed,
+{
+    const INTERFACE: Interface = Interface::Mii;
+
+    fn setup_pins(self) {
+        MiiPins::setup_pins(self)
+    }
+}
+
+/// Implement pin traits for pins that already implement [`AlternateVeryHighSpeed`]
+/// because they have the same function in RMII mode.
+#[allow(unused_macros)]
+macro_rules! impl_pin_traits {
+    ( $($traity:ident: [$($pin:ty,)+],)+ ) => {
+        $(
+            $(
+                unsafe impl $traity for $pin {}
+            )+
+        )+
+    };
+}
+
 #[allow(unused_macros)]
 macro_rules! impl_pins {
     ( $($traity:ident: [$($pin:ty,)+],)+ ) => {
@@ -333,6 +585,54 @@ impl_pins!(
     ],
 );
 
+#[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
+impl_pins!(
+    MiiTxClk: [
+        PC3<Input>,
+    ],
+    MiiTxD2: [
+        PC2<Input>,
+    ],
+    MiiTxD3: [
+        PB8<Input>,
+        PE2<Input>,
+    ],
+    MiiRxD2: [
+        PB0<Input>,
+    ],
+    MiiRxD3: [
+        PB1<Input>,
+    ],
+);
+
+#[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
+impl_pin_traits!(
+    MiiTxEN: [
+        PB11<Input>,
+        PG11<Input>,
+    ],
+    MiiTxD0: [
+        PB12<Input>,
+        PG13<Input>,
+    ],
+    MiiTxD1: [
+        PB13<Input>,
+        PG14<Input>,
+    ],
+    MiiRxClk: [
+        PA1<Input>,
+    ],
+    MiiRxDv: [
+        PA7<Input>,
+    ],
+    MiiRxD0: [
+        PC4<Input>,
+    ],
+    MiiRxD1: [
+        PC5<Input>,
+    ],
+);
+
 #[cfg(feature = "stm32f1xx-hal")]
 mod stm32f1 {
     use super::*;
@@ -361,6 +661,7 @@ mod stm32f1 {
                                 // will fail as `mem::transmute` can only convert between types of the same size.
                                 //
                                 // This guards us from unsound behaviour introduced by point releases of the f1 hal
+                                #[allow(clippy::missing_transmute_annotations)]
                                 let cr: &mut _ = &mut unsafe { core::mem::transmute(()) };
                                 // The speed can only be changed on output pins
                                 let mut pin = self.into_alternate_push_pull(cr);
@@ -386,4 +687,22 @@ mod stm32f1 {
         RmiiRxD0: [(PC4<Input<Floating>>, true), (PD9<Input<Floating>>, true)],
         RmiiRxD1: [(PC5<Input<Floating>>, true), (PD10<Input<Floating>>, true)]
     );
+
+    impl_pins!(
+        MiiTxClk: [(PC3<Input<Floating>>, true)],
+        MiiTxD2: [(PC2<Alternate<PushPull>>, false)],
+        MiiTxD3: [(PB8<Alternate<PushPull>>, false)],
+        MiiRxD2: [(PB0<Input<Floating>>, true), (PD11<Input<Floating>>, true)],
+        MiiRxD3: [(PB1<Input<Floating>>, true), (PD12<Input<Floating>>, true)]
+    );
+
+    impl_pin_traits!(
+        MiiTxEN: [PB11<Alternate<PushPull>>,],
+        MiiTxD0: [PB12<Alternate<PushPull>>,],
+        MiiTxD1: [PB13<Alternate<PushPull>>,],
+        MiiRxClk: [PA1<Input<Floating>>,],
+        MiiRxDv: [PA7<Input<Floating>>, PD8<Input<Floating>>,],
+        MiiRxD0: [PC4<Input<Floating>>, PD9<Input<Floating>>,],
+        MiiRxD1: [PC5<Input<Floating>>, PD10<Input<Floating>>,],
+    );
 }
@@ -0,0 +1,148 @@
+//! Helpers for assembling Ethernet frames in TX buffers.
+
+/// The length of an Ethernet header without a VLAN tag.
+pub const ETHERNET_HEADER_LEN: usize = 14;
+
+/// A view of an Ethernet frame stored in a buffer, such as the one
+/// provided to the closure passed to [`EthernetDMA::send`](crate::dma::EthernetDMA::send).
+///
+/// The header is read from and written to the buffer in place.
+///
+/// ```rust
+/// use stm32_eth::util::EthernetFrame;
+///
+/// let mut buffer = [0u8; 60];
+///
+/// let mut frame = EthernetFrame::new(&mut buffer).unwrap();
+/// frame
+///     .set_destination([0xFF; 6])
+///     .set_source([0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF])
+///     .set_ethertype(0x0806);
+/// frame.payload_mut()[..2].copy_from_slice(&[0x00, 0x01]);
+///
+/// assert_eq!(frame.ethertype(), 0x0806);
+/// assert_eq!(&buffer[12..16], &[0x08, 0x06, 0x00, 0x01]);
+/// ```
+pub struct EthernetFrame<'a> {
+    buffer: &'a mut [u8],
+}
+
+impl<'a> EthernetFrame<'a> {
+    /// Create a view of the frame stored in `buffer`.
+    ///
+    /// Returns `None` if `buffer` is too short to hold an Ethernet header.
+    pub fn new(buffer: &'a mut [u8]) -> Option<Self> {
+        if buffer.len() < ETHERNET_HEADER_LEN {
+            None
+        } else {
+            Some(Self { buffer })
+        }
+    }
+
+    /// Get the destination MAC address.
+    pub fn destination(&self) -> [u8; 6] {
+        self.address(0)
+    }
+
+    /// Set the destination MAC address.
+    pub fn set_destination(&mut self, address: [u8; 6]) -> &mut Self {
+        self.buffer[0..6].copy_from_slice(&address);
+        self
+    }
+
+    /// Get the source MAC address.
+    pub fn source(&self) -> [u8; 6] {
+        self.address(6)
+    }
+
+    /// Set the source MAC address.
+    pub fn set_source(&mut self, address: [u8; 6]) -> &mut Self {
+        self.buffer[6..12].copy_from_slice(&address);
+        self
+    }
+
+    /// Get the EtherType (or length) field.
+    pub fn ethertype(&self) -> u16 {
+        u16::from_be_bytes([self.buffer[12], self.buffer[13]])
+    }
+
+    /// Set the EtherType (or length) field.
+    pub fn set_ethertype(&mut self, ethertype: u16) -> &mut Self {
+        self.buffer[12..14].copy_from_slice(&ethertype.to_be_bytes());
+        self
+    }
+
+    /// Get the payload of the frame, which follows the header.
+    pub fn payload(&self) -> &[u8] {
+        &self.buffer[ETHERNET_HEADER_LEN..]
+    }
+
+    /// Get the payload of the frame mutably.
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer[ETHERNET_HEADER_LEN..]
+    }
+
+    /// Consume this view, and return the payload of the frame.
+    pub fn into_payload(self) -> &'a mut [u8] {
+        &mut self.buffer[ETHERNET_HEADER_LEN..]
+    }
+
+    fn address(&self, offset: usize) -> [u8; 6] {
+        let mut address = [0; 6];
+        address.copy_from_slice(&self.buffer[offset..offset + 6]);
+        address
+    }
+}
+
+/// A VLAN tag, as inserted by
+/// [`EthernetDMA::send_with_vlans`](crate::dma::EthernetDMA::send_with_vlans).
+///
+/// ```rust
+/// use stm32_eth::util::VlanTag;
+///
+/// let tag = VlanTag::service(0x0064);
+/// assert_eq!(tag.to_bytes(), [0x88, 0xA8, 0x00, 0x64]);
+/// ```
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VlanTag {
+    /// The tag protocol identifier, which takes the place of the EtherType.
+    pub tpid: u16,
+    /// The tag control information: the priority code point (3 bits), the
+    /// drop eligible indicator (1 bit) and the VLAN identifier (12 bits).
+    pub tci: u16,
+}
+
+impl VlanTag {
+    /// The length of a VLAN tag.
+    pub const LEN: usize = 4;
+
+    /// The tag protocol identifier of customer VLAN tags (C-tags, IEEE 802.1Q).
+    pub const CUSTOMER_TPID: u16 = 0x8100;
+
+    /// The tag protocol identifier of service VLAN tags (S-tags, IEEE 802.1ad).
+    pub const SERVICE_TPID: u16 = 0x88A8;
+
+    /// Create a customer VLAN tag (C-tag) with `tci`.
+    pub const fn customer(tci: u16) -> Self {
+        Self {
+            tpid: Self::CUSTOMER_TPID,
+            tci,
+        }
+    }
+
+    /// Create a service VLAN tag (S-tag) with `tci`.
+    pub const fn service(tci: u16) -> Self {
+        Self {
+            tpid: Self::SERVICE_TPID,
+            tci,
+        }
+    }
+
+    /// The tag as it is written into a frame.
+    pub const fn to_bytes(&self) -> [u8; 4] {
+        let tpid = self.tpid.to_be_bytes();
+        let tci = self.tci.to_be_bytes();
+        [tpid[0], tpid[1], tci[0], tci[1]]
+    }
+}
@@ -0,0 +1,78 @@
+//! Bounded busy-waiting for the Ethernet peripheral.
+//!
+//! All waits for hardware status bits (such as the reset and stop of the DMA,
+//! the busy bit of the MII and the update bits of the PTP clock) go through
+//! [`wait_for`], so that a hardware fault (e.g. a missing reference clock)
+//! results in a timeout instead of a silent endless loop.
+
+/// The amount of iterations after which a wait that still completes is
+/// reported as slow.
+const SLOW_ITERATIONS: u32 = 10_000;
+
+/// A wait did not complete within its maximum amount of iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Timeout;
+
+/// Poll `done` until it returns `true`, at most `max_iterations` times.
+///
+/// If the `defmt` feature is enabled, a warning naming `what` is emitted if
+/// the wait takes more than [`SLOW_ITERATIONS`] iterations, and if it times
+/// out.
+pub(crate) fn wait_for(
+    what: &'static str,
+    max_iterations: u32,
+    mut done: impl FnMut() -> bool,
+) -> Result<(), Timeout> {
+    #[cfg(not(feature = "defmt"))]
+    let _ = what;
+
+    for iteration in 0..max_iterations {
+        if done() {
+            #[cfg(feature = "defmt")]
+            if iteration >= SLOW_ITERATIONS {
+                defmt::warn!("Waiting for {=str} took {=u32} iterations", what, iteration);
+            }
+            #[cfg(not(feature = "defmt"))]
+            let _ = (iteration, SLOW_ITERATIONS);
+
+            return Ok(());
+        }
+    }
+
+    #[cfg(feature = "defmt")]
+    defmt::warn!(
+        "Waiting for {=str} timed out after {=u32} iterations",
+        what,
+        max_iterations
+    );
+
+    Err(Timeout)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        let mut polls = 0;
+        assert_eq!(
+            wait_for("test", 10, || {
+                polls += 1;
+                polls == 3
+            }),
+            Ok(())
+        );
+        assert_eq!(polls, 3);
+
+        polls = 0;
+        assert_eq!(
+            wait_for("test", 10, || {
+                polls += 1;
+                false
+            }),
+            Err(Timeout)
+        );
+        assert_eq!(polls, 10);
+    }
+}